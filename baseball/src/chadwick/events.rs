@@ -1,9 +1,16 @@
+use std::io;
+
+use csv::{ReaderBuilder, StringRecord};
 use serde_derive::Deserialize;
 
 use crate::chadwick::bool_from_string;
 use crate::chadwick::parse_handedness;
 
 
+/// Number of standard fields output by cwevent.
+pub const FULL_EVENT_FIELDS: usize = 97;
+
+
 #[derive(Deserialize, Debug)]
 pub struct FullEventRow<'a> {
     // 0
@@ -187,8 +194,9 @@ pub struct FullEventRow<'a> {
 }
 
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum Handedness {
+    #[default]
     Unknown,
     Both,
     Left,
@@ -196,7 +204,7 @@ pub enum Handedness {
 }
 
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Default, Deserialize, Debug, PartialEq)]
 pub struct FullEvent {
     // Event 0
     pub game_id: String,
@@ -338,7 +346,7 @@ pub struct FullEvent {
 }
 
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Default, Deserialize, Debug, PartialEq)]
 pub struct Event {
     // Event 0
     pub game_id: String,
@@ -480,7 +488,7 @@ pub struct Event {
 }
 
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Default, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
 pub struct ExtendedEvent {
     // Event 0
@@ -569,3 +577,64 @@ pub struct ExtendedEvent {
     pub uncertain_play_exc_fl: bool,
     pub count_tx: String,
 }
+
+
+impl Handedness {
+    /// Convert a Retrosheet or Chadwick hand code. Anything unrecognized is unknown.
+    pub fn from_code(code: &str) -> Self {
+        match code {
+            "B" => Handedness::Both,
+            "L" => Handedness::Left,
+            "R" => Handedness::Right,
+            _ => Handedness::Unknown,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Handedness::Unknown => "?",
+            Handedness::Both => "B",
+            Handedness::Left => "L",
+            Handedness::Right => "R",
+        }
+    }
+}
+
+
+/// Read the output of `cwevent -f 0-96 -x 0-63`, splitting each row into the standard and
+/// extended fields.
+pub fn read_full_extended_events<T: io::Read>(file: T) -> Result<Vec<(FullEvent, ExtendedEvent)>, csv::Error> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(file);
+
+    let mut events = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let full: StringRecord = record.iter().take(FULL_EVENT_FIELDS).collect();
+        let extended: StringRecord = record.iter().skip(FULL_EVENT_FIELDS).collect();
+        events.push((full.deserialize(None)?, extended.deserialize(None)?));
+    }
+    Ok(events)
+}
+
+
+/// A batter event with nobody on base for unit tests. The batter is "bat" followed by the event
+/// id and the pitcher is "pit01"; an empty `bat_dest` makes it a non-batter event.
+#[cfg(test)]
+pub(crate) fn test_event(game_id: &str, event_id: &str, inn_ct: u8, home: bool, outs: u8, bat_dest: &str, event_outs: u8) -> FullEvent {
+    FullEvent {
+        game_id: game_id.to_string(),
+        event_id: event_id.to_string(),
+        inn_ct,
+        bat_home_id: if home { "1" } else { "0" }.to_string(),
+        outs_ct: outs,
+        bat_id: format!("bat{}", event_id),
+        resp_bat_id: format!("bat{}", event_id),
+        resp_pit_id: "pit01".to_string(),
+        bat_event_fl: !bat_dest.is_empty(),
+        bat_dest_id: bat_dest.to_string(),
+        event_outs_ct: event_outs,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,202 @@
+//! Leverage index of each game state and of every plate appearance.
+//!
+//! The leverage of a state is how much the win probability tends to swing on a plate appearance
+//! starting from it, relative to the average plate appearance. Swings come from the WPA of the
+//! plate appearances in the events, so an index of 2.0 means twice the usual swing. Rare states
+//! are smoothed toward the same inning and score with the bases and outs pooled.
+
+use std::collections::HashMap;
+
+use crate::chadwick::events::{ExtendedEvent, FullEvent};
+use crate::chadwick::wpa::{GameState, WinExpectancy};
+
+
+/// Plate appearances below this index are low leverage.
+pub const LOW_LEVERAGE: f32 = 0.85;
+/// Plate appearances above this index are high leverage.
+pub const HIGH_LEVERAGE: f32 = 2.0;
+/// Number of plate appearances of the broader state blended into each state's own swings.
+pub const SMOOTHING_PLATE_APPEARANCES: f64 = 20.0;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LeverageLevel {
+    Low,
+    Medium,
+    High,
+}
+
+
+/// Total win probability swing of the plate appearances starting from a state.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Swing {
+    pub total: f64,
+    pub times: u32,
+}
+
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LeverageIndex {
+    states: HashMap<GameState, Swing>,
+    /// States pooled over the bases and outs.
+    situations: HashMap<(u8, bool, i8), Swing>,
+    overall: Swing,
+}
+
+
+/// Leverage of a plate appearance, taken from the state when it started.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlateAppearanceLeverage<'a> {
+    /// The event ending the plate appearance.
+    pub event: &'a FullEvent,
+    pub li: f32,
+    pub level: LeverageLevel,
+}
+
+
+impl LeverageLevel {
+    pub fn from_index(li: f32) -> Self {
+        if li < LOW_LEVERAGE {
+            Self::Low
+        }
+        else if li > HIGH_LEVERAGE {
+            Self::High
+        }
+        else {
+            Self::Medium
+        }
+    }
+}
+
+
+impl Swing {
+    fn add(&mut self, wpa: f32) {
+        self.total += wpa.abs() as f64;
+        self.times += 1;
+    }
+
+    fn mean(&self) -> Option<f64> {
+        if self.times > 0 {
+            Some(self.total / self.times as f64)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Blend the average swing with a prior worth `SMOOTHING_PLATE_APPEARANCES` plate appearances.
+    fn smoothed(swing: Option<&Swing>, prior: f64) -> f64 {
+        let (total, times) = swing.map_or((0.0, 0.0), |swing| (swing.total, swing.times as f64));
+        (total + SMOOTHING_PLATE_APPEARANCES * prior) / (times + SMOOTHING_PLATE_APPEARANCES)
+    }
+}
+
+
+impl LeverageIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the index from the plate appearances in events, which must be in game order, using
+    /// the win expectancy model to value them.
+    pub fn from_events(we: &WinExpectancy, events: &[FullEvent]) -> Self {
+        let mut li = Self::new();
+        for (event, value) in events.iter().zip(we.event_values(events)) {
+            if event.bat_event_fl {
+                let state = GameState::from_event(event);
+                li.states.entry(state).or_default().add(value.wpa);
+                li.situations.entry(state.situation()).or_default().add(value.wpa);
+                li.overall.add(value.wpa);
+            }
+        }
+        li
+    }
+
+    pub fn swing(&self, state: &GameState) -> Option<&Swing> {
+        self.states.get(state)
+    }
+
+    /// Leverage index of a state. An average state is 1.0, as is everything before any plate
+    /// appearances were added.
+    pub fn index(&self, state: &GameState) -> f32 {
+        let Some(average) = self.overall.mean().filter(|average| *average > 0.0)
+        else {
+            return 1.0;
+        };
+        let situation = Swing::smoothed(self.situations.get(&state.situation()), average);
+        (Swing::smoothed(self.states.get(state), situation) / average) as f32
+    }
+
+    /// Leverage of every plate appearance in events, which must be in game order. The state
+    /// comes from the event starting the plate appearance, so a stolen base during it doesn't
+    /// change its leverage.
+    pub fn plate_appearances<'a>(&self, events: &'a [(FullEvent, ExtendedEvent)]) -> Vec<PlateAppearanceLeverage<'a>> {
+        let mut plate_appearances = Vec::new();
+        let mut start = None;
+        for (event, extended) in events {
+            if extended.pa_new_fl || start.is_none() {
+                start = Some(GameState::from_event(event));
+            }
+            if event.bat_event_fl && let Some(state) = start.take() {
+                let li = self.index(&state);
+                plate_appearances.push(PlateAppearanceLeverage {
+                    event,
+                    li,
+                    level: LeverageLevel::from_index(li),
+                });
+            }
+        }
+        plate_appearances
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chadwick::events::test_event;
+
+    fn event(game_id: &str, home: bool, outs: u8, score: (u8, u8), dest: &str, event_outs: u8) -> (FullEvent, ExtendedEvent) {
+        let full = FullEvent {
+            away_score_ct: score.0,
+            home_score_ct: score.1,
+            ..test_event(game_id, &outs.to_string(), 9, home, outs, dest, event_outs)
+        };
+        let extended = ExtendedEvent {
+            pa_new_fl: true,
+            ..Default::default()
+        };
+        (full, extended)
+    }
+
+    #[test]
+    fn leverage_levels() {
+        assert_eq!(LeverageLevel::from_index(0.5), LeverageLevel::Low);
+        assert_eq!(LeverageLevel::from_index(1.0), LeverageLevel::Medium);
+        assert_eq!(LeverageLevel::from_index(2.5), LeverageLevel::High);
+        assert_eq!(LeverageIndex::new().index(&GameState::new(1, false, 0, 0, 0)), 1.0);
+    }
+
+    #[test]
+    fn plate_appearances() {
+        // A tie game won with a walk-off homer and a blowout finishing with three quick outs.
+        let events = vec![
+            event("TST202404010", true, 0, (0, 0), "4", 0),
+            event("TST202404020", false, 0, (9, 0), "0", 1),
+            event("TST202404020", false, 1, (9, 0), "0", 1),
+            event("TST202404020", false, 2, (9, 0), "0", 1),
+        ];
+        let full: Vec<FullEvent> = events.iter().map(|(full, _)| full.clone()).collect();
+        let we = WinExpectancy::from_events(&full);
+        let li = LeverageIndex::from_events(&we, &full);
+        let tied = GameState::new(9, true, 0, 0, 0);
+        assert_eq!(li.swing(&tied).map(|swing| swing.times), Some(1));
+        assert!(li.index(&tied) > li.index(&GameState::new(9, false, 0, 0, -9)));
+
+        let plate_appearances = li.plate_appearances(&events);
+        assert_eq!(plate_appearances.len(), 4);
+        assert_eq!(plate_appearances[0].event.game_id, "TST202404010");
+        assert!(plate_appearances[0].li > 1.0);
+        assert!(plate_appearances[1].li < 1.0);
+    }
+}
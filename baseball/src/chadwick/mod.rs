@@ -8,6 +8,12 @@ use serde::de::{self, Deserialize, Deserializer, Unexpected};
 pub mod events;
 pub mod games;
 pub mod gamelogs;
+pub mod leverage;
+pub mod pitches;
+pub mod play;
+pub mod re24;
+pub mod state;
+pub mod wpa;
 
 pub use events::Event;
 pub use events::ExtendedEvent;
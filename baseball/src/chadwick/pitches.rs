@@ -0,0 +1,421 @@
+//! Typed model of the Retrosheet pitch sequences found in play records and
+//! `FullEvent::pitch_seq_tx`.
+//!
+//! Besides the pitches themselves, a sequence records pickoff throws ("1", "+2"), pitches blocked
+//! by the catcher ("*"), runners going on the pitch (">") and plays not involving the batter ("."):
+//!
+//! ```text
+//! CB1*B>F.X
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::chadwick::play::Base;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PitchKind {
+    /// B
+    Ball,
+    /// I
+    IntentionalBall,
+    /// P
+    Pitchout,
+    /// H
+    HitBatter,
+    /// V, called when the pitcher goes to their mouth or for a pitch clock violation.
+    AutomaticBall,
+    /// C
+    CalledStrike,
+    /// S
+    SwingingStrike,
+    /// M
+    MissedBunt,
+    /// Q
+    SwingingPitchout,
+    /// F
+    Foul,
+    /// L
+    FoulBunt,
+    /// T
+    FoulTip,
+    /// O
+    FoulTipBunt,
+    /// R
+    FoulPitchout,
+    /// X
+    InPlay,
+    /// Y
+    InPlayPitchout,
+    /// K, a strike of unknown type.
+    Strike,
+    /// A, called for a pitch clock violation.
+    AutomaticStrike,
+    /// N, no pitch on balks and interference calls.
+    NoPitch,
+    /// U, unknown or missed pitch.
+    Unknown,
+}
+
+
+/// A throw to a base between pitches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PickoffAttempt {
+    pub base: Base,
+    /// Thrown by the catcher ("+1") rather than the pitcher ("1").
+    pub by_catcher: bool,
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pitch {
+    pub kind: PitchKind,
+    /// Balls in the count before the pitch.
+    pub balls: u8,
+    /// Strikes in the count before the pitch.
+    pub strikes: u8,
+    /// Pickoff throws made since the previous pitch.
+    pub pickoffs: Vec<PickoffAttempt>,
+    /// The pitch was blocked by the catcher.
+    pub blocked: bool,
+    /// A runner was going on the pitch.
+    pub runner_going: bool,
+    /// A play not involving the batter happened before the pitch.
+    pub after_play: bool,
+}
+
+
+/// Decoded pitch sequence of a plate appearance.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PitchSequence {
+    pitches: Vec<Pitch>,
+    /// Pickoff throws after the last pitch, usually when the event is the pickoff itself.
+    trailing_pickoffs: Vec<PickoffAttempt>,
+}
+
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PitchSequenceError {
+    pub text: String,
+    pub message: String,
+}
+
+
+impl fmt::Display for PitchSequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "can't parse pitch sequence \"{}\": {}", self.text, self.message)
+    }
+}
+
+
+impl Error for PitchSequenceError {}
+
+
+impl PitchKind {
+    pub fn from_code(code: char) -> Option<Self> {
+        let kind = match code {
+            'B' => PitchKind::Ball,
+            'I' => PitchKind::IntentionalBall,
+            'P' => PitchKind::Pitchout,
+            'H' => PitchKind::HitBatter,
+            'V' => PitchKind::AutomaticBall,
+            'C' => PitchKind::CalledStrike,
+            'S' => PitchKind::SwingingStrike,
+            'M' => PitchKind::MissedBunt,
+            'Q' => PitchKind::SwingingPitchout,
+            'F' => PitchKind::Foul,
+            'L' => PitchKind::FoulBunt,
+            'T' => PitchKind::FoulTip,
+            'O' => PitchKind::FoulTipBunt,
+            'R' => PitchKind::FoulPitchout,
+            'X' => PitchKind::InPlay,
+            'Y' => PitchKind::InPlayPitchout,
+            'K' => PitchKind::Strike,
+            'A' => PitchKind::AutomaticStrike,
+            'N' => PitchKind::NoPitch,
+            'U' => PitchKind::Unknown,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    pub fn code(&self) -> char {
+        match self {
+            PitchKind::Ball => 'B',
+            PitchKind::IntentionalBall => 'I',
+            PitchKind::Pitchout => 'P',
+            PitchKind::HitBatter => 'H',
+            PitchKind::AutomaticBall => 'V',
+            PitchKind::CalledStrike => 'C',
+            PitchKind::SwingingStrike => 'S',
+            PitchKind::MissedBunt => 'M',
+            PitchKind::SwingingPitchout => 'Q',
+            PitchKind::Foul => 'F',
+            PitchKind::FoulBunt => 'L',
+            PitchKind::FoulTip => 'T',
+            PitchKind::FoulTipBunt => 'O',
+            PitchKind::FoulPitchout => 'R',
+            PitchKind::InPlay => 'X',
+            PitchKind::InPlayPitchout => 'Y',
+            PitchKind::Strike => 'K',
+            PitchKind::AutomaticStrike => 'A',
+            PitchKind::NoPitch => 'N',
+            PitchKind::Unknown => 'U',
+        }
+    }
+
+    /// Counts as a ball, the same pitches as `pa_ball_ct`.
+    pub fn is_ball(&self) -> bool {
+        matches!(
+            self,
+            PitchKind::Ball | PitchKind::IntentionalBall | PitchKind::Pitchout | PitchKind::HitBatter |
+            PitchKind::AutomaticBall
+        )
+    }
+
+    /// Counts as a strike, the same pitches as `pa_strike_ct`. Balls put in play are strikes.
+    pub fn is_strike(&self) -> bool {
+        matches!(
+            self,
+            PitchKind::CalledStrike | PitchKind::SwingingStrike | PitchKind::MissedBunt |
+            PitchKind::SwingingPitchout | PitchKind::Foul | PitchKind::FoulBunt | PitchKind::FoulTip |
+            PitchKind::FoulTipBunt | PitchKind::FoulPitchout | PitchKind::InPlay | PitchKind::InPlayPitchout |
+            PitchKind::Strike | PitchKind::AutomaticStrike
+        )
+    }
+
+    pub fn is_swing(&self) -> bool {
+        matches!(
+            self,
+            PitchKind::SwingingStrike | PitchKind::MissedBunt | PitchKind::SwingingPitchout | PitchKind::Foul |
+            PitchKind::FoulBunt | PitchKind::FoulTip | PitchKind::FoulTipBunt | PitchKind::FoulPitchout |
+            PitchKind::InPlay | PitchKind::InPlayPitchout
+        )
+    }
+
+    pub fn is_foul(&self) -> bool {
+        matches!(self, PitchKind::Foul | PitchKind::FoulBunt | PitchKind::FoulPitchout)
+    }
+
+    pub fn is_in_play(&self) -> bool {
+        matches!(self, PitchKind::InPlay | PitchKind::InPlayPitchout)
+    }
+
+    /// Whether the pitch adds a ball to the count. Hitting the batter ends the plate appearance
+    /// without one.
+    fn adds_ball(&self) -> bool {
+        self.is_ball() && *self != PitchKind::HitBatter
+    }
+
+    /// Whether the pitch adds a strike to the count, which fouls don't with two strikes.
+    fn adds_strike(&self, strikes: u8) -> bool {
+        match self {
+            PitchKind::Foul | PitchKind::FoulPitchout => strikes < 2,
+            PitchKind::InPlay | PitchKind::InPlayPitchout => false,
+            kind => kind.is_strike(),
+        }
+    }
+}
+
+
+impl Pitch {
+    /// Count before the pitch as (balls, strikes).
+    pub fn count(&self) -> (u8, u8) {
+        (self.balls, self.strikes)
+    }
+}
+
+
+impl PitchSequence {
+    pub fn parse(text: &str) -> Result<Self, PitchSequenceError> {
+        parse_sequence(text).map_err(|message| PitchSequenceError {
+            text: text.to_string(),
+            message,
+        })
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Pitch> {
+        self.pitches.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pitches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pitches.is_empty()
+    }
+
+    pub fn first(&self) -> Option<&Pitch> {
+        self.pitches.first()
+    }
+
+    pub fn last(&self) -> Option<&Pitch> {
+        self.pitches.last()
+    }
+
+    /// Whether the first pitch was a strike, or None if no pitches were recorded.
+    pub fn first_pitch_strike(&self) -> Option<bool> {
+        self.first().map(|pitch| pitch.kind.is_strike())
+    }
+
+    /// Foul balls hit with two strikes.
+    pub fn two_strike_fouls(&self) -> usize {
+        self.pitches.iter()
+            .filter(|pitch| pitch.strikes == 2 && pitch.kind.is_foul())
+            .count()
+    }
+
+    /// Every pickoff throw in the sequence, including any after the last pitch.
+    pub fn pickoffs(&self) -> impl Iterator<Item = &PickoffAttempt> {
+        self.pitches.iter()
+            .flat_map(|pitch| pitch.pickoffs.iter())
+            .chain(self.trailing_pickoffs.iter())
+    }
+
+    /// Count after the last pitch, capped at three balls and two strikes, which is the count
+    /// Chadwick reports for the event.
+    pub fn final_count(&self) -> (u8, u8) {
+        match self.pitches.last() {
+            Some(pitch) => {
+                let balls = pitch.balls + u8::from(pitch.kind.adds_ball());
+                let strikes = pitch.strikes + u8::from(pitch.kind.adds_strike(pitch.strikes));
+                (balls.min(3), strikes.min(2))
+            }
+            None => (0, 0),
+        }
+    }
+}
+
+
+impl<'a> IntoIterator for &'a PitchSequence {
+    type Item = &'a Pitch;
+    type IntoIter = std::slice::Iter<'a, Pitch>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pitches.iter()
+    }
+}
+
+
+impl IntoIterator for PitchSequence {
+    type Item = Pitch;
+    type IntoIter = std::vec::IntoIter<Pitch>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pitches.into_iter()
+    }
+}
+
+
+impl FromStr for PitchSequence {
+    type Err = PitchSequenceError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        PitchSequence::parse(text)
+    }
+}
+
+
+fn parse_sequence(text: &str) -> Result<PitchSequence, String> {
+    let mut sequence = PitchSequence::default();
+    let (mut balls, mut strikes) = (0u8, 0u8);
+    let mut pickoffs = Vec::new();
+    let mut blocked = false;
+    let mut runner_going = false;
+    let mut after_play = false;
+    let mut by_catcher = false;
+
+    for (idx, code) in text.chars().enumerate() {
+        if by_catcher && !matches!(code, '1'..='3') {
+            return Err(format!("catcher pickoff without a base at position {}", idx));
+        }
+        match code {
+            '1' | '2' | '3' => {
+                let base = match code {
+                    '1' => Base::First,
+                    '2' => Base::Second,
+                    _ => Base::Third,
+                };
+                pickoffs.push(PickoffAttempt { base, by_catcher });
+                by_catcher = false;
+            }
+            '+' => by_catcher = true,
+            '*' => blocked = true,
+            '>' => runner_going = true,
+            '.' => after_play = true,
+            _ => {
+                let kind = PitchKind::from_code(code)
+                    .ok_or_else(|| format!("unknown pitch '{}' at position {}", code, idx))?;
+                sequence.pitches.push(Pitch {
+                    kind,
+                    balls: balls.min(3),
+                    strikes: strikes.min(2),
+                    pickoffs: std::mem::take(&mut pickoffs),
+                    blocked,
+                    runner_going,
+                    after_play,
+                });
+                if kind.adds_ball() {
+                    balls += 1;
+                }
+                else if kind.adds_strike(strikes) {
+                    strikes += 1;
+                }
+                blocked = false;
+                runner_going = false;
+                after_play = false;
+            }
+        }
+    }
+
+    if by_catcher {
+        return Err("catcher pickoff without a base at the end".to_string());
+    }
+    if blocked || runner_going {
+        return Err("marker without a following pitch".to_string());
+    }
+    sequence.trailing_pickoffs = pickoffs;
+    Ok(sequence)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts() {
+        let sequence = PitchSequence::parse("CBFFB*BX").unwrap();
+        let counts: Vec<(u8, u8)> = sequence.iter().map(Pitch::count).collect();
+        assert_eq!(counts, vec![(0, 0), (0, 1), (1, 1), (1, 2), (1, 2), (2, 2), (3, 2)]);
+        assert_eq!(sequence.first_pitch_strike(), Some(true));
+        assert_eq!(sequence.two_strike_fouls(), 1);
+        assert!(sequence.iter().nth(5).unwrap().blocked);
+        assert_eq!(sequence.final_count(), (3, 2));
+    }
+
+    #[test]
+    fn pickoffs() {
+        let sequence = PitchSequence::parse("B1>C.+2S3").unwrap();
+        assert_eq!(sequence.len(), 3);
+        let called = sequence.iter().nth(1).unwrap();
+        assert_eq!(called.pickoffs, vec![PickoffAttempt { base: Base::First, by_catcher: false }]);
+        assert!(called.runner_going);
+        let swinging = sequence.last().unwrap();
+        assert!(swinging.after_play);
+        assert_eq!(swinging.pickoffs, vec![PickoffAttempt { base: Base::Second, by_catcher: true }]);
+        assert_eq!(sequence.pickoffs().count(), 3);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(PitchSequence::parse("").unwrap(), PitchSequence::default());
+        let error = PitchSequence::parse("BZ").unwrap_err();
+        assert_eq!(error.to_string(), "can't parse pitch sequence \"BZ\": unknown pitch 'Z' at position 1");
+        assert!(PitchSequence::parse("B+").is_err());
+        assert!(PitchSequence::parse("B*").is_err());
+    }
+}
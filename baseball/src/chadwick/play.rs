@@ -0,0 +1,846 @@
+//! Typed model of the Retrosheet play text found in play records and `FullEvent::event_tx`.
+//!
+//! A play is made up of the basic play, an optional second event after a '+' (as in "K+SB2"),
+//! modifiers after each '/' and runner advances after the '.':
+//!
+//! ```text
+//! 64(1)3/GDP/G6.2-3
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Base {
+    Batter,
+    First,
+    Second,
+    Third,
+    Home,
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BattedBallType {
+    Ground,
+    Line,
+    Fly,
+    Popup,
+}
+
+
+/// Fielders handling the ball, such as "64", "E6" or "2E4".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Fielding {
+    pub fielders: Vec<u8>,
+    /// Fielder charged with an error, which means the runner was safe.
+    pub error: Option<u8>,
+}
+
+
+/// One out in a fielded play, "64(1)" puts out the runner from first and "3" the batter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldedOut {
+    pub runner: Base,
+    pub fielding: Fielding,
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BasicPlay {
+    /// Batter or runners put out on a batted ball, e.g. "8", "63", "64(1)3" or "54(1)".
+    FieldedOut(Vec<FieldedOut>),
+    /// Fielders are only listed when the third strike wasn't caught cleanly, e.g. "K23".
+    Strikeout(Fielding),
+    NoPlay,
+    /// "99", an out whose details aren't known.
+    UnknownOut,
+    Walk,
+    IntentionalWalk,
+    HitByPitch,
+    /// Catcher (or other fielder) interference.
+    Interference(Option<u8>),
+    Single(Option<u8>),
+    Double(Option<u8>),
+    GroundRuleDouble,
+    Triple(Option<u8>),
+    HomeRun(Option<u8>),
+    Error(u8),
+    FieldersChoice(Option<u8>),
+    FoulFlyError(u8),
+    /// Bases stolen, "SB2;SB3" steals second and third.
+    StolenBase(Vec<Steal>),
+    CaughtStealing {
+        base: Base,
+        fielding: Vec<Fielding>,
+        notes: Vec<AdvanceNote>,
+    },
+    PickoffCaughtStealing {
+        base: Base,
+        fielding: Vec<Fielding>,
+        notes: Vec<AdvanceNote>,
+    },
+    /// Pickoff of the runner on a base.
+    Pickoff {
+        base: Base,
+        fielding: Vec<Fielding>,
+        notes: Vec<AdvanceNote>,
+    },
+    WildPitch,
+    PassedBall,
+    Balk,
+    OtherAdvance,
+    DefensiveIndifference,
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Modifier {
+    SacrificeHit,
+    SacrificeFly,
+    DoublePlay {
+        batted_ball: Option<BattedBallType>,
+        bunt: bool,
+    },
+    TriplePlay {
+        batted_ball: Option<BattedBallType>,
+        bunt: bool,
+    },
+    NoDoublePlay,
+    Foul,
+    /// Batted ball type and location, e.g. "G", "BG", "L9L" or "78XD".
+    BattedBall {
+        batted_ball: Option<BattedBallType>,
+        bunt: bool,
+        location: Option<String>,
+    },
+    /// Throwing error, optionally to a base.
+    Throw(Option<Base>),
+    Error(u8),
+    Relay(Vec<u8>),
+    /// Descriptive modifiers that don't change the outcome, such as "AP", "INT" or "IPHR".
+    Other(String),
+}
+
+
+/// Parenthesized notes after an advance, e.g. "(E4/TH)", "(UR)" or "(NR)".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdvanceNote {
+    Fielding {
+        fielding: Fielding,
+        throw: bool,
+    },
+    Unearned,
+    TeamUnearned,
+    NoRbi,
+    Rbi,
+    WildPitch,
+    PassedBall,
+    Throw,
+    Interference,
+    UnknownFielding,
+    Other(String),
+}
+
+
+/// One stolen base, the base taken and any notes such as "(UR)".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Steal {
+    pub base: Base,
+    pub notes: Vec<AdvanceNote>,
+}
+
+
+/// A runner advance such as "2-H(UR)" or "1X3(E5)".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Advance {
+    pub runner: Base,
+    pub to: Base,
+    /// Marked with an 'X'. The runner is still safe if an error is noted.
+    pub thrown_out: bool,
+    pub notes: Vec<AdvanceNote>,
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Play {
+    pub basic: BasicPlay,
+    /// Second event after a '+', as in "K+WP" or "W+SB2".
+    pub additional: Option<BasicPlay>,
+    pub modifiers: Vec<Modifier>,
+    pub advances: Vec<Advance>,
+    /// Marked with '#' or '?'.
+    pub uncertain: bool,
+    /// Marked with '!'.
+    pub exceptional: bool,
+}
+
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PlayParseError {
+    pub text: String,
+    pub message: String,
+}
+
+
+impl fmt::Display for PlayParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "can't parse play \"{}\": {}", self.text, self.message)
+    }
+}
+
+
+impl Error for PlayParseError {}
+
+
+impl Base {
+    /// Runner codes used before an advance or in a fielded out, "B", "1", "2" or "3".
+    fn from_runner(code: char) -> Result<Self, String> {
+        match code {
+            'B' => Ok(Base::Batter),
+            '1' => Ok(Base::First),
+            '2' => Ok(Base::Second),
+            '3' => Ok(Base::Third),
+            _ => Err(format!("unknown runner '{}'", code)),
+        }
+    }
+
+    /// Base codes used as a destination, "1", "2", "3" or "H".
+    fn from_destination(code: char) -> Result<Self, String> {
+        match code {
+            '1' => Ok(Base::First),
+            '2' => Ok(Base::Second),
+            '3' => Ok(Base::Third),
+            'H' => Ok(Base::Home),
+            _ => Err(format!("unknown base '{}'", code)),
+        }
+    }
+
+    /// Base number as Chadwick uses it: 0 for the batter, 1-3 for the bases and 4 for home.
+    pub fn number(&self) -> u8 {
+        match self {
+            Base::Batter => 0,
+            Base::First => 1,
+            Base::Second => 2,
+            Base::Third => 3,
+            Base::Home => 4,
+        }
+    }
+}
+
+
+impl BattedBallType {
+    /// Code used for `battedball_cd`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            BattedBallType::Ground => "G",
+            BattedBallType::Line => "L",
+            BattedBallType::Fly => "F",
+            BattedBallType::Popup => "P",
+        }
+    }
+}
+
+
+impl Fielding {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut fielding = Fielding::default();
+        let mut chars = text.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '1'..='9' => fielding.fielders.push(ch as u8 - b'0'),
+                'E' => {
+                    match chars.next() {
+                        Some(e @ '1'..='9') => fielding.error = Some(e as u8 - b'0'),
+                        _ => return Err(format!("error without a fielder in \"{}\"", text)),
+                    }
+                }
+                _ => return Err(format!("unexpected '{}' in fielders \"{}\"", ch, text)),
+            }
+        }
+        Ok(fielding)
+    }
+
+    /// Fielder credited with the putout, when there was no error.
+    pub fn putout(&self) -> Option<u8> {
+        match self.error {
+            Some(_) => None,
+            None => self.fielders.last().copied(),
+        }
+    }
+
+    /// Fielders credited with an assist.
+    pub fn assists(&self) -> &[u8] {
+        match self.error {
+            Some(_) => &self.fielders,
+            None => &self.fielders[..self.fielders.len().saturating_sub(1)],
+        }
+    }
+}
+
+
+impl Advance {
+    /// Whether an error let the runner advance or kept them from being put out.
+    pub fn on_error(&self) -> bool {
+        self.notes.iter().any(|note| {
+            matches!(note, AdvanceNote::Fielding { fielding: Fielding { error: Some(_), .. }, .. })
+        })
+    }
+
+    pub fn is_out(&self) -> bool {
+        self.thrown_out && !self.on_error()
+    }
+
+    pub fn is_unearned(&self) -> bool {
+        self.notes.iter().any(|note| matches!(note, AdvanceNote::Unearned | AdvanceNote::TeamUnearned))
+    }
+}
+
+
+impl BasicPlay {
+    /// True for plays that end a plate appearance.
+    pub fn is_batter_event(&self) -> bool {
+        matches!(
+            self,
+            BasicPlay::FieldedOut(_) | BasicPlay::Strikeout(_) | BasicPlay::Walk | BasicPlay::IntentionalWalk |
+            BasicPlay::HitByPitch | BasicPlay::Interference(_) | BasicPlay::Single(_) | BasicPlay::Double(_) |
+            BasicPlay::GroundRuleDouble | BasicPlay::Triple(_) | BasicPlay::HomeRun(_) | BasicPlay::Error(_) |
+            BasicPlay::FieldersChoice(_)
+        )
+    }
+
+    pub fn is_hit(&self) -> bool {
+        matches!(
+            self,
+            BasicPlay::Single(_) | BasicPlay::Double(_) | BasicPlay::GroundRuleDouble | BasicPlay::Triple(_) |
+            BasicPlay::HomeRun(_)
+        )
+    }
+
+    pub fn is_pickoff(&self) -> bool {
+        matches!(self, BasicPlay::Pickoff { .. } | BasicPlay::PickoffCaughtStealing { .. })
+    }
+
+    /// Fielders charged with an error in the basic play itself.
+    fn errors(&self) -> Vec<u8> {
+        match self {
+            BasicPlay::Error(fielder) | BasicPlay::FoulFlyError(fielder) => vec![*fielder],
+            BasicPlay::Strikeout(fielding) => fielding.error.into_iter().collect(),
+            BasicPlay::FieldedOut(outs) => outs.iter().filter_map(|out| out.fielding.error).collect(),
+            BasicPlay::CaughtStealing { fielding, .. } | BasicPlay::PickoffCaughtStealing { fielding, .. } |
+            BasicPlay::Pickoff { fielding, .. } => fielding.iter().filter_map(|f| f.error).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+
+impl Play {
+    pub fn parse(text: &str) -> Result<Self, PlayParseError> {
+        parse_play(text).map_err(|message| PlayParseError {
+            text: text.to_string(),
+            message,
+        })
+    }
+
+    /// Both events of the play, the basic play and any event after a '+'.
+    pub fn events(&self) -> impl Iterator<Item = &BasicPlay> {
+        std::iter::once(&self.basic).chain(self.additional.as_ref())
+    }
+
+    pub fn has_modifier(&self, modifier: &Modifier) -> bool {
+        self.modifiers.contains(modifier)
+    }
+
+    pub fn is_sacrifice_fly(&self) -> bool {
+        self.has_modifier(&Modifier::SacrificeFly)
+    }
+
+    pub fn is_sacrifice_hit(&self) -> bool {
+        self.has_modifier(&Modifier::SacrificeHit)
+    }
+
+    pub fn is_double_play(&self) -> bool {
+        self.modifiers.iter().any(|modifier| matches!(modifier, Modifier::DoublePlay { .. }))
+    }
+
+    pub fn is_triple_play(&self) -> bool {
+        self.modifiers.iter().any(|modifier| matches!(modifier, Modifier::TriplePlay { .. }))
+    }
+
+    pub fn is_pickoff(&self) -> bool {
+        self.events().any(BasicPlay::is_pickoff)
+    }
+
+    /// Every fielder charged with an error anywhere in the play.
+    pub fn errors(&self) -> Vec<u8> {
+        let mut errors: Vec<u8> = self.events().flat_map(BasicPlay::errors).collect();
+        for modifier in &self.modifiers {
+            if let Modifier::Error(fielder) = modifier {
+                errors.push(*fielder);
+            }
+        }
+        for advance in &self.advances {
+            for note in &advance.notes {
+                if let AdvanceNote::Fielding { fielding: Fielding { error: Some(fielder), .. }, .. } = note {
+                    errors.push(*fielder);
+                }
+            }
+        }
+        errors
+    }
+
+    /// Advance noted for a runner, if any.
+    pub fn advance(&self, runner: Base) -> Option<&Advance> {
+        self.advances.iter().find(|advance| advance.runner == runner)
+    }
+}
+
+
+impl FromStr for Play {
+    type Err = PlayParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Play::parse(text)
+    }
+}
+
+
+/// Split text on a separator character, ignoring separators inside parentheses.
+fn split_outside_parens(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (idx, ch) in text.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ch if ch == separator && depth == 0 => {
+                parts.push(&text[start..idx]);
+                start = idx + ch.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+
+/// Pull the parenthesized groups off the end of a token, returning the leading text and the
+/// contents of each group.
+fn split_groups(text: &str) -> Result<(&str, Vec<&str>), String> {
+    let head_end = text.find('(').unwrap_or(text.len());
+    let mut groups = Vec::new();
+    let mut rest = &text[head_end..];
+    while !rest.is_empty() {
+        if !rest.starts_with('(') {
+            return Err(format!("unexpected text after parentheses in \"{}\"", text));
+        }
+        let close = rest.find(')').ok_or_else(|| format!("unclosed parenthesis in \"{}\"", text))?;
+        groups.push(&rest[1..close]);
+        rest = &rest[close + 1..];
+    }
+    Ok((&text[..head_end], groups))
+}
+
+
+fn parse_play(text: &str) -> Result<Play, String> {
+    let uncertain = text.contains('#') || text.contains('?');
+    let exceptional = text.contains('!');
+    let cleaned: String = text.chars().filter(|ch| !matches!(ch, '#' | '?' | '!')).collect();
+    if cleaned.is_empty() {
+        return Err("empty play".to_string());
+    }
+
+    let (play, advances) = match split_outside_parens(&cleaned, '.').as_slice() {
+        [play] => (play.to_string(), ""),
+        [play, advances] => (play.to_string(), *advances),
+        _ => return Err("more than one advance section".to_string()),
+    };
+
+    let mut sections = split_outside_parens(&play, '/').into_iter();
+    let main = sections.next().unwrap_or("");
+    let modifiers = sections
+        .filter(|modifier| !modifier.is_empty())
+        .map(parse_modifier)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (basic, additional) = match split_outside_parens(main, '+').as_slice() {
+        [basic] => (parse_basic_play(basic)?, None),
+        [basic, additional] => (parse_basic_play(basic)?, Some(parse_basic_play(additional)?)),
+        _ => return Err("more than two events".to_string()),
+    };
+
+    let advances = if advances.is_empty() {
+        Vec::new()
+    }
+    else {
+        advances.split(';')
+            .map(parse_advance)
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    Ok(Play {
+        basic,
+        additional,
+        modifiers,
+        advances,
+        uncertain,
+        exceptional,
+    })
+}
+
+
+/// Optional fielder after a hit or similar, "S" or "S7".
+fn parse_optional_fielder(text: &str, play: &str) -> Result<Option<u8>, String> {
+    let fielding = Fielding::parse(text)?;
+    if fielding.error.is_some() || fielding.fielders.len() > 1 {
+        return Err(format!("expected at most one fielder in \"{}\"", play));
+    }
+    Ok(fielding.fielders.first().copied())
+}
+
+
+fn parse_required_fielder(text: &str, play: &str) -> Result<u8, String> {
+    parse_optional_fielder(text, play)?.ok_or_else(|| format!("missing fielder in \"{}\"", play))
+}
+
+
+fn parse_basic_play(text: &str) -> Result<BasicPlay, String> {
+    let play = match text {
+        "" => return Err("empty event".to_string()),
+        "NP" => BasicPlay::NoPlay,
+        "99" => BasicPlay::UnknownOut,
+        "W" => BasicPlay::Walk,
+        "IW" | "I" => BasicPlay::IntentionalWalk,
+        "HP" => BasicPlay::HitByPitch,
+        "BK" => BasicPlay::Balk,
+        "DI" => BasicPlay::DefensiveIndifference,
+        "OA" => BasicPlay::OtherAdvance,
+        "PB" => BasicPlay::PassedBall,
+        "WP" => BasicPlay::WildPitch,
+        "DGR" => BasicPlay::GroundRuleDouble,
+        _ => {
+            if let Some(rest) = text.strip_prefix("POCS") {
+                let (base, fielding, notes) = parse_runner_play(rest, text)?;
+                BasicPlay::PickoffCaughtStealing { base, fielding, notes }
+            }
+            else if let Some(rest) = text.strip_prefix("PO") {
+                let (base, fielding, notes) = parse_runner_play(rest, text)?;
+                if base == Base::Home {
+                    return Err(format!("can't pick off a runner at home in \"{}\"", text));
+                }
+                BasicPlay::Pickoff { base, fielding, notes }
+            }
+            else if let Some(rest) = text.strip_prefix("CS") {
+                let (base, fielding, notes) = parse_runner_play(rest, text)?;
+                BasicPlay::CaughtStealing { base, fielding, notes }
+            }
+            else if text.starts_with("SB") {
+                let mut steals = Vec::new();
+                for steal in text.split(';') {
+                    let rest = steal.strip_prefix("SB")
+                        .ok_or_else(|| format!("expected a stolen base in \"{}\"", text))?;
+                    let (base, fielding, notes) = parse_runner_play(rest, text)?;
+                    if base == Base::First || !fielding.is_empty() {
+                        return Err(format!("bad stolen base \"{}\"", steal));
+                    }
+                    steals.push(Steal { base, notes });
+                }
+                BasicPlay::StolenBase(steals)
+            }
+            else if let Some(rest) = text.strip_prefix("FLE") {
+                BasicPlay::FoulFlyError(parse_required_fielder(rest, text)?)
+            }
+            else if let Some(rest) = text.strip_prefix("FC") {
+                BasicPlay::FieldersChoice(parse_optional_fielder(rest, text)?)
+            }
+            else if let Some(rest) = text.strip_prefix("HR") {
+                BasicPlay::HomeRun(parse_optional_fielder(rest, text)?)
+            }
+            else if let Some(rest) = text.strip_prefix('H') {
+                BasicPlay::HomeRun(parse_optional_fielder(rest, text)?)
+            }
+            else if let Some(rest) = text.strip_prefix('C') {
+                // "C/E2" is the usual form, but "CE2" turns up too.
+                BasicPlay::Interference(parse_optional_fielder(rest.trim_start_matches('E'), text)?)
+            }
+            else if let Some(rest) = text.strip_prefix('K') {
+                BasicPlay::Strikeout(Fielding::parse(rest)?)
+            }
+            else if let Some(rest) = text.strip_prefix('E') {
+                BasicPlay::Error(parse_required_fielder(rest, text)?)
+            }
+            else if let Some(rest) = text.strip_prefix('S') {
+                BasicPlay::Single(parse_optional_fielder(rest, text)?)
+            }
+            else if let Some(rest) = text.strip_prefix('D') {
+                BasicPlay::Double(parse_optional_fielder(rest, text)?)
+            }
+            else if let Some(rest) = text.strip_prefix('T') {
+                BasicPlay::Triple(parse_optional_fielder(rest, text)?)
+            }
+            else if text.starts_with(|ch: char| ch.is_ascii_digit()) {
+                BasicPlay::FieldedOut(parse_fielded_outs(text)?)
+            }
+            else {
+                return Err(format!("unknown event \"{}\"", text));
+            }
+        }
+    };
+    Ok(play)
+}
+
+
+/// Base and fielding for caught stealing and pickoffs, e.g. "2(24)" or "H(E2)(UR)".
+fn parse_runner_play(rest: &str, play: &str) -> Result<(Base, Vec<Fielding>, Vec<AdvanceNote>), String> {
+    let (base, groups) = split_groups(rest)?;
+    let mut chars = base.chars();
+    let base = match (chars.next(), chars.next()) {
+        (Some(base), None) => Base::from_destination(base)?,
+        _ => return Err(format!("missing base in \"{}\"", play)),
+    };
+    let mut fielding = Vec::new();
+    let mut notes = Vec::new();
+    for group in groups {
+        match parse_advance_note(group)? {
+            AdvanceNote::Fielding { fielding: f, .. } => fielding.push(f),
+            note => notes.push(note),
+        }
+    }
+    Ok((base, fielding, notes))
+}
+
+
+/// Parse a fielded out such as "8", "63", "64(1)3", or "8(B)84(2)".
+fn parse_fielded_outs(text: &str) -> Result<Vec<FieldedOut>, String> {
+    let mut outs = Vec::new();
+    let mut segment = String::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '1'..='9' | 'E' => segment.push(ch),
+            '(' => {
+                let runner = match (chars.next(), chars.next()) {
+                    (Some(runner), Some(')')) => Base::from_runner(runner)?,
+                    _ => return Err(format!("bad runner in \"{}\"", text)),
+                };
+                if segment.is_empty() {
+                    return Err(format!("runner without fielders in \"{}\"", text));
+                }
+                outs.push(FieldedOut { runner, fielding: Fielding::parse(&segment)? });
+                segment.clear();
+            }
+            _ => return Err(format!("unexpected '{}' in \"{}\"", ch, text)),
+        }
+    }
+    if !segment.is_empty() {
+        outs.push(FieldedOut { runner: Base::Batter, fielding: Fielding::parse(&segment)? });
+    }
+    Ok(outs)
+}
+
+
+fn parse_batted_ball_type(code: &str) -> Option<(Option<BattedBallType>, bool)> {
+    let batted_ball = match code {
+        "" => (None, false),
+        "G" => (Some(BattedBallType::Ground), false),
+        "L" => (Some(BattedBallType::Line), false),
+        "F" => (Some(BattedBallType::Fly), false),
+        "P" => (Some(BattedBallType::Popup), false),
+        "B" => (None, true),
+        "BG" => (Some(BattedBallType::Ground), true),
+        "BL" => (Some(BattedBallType::Line), true),
+        "BP" => (Some(BattedBallType::Popup), true),
+        _ => return None,
+    };
+    Some(batted_ball)
+}
+
+
+fn is_location(location: &str) -> bool {
+    location.starts_with(|ch: char| ch.is_ascii_digit())
+        && location.chars().all(|ch| ch.is_ascii_digit() || matches!(ch, 'L' | 'M' | 'D' | 'S' | 'X' | 'F'))
+}
+
+
+fn parse_modifier(text: &str) -> Result<Modifier, String> {
+    let modifier = match text {
+        "SH" => Modifier::SacrificeHit,
+        "SF" => Modifier::SacrificeFly,
+        "NDP" => Modifier::NoDoublePlay,
+        "FL" => Modifier::Foul,
+        "TH" => Modifier::Throw(None),
+        "AP" | "BINT" | "BOOT" | "BR" | "C" | "COUB" | "COUF" | "COUR" | "FINT" | "FO" | "IF" | "INT" |
+        "IPHR" | "MREV" | "OBS" | "PASS" | "RINT" | "UINT" | "UREV" | "R" => Modifier::Other(text.to_string()),
+        _ => {
+            if let Some(batted_ball) = text.strip_suffix("DP").and_then(parse_batted_ball_type) {
+                Modifier::DoublePlay { batted_ball: batted_ball.0, bunt: batted_ball.1 }
+            }
+            else if let Some(batted_ball) = text.strip_suffix("TP").and_then(parse_batted_ball_type) {
+                Modifier::TriplePlay { batted_ball: batted_ball.0, bunt: batted_ball.1 }
+            }
+            else if let Some(base) = text.strip_prefix("TH") {
+                let mut chars = base.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(base), None) => Modifier::Throw(Some(Base::from_destination(base)?)),
+                    _ => return Err(format!("bad throw modifier \"{}\"", text)),
+                }
+            }
+            else if let Some(fielder) = text.strip_prefix('E') {
+                Modifier::Error(parse_required_fielder(fielder, text)?)
+            }
+            else if let Some(fielders) = text.strip_prefix('R') && fielders.chars().all(|ch| ch.is_ascii_digit()) {
+                Modifier::Relay(Fielding::parse(fielders)?.fielders)
+            }
+            else {
+                // Trailing '+' and '-' mark hard and soft contact, which isn't kept.
+                let code = text.trim_end_matches(['+', '-']);
+                let split = code.find(|ch: char| ch.is_ascii_digit()).unwrap_or(code.len());
+                let (batted_ball, location) = code.split_at(split);
+                match parse_batted_ball_type(batted_ball) {
+                    Some((batted_ball, bunt)) if !code.is_empty() && (location.is_empty() || is_location(location)) => {
+                        let location = if location.is_empty() { None } else { Some(location.to_string()) };
+                        Modifier::BattedBall { batted_ball, bunt, location }
+                    }
+                    // Scorers use plenty of descriptive codes beyond the documented ones.
+                    _ if !code.is_empty() && code.chars().all(|ch| ch.is_ascii_alphanumeric()) => {
+                        Modifier::Other(text.to_string())
+                    }
+                    _ => return Err(format!("unknown modifier \"{}\"", text)),
+                }
+            }
+        }
+    };
+    Ok(modifier)
+}
+
+
+fn parse_advance_note(text: &str) -> Result<AdvanceNote, String> {
+    let note = match text {
+        "UR" => AdvanceNote::Unearned,
+        "TUR" => AdvanceNote::TeamUnearned,
+        "NR" | "NORBI" => AdvanceNote::NoRbi,
+        "RBI" => AdvanceNote::Rbi,
+        "WP" => AdvanceNote::WildPitch,
+        "PB" => AdvanceNote::PassedBall,
+        "TH" | "TH1" | "TH2" | "TH3" | "THH" => AdvanceNote::Throw,
+        "INT" => AdvanceNote::Interference,
+        "99" => AdvanceNote::UnknownFielding,
+        _ => {
+            let mut parts = text.split('/');
+            let fielders = parts.next().unwrap_or("");
+            let modifiers: Vec<&str> = parts.collect();
+            if !fielders.is_empty() && fielders.chars().all(|ch| ch.is_ascii_digit() || ch == 'E') {
+                let throw = modifiers.iter().any(|modifier| modifier.starts_with("TH"));
+                AdvanceNote::Fielding { fielding: Fielding::parse(fielders)?, throw }
+            }
+            else if !text.is_empty() && text.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '/') {
+                AdvanceNote::Other(text.to_string())
+            }
+            else {
+                return Err(format!("unknown advance note \"({})\"", text));
+            }
+        }
+    };
+    Ok(note)
+}
+
+
+fn parse_advance(text: &str) -> Result<Advance, String> {
+    let mut chars = text.chars();
+    let (runner, kind, to) = match (chars.next(), chars.next(), chars.next()) {
+        (Some(runner), Some(kind), Some(to)) => (runner, kind, to),
+        _ => return Err(format!("incomplete advance \"{}\"", text)),
+    };
+    let runner = Base::from_runner(runner)?;
+    let to = Base::from_destination(to)?;
+    let thrown_out = match kind {
+        '-' => false,
+        'X' => true,
+        _ => return Err(format!("advance \"{}\" must use '-' or 'X'", text)),
+    };
+    if to.number() < runner.number() || (to == runner && runner != Base::Batter) {
+        return Err(format!("runner can't move backwards in \"{}\"", text));
+    }
+    let (rest, groups) = split_groups(&text[3..])?;
+    if !rest.is_empty() {
+        return Err(format!("unexpected \"{}\" in advance \"{}\"", rest, text));
+    }
+    let notes = groups.into_iter()
+        .map(parse_advance_note)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Advance {
+        runner,
+        to,
+        thrown_out,
+        notes,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn double_play() {
+        let play = Play::parse("64(1)3/GDP/G6.2-3").unwrap();
+        assert_eq!(play.basic, BasicPlay::FieldedOut(vec![
+            FieldedOut { runner: Base::First, fielding: Fielding { fielders: vec![6, 4], error: None } },
+            FieldedOut { runner: Base::Batter, fielding: Fielding { fielders: vec![3], error: None } },
+        ]));
+        assert!(play.is_double_play());
+        assert_eq!(play.modifiers[1], Modifier::BattedBall {
+            batted_ball: Some(BattedBallType::Ground),
+            bunt: false,
+            location: Some("6".to_string()),
+        });
+        assert_eq!(play.advance(Base::Second).map(|a| a.to), Some(Base::Third));
+    }
+
+    #[test]
+    fn advances() {
+        let play = Play::parse("S9/L9S.2-H(E9/TH)(UR)(NR);1X3(95);B-2").unwrap();
+        assert_eq!(play.basic, BasicPlay::Single(Some(9)));
+        let scored = play.advance(Base::Second).unwrap();
+        assert!(scored.on_error() && scored.is_unearned());
+        assert!(scored.notes.contains(&AdvanceNote::NoRbi));
+        assert!(play.advance(Base::First).unwrap().is_out());
+        assert_eq!(play.errors(), vec![9]);
+    }
+
+    #[test]
+    fn additional_events() {
+        let play = Play::parse("K+PO1(E3).1-2").unwrap();
+        assert_eq!(play.basic, BasicPlay::Strikeout(Fielding::default()));
+        assert!(play.is_pickoff());
+        assert_eq!(play.errors(), vec![3]);
+
+        let play = Play::parse("SB3;SBH(UR)").unwrap();
+        assert_eq!(play.basic, BasicPlay::StolenBase(vec![
+            Steal { base: Base::Third, notes: Vec::new() },
+            Steal { base: Base::Home, notes: vec![AdvanceNote::Unearned] },
+        ]));
+    }
+
+    #[test]
+    fn sacrifice_fly_double_play() {
+        let play = Play::parse("9/SF/DP/E4.3-H;1X2(94)").unwrap();
+        assert!(play.is_sacrifice_fly() && play.is_double_play());
+        assert_eq!(play.errors(), vec![4]);
+    }
+
+    #[test]
+    fn errors() {
+        let error = Play::parse("S8/G8*").unwrap_err();
+        assert_eq!(error.to_string(), "can't parse play \"S8/G8*\": unknown modifier \"G8*\"");
+        assert!(Play::parse("SB1").is_err());
+        assert!(Play::parse("2-1").is_err());
+        assert!(Play::parse("63.3-2").is_err());
+        assert!(Play::parse("64(1").is_err());
+    }
+}
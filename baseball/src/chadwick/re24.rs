@@ -0,0 +1,293 @@
+//! Run expectancy for the 24 base-out states and the RE24 value of each plate appearance.
+//!
+//! The matrix holds, for every base-out state, how many runs scored from that point to the end of
+//! the half-inning. A plate appearance is worth the change in run expectancy plus the runs that
+//! scored on it, which is credited to the batter and charged to the pitcher.
+
+use std::fmt;
+
+use crate::chadwick::events::FullEvent;
+use crate::chadwick::state::{self, StateChange};
+
+
+/// Ignore innings after the eighth because tactics change.
+pub const INNING_LIMIT: u8 = 8;
+
+
+/// Totals for one base-out state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StateTotals {
+    /// Runs scored from this state through the end of the half-inning.
+    pub runs: u32,
+    /// Times the state came up.
+    pub times: u32,
+    /// Times no runs scored through the end of the half-inning.
+    pub scoreless: u32,
+}
+
+
+/// Run expectancy indexed by `start_bases_cd` (0-7) and outs (0-2).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunExpectancy {
+    states: [[StateTotals; 3]; 8],
+}
+
+
+/// Displays the chance of scoring from each state of a matrix, -1 for states that never came up.
+pub struct ScoringChances<'a>(&'a RunExpectancy);
+
+
+/// RE24 of a single plate appearance. The pitcher's value is the negative of the batter's.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlateAppearanceValue {
+    pub game_id: String,
+    pub event_id: String,
+    pub bat_id: String,
+    pub pit_id: String,
+    pub re24: f32,
+}
+
+
+impl StateTotals {
+    pub fn expected_runs(&self) -> f64 {
+        if self.times > 0 {
+            self.runs as f64 / self.times as f64
+        }
+        else {
+            0.0
+        }
+    }
+
+    /// Chance of scoring at least one run, None when the state never came up.
+    pub fn scoring_probability(&self) -> Option<f64> {
+        if self.times > 0 {
+            Some((self.times - self.scoreless) as f64 / self.times as f64)
+        }
+        else {
+            None
+        }
+    }
+}
+
+
+impl RunExpectancy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the matrix from events in game order, such as one or more seasons of cwevent output.
+    pub fn from_events(events: &[FullEvent]) -> Self {
+        let mut re = Self::new();
+        re.add_events(events);
+        re
+    }
+
+    /// Add the completed half-innings found in events, which must be in game order.
+    pub fn add_events(&mut self, events: &[FullEvent]) {
+        let changes = state::replay(events);
+        let mut start = 0;
+        while start < changes.len() {
+            let half_inning = (&events[start].game_id, events[start].inn_ct, &events[start].bat_home_id);
+            let end = (start..changes.len())
+                .find(|idx| (&events[*idx].game_id, events[*idx].inn_ct, &events[*idx].bat_home_id) != half_inning)
+                .unwrap_or(changes.len());
+            // Skip innings that didn't finish, like walk-offs and shortened games.
+            if events[start].inn_ct <= INNING_LIMIT && changes[end - 1].after.inning_over() {
+                self.add_half_inning(&changes[start..end]);
+            }
+            start = end;
+        }
+    }
+
+    fn add_half_inning(&mut self, changes: &[StateChange]) {
+        let mut runs_after: u32 = changes.iter().map(|change| change.runs_scored() as u32).sum();
+        for change in changes {
+            if let Some(totals) = self.totals_mut(change.before.bases_cd(), change.before.outs) {
+                totals.runs += runs_after;
+                totals.times += 1;
+                if runs_after == 0 {
+                    totals.scoreless += 1;
+                }
+            }
+            runs_after -= change.runs_scored() as u32;
+        }
+    }
+
+    /// Combine totals with another matrix, for building a matrix over several seasons.
+    pub fn merge(&mut self, other: &RunExpectancy) {
+        for (bases, outs) in self.states.iter_mut().zip(other.states.iter()) {
+            for (totals, other) in bases.iter_mut().zip(outs.iter()) {
+                totals.runs += other.runs;
+                totals.times += other.times;
+                totals.scoreless += other.scoreless;
+            }
+        }
+    }
+
+    /// Add saved totals for one state, such as a season's row of a stored matrix.
+    pub fn add_totals(&mut self, bases_cd: u8, outs: u8, other: &StateTotals) {
+        if let Some(totals) = self.totals_mut(bases_cd, outs) {
+            totals.runs += other.runs;
+            totals.times += other.times;
+            totals.scoreless += other.scoreless;
+        }
+    }
+
+    pub fn totals(&self, bases_cd: u8, outs: u8) -> Option<&StateTotals> {
+        self.states.get(bases_cd as usize).and_then(|bases| bases.get(outs as usize))
+    }
+
+    fn totals_mut(&mut self, bases_cd: u8, outs: u8) -> Option<&mut StateTotals> {
+        self.states.get_mut(bases_cd as usize).and_then(|bases| bases.get_mut(outs as usize))
+    }
+
+    /// Runs expected through the end of the half-inning. Three outs expects no more runs.
+    pub fn expected_runs(&self, bases_cd: u8, outs: u8) -> f64 {
+        self.totals(bases_cd, outs).map(StateTotals::expected_runs).unwrap_or_default()
+    }
+
+    /// The chance of scoring at least one run from each state, for display.
+    pub fn scoring_chances(&self) -> ScoringChances<'_> {
+        ScoringChances(self)
+    }
+
+    /// Change in run expectancy plus runs scored for an event.
+    pub fn event_value(&self, change: &StateChange) -> f64 {
+        let start = self.expected_runs(change.before.bases_cd(), change.before.outs);
+        let end = self.expected_runs(change.after.bases_cd(), change.after.outs);
+        end - start + change.runs_scored() as f64
+    }
+
+    /// RE24 for every plate appearance in events, which must be in game order. Only the event
+    /// ending the plate appearance counts, so stolen bases and wild pitches during it don't.
+    pub fn plate_appearance_values(&self, events: &[FullEvent]) -> Vec<PlateAppearanceValue> {
+        state::replay(events).iter()
+            .zip(events.iter())
+            .filter(|(_, event)| event.bat_event_fl)
+            .map(|(change, event)| PlateAppearanceValue {
+                game_id: event.game_id.clone(),
+                event_id: event.event_id.clone(),
+                bat_id: event.resp_bat_id.clone(),
+                pit_id: event.resp_pit_id.clone(),
+                re24: self.event_value(change) as f32,
+            })
+            .collect()
+    }
+}
+
+
+fn bases_label(bases_cd: usize) -> &'static str {
+    match bases_cd {
+        1 => "1B -- --",
+        2 => "-- 2B --",
+        3 => "1B 2B --",
+        4 => "-- -- 3B",
+        5 => "1B -- 3B",
+        6 => "-- 2B 3B",
+        7 => "1B 2B 3B",
+        _ => "-- -- --",
+    }
+}
+
+
+fn write_table(f: &mut fmt::Formatter<'_>, re: &RunExpectancy, value: fn(&StateTotals) -> f64) -> fmt::Result {
+    writeln!(f, "1B 2B 3B  0 Outs 1 Outs 2 Outs")?;
+    for (bases_cd, outs) in re.states.iter().enumerate() {
+        write!(f, "{}", bases_label(bases_cd))?;
+        for totals in outs {
+            write!(f, "  {:0.3}", value(totals))?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+
+impl fmt::Display for RunExpectancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_table(f, self, StateTotals::expected_runs)
+    }
+}
+
+
+impl fmt::Display for ScoringChances<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_table(f, self.0, |totals| totals.scoring_probability().unwrap_or(-1.0))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chadwick::events::test_event;
+
+    fn event(event_id: &str, outs: u8, base1: &str, dests: [&str; 2], event_outs: u8) -> FullEvent {
+        FullEvent {
+            base1_run_id: base1.to_string(),
+            run1_resp_pit_id: if base1.is_empty() { String::new() } else { "pit01".to_string() },
+            run1_dest_id: dests[1].to_string(),
+            ..test_event("TST202404010", event_id, 1, false, outs, dests[0], event_outs)
+        }
+    }
+
+    #[test]
+    fn half_inning() {
+        // Single, home run, then three outs.
+        let events = vec![
+            event("1", 0, "", ["1", "0"], 0),
+            event("2", 0, "bat1", ["4", "4"], 0),
+            event("3", 0, "", ["0", "0"], 1),
+            event("4", 1, "", ["0", "0"], 1),
+            event("5", 2, "", ["0", "0"], 1),
+        ];
+        let re = RunExpectancy::from_events(&events);
+        assert_eq!(re.totals(0, 0), Some(&StateTotals { runs: 2, times: 2, scoreless: 1 }));
+        assert_eq!(re.totals(1, 0), Some(&StateTotals { runs: 2, times: 1, scoreless: 0 }));
+        assert_eq!(re.expected_runs(0, 2), 0.0);
+
+        let values = re.plate_appearance_values(&events);
+        assert_eq!(values.len(), 5);
+        // Bases empty with no outs to a runner on first: 2.0 - 1.0.
+        assert_eq!(values[0].re24, 1.0);
+        // Back to bases empty with two runs in: 1.0 - 2.0 + 2.
+        assert_eq!(values[1].re24, 1.0);
+        assert_eq!(values[2].re24, -1.0);
+    }
+
+    #[test]
+    fn unfinished_innings() {
+        let events = vec![
+            event("1", 0, "", ["1", "0"], 0),
+            event("2", 0, "bat1", ["0", "2"], 1),
+        ];
+        let re = RunExpectancy::from_events(&events);
+        assert_eq!(re, RunExpectancy::new());
+    }
+
+    #[test]
+    fn combined_seasons() {
+        let season = RunExpectancy::from_events(&[
+            event("1", 0, "", ["0", "0"], 1),
+            event("2", 1, "", ["1", "0"], 0),
+            event("3", 1, "bat2", ["4", "4"], 0),
+            event("4", 1, "", ["0", "0"], 1),
+            event("5", 2, "", ["0", "0"], 1),
+        ]);
+        let mut merged = season.clone();
+        merged.merge(&season);
+        let mut added = RunExpectancy::new();
+        for _ in 0..2 {
+            for bases_cd in 0..8 {
+                for outs in 0..3 {
+                    added.add_totals(bases_cd, outs, season.totals(bases_cd, outs).unwrap());
+                }
+            }
+        }
+        assert_eq!(added, merged);
+        assert_eq!(added.totals(0, 1), Some(&StateTotals { runs: 4, times: 4, scoreless: 2 }));
+        let chances = added.scoring_chances().to_string();
+        assert!(chances.starts_with("1B 2B 3B  0 Outs 1 Outs 2 Outs\n-- -- --  1.000  0.500  0.000\n"));
+        assert!(chances.ends_with("1B 2B 3B  -1.000  -1.000  -1.000\n"));
+    }
+}
@@ -0,0 +1,333 @@
+//! Follow the base-out state through a game by replaying the runner destinations of each event.
+//!
+//! Each half-inning starts from the state recorded on its first event. After that the state is
+//! only carried forward from `bat_dest_id` and `run1_dest_id` through `run3_dest_id`, so comparing
+//! the replayed state with what the next event recorded catches inconsistent data.
+
+use crate::chadwick::events::FullEvent;
+
+
+// Destination codes used by Chadwick. Bases 1-3 are themselves.
+const DEST_OUT: u8 = 0;
+const DEST_SCORED: u8 = 4;
+const DEST_UNEARNED: u8 = 5;
+const DEST_TEAM_UNEARNED: u8 = 6;
+
+const EVENT_GENERIC_OUT: u8 = 2;
+const EVENT_FIELDERS_CHOICE: u8 = 19;
+
+
+/// Runner on base along with the pitcher charged if they score.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BaseRunner {
+    pub runner_id: String,
+    pub resp_pit_id: String,
+}
+
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BaseOutState {
+    pub outs: u8,
+    /// Runners on first, second and third.
+    pub runners: [Option<BaseRunner>; 3],
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunScored {
+    pub runner_id: String,
+    pub resp_pit_id: String,
+    /// Marked unearned with (UR) or (TUR).
+    pub unearned: bool,
+    /// Only unearned to the team, marked with (TUR).
+    pub team_unearned: bool,
+}
+
+
+/// The replayed state after an event differs from the state recorded at the start of the next.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateMismatch {
+    pub next_event_id: String,
+    pub replayed: BaseOutState,
+    pub recorded: BaseOutState,
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateChange {
+    pub game_id: String,
+    pub event_id: String,
+    pub before: BaseOutState,
+    pub after: BaseOutState,
+    pub runs: Vec<RunScored>,
+    /// Only filled in by `replay`, which can look at the next event.
+    pub mismatch: Option<StateMismatch>,
+}
+
+
+/// Replays events one at a time, restarting from the recorded state at each new half-inning.
+#[derive(Default)]
+pub struct BaseOutTracker {
+    half_inning: Option<(String, u8, String)>,
+    state: BaseOutState,
+    /// Bases where the last event flagged a pinch runner coming in afterwards.
+    pinch_runners: [bool; 3],
+}
+
+
+fn dest_code(dest: &str) -> Option<u8> {
+    dest.parse().ok()
+}
+
+
+fn runner(runner_id: &str, resp_pit_id: &str) -> Option<BaseRunner> {
+    if runner_id.is_empty() {
+        None
+    }
+    else {
+        Some(BaseRunner {
+            runner_id: runner_id.to_string(),
+            resp_pit_id: resp_pit_id.to_string(),
+        })
+    }
+}
+
+
+impl BaseOutState {
+    /// State at the start of an event, as recorded on it.
+    pub fn from_event(event: &FullEvent) -> Self {
+        Self {
+            outs: event.outs_ct,
+            runners: [
+                runner(&event.base1_run_id, &event.run1_resp_pit_id),
+                runner(&event.base2_run_id, &event.run2_resp_pit_id),
+                runner(&event.base3_run_id, &event.run3_resp_pit_id),
+            ],
+        }
+    }
+
+    /// Bases occupied, in the same form as `start_bases_cd`: 1 for first, 2 for second and 4
+    /// for third.
+    pub fn bases_cd(&self) -> u8 {
+        self.runners.iter()
+            .enumerate()
+            .filter(|(_, runner)| runner.is_some())
+            .map(|(base, _)| 1 << base)
+            .sum()
+    }
+
+    pub fn inning_over(&self) -> bool {
+        self.outs >= 3
+    }
+
+    /// Apply the runner destinations of an event to this state.
+    pub fn apply(&self, event: &FullEvent) -> StateChange {
+        let mut after = BaseOutState {
+            outs: self.outs + event.event_outs_ct,
+            ..Default::default()
+        };
+        let mut runs = Vec::new();
+
+        let batter_safe = event.bat_event_fl
+            && dest_code(&event.bat_dest_id).is_some_and(|dest| dest != DEST_OUT);
+        // Batters reaching on a force take over the pitcher responsibility of the runner put out.
+        let mut batter_resp_pit = event.resp_pit_id.clone();
+        if batter_safe && matches!(event.event_cd, EVENT_GENERIC_OUT | EVENT_FIELDERS_CHOICE) {
+            let forced = (0..3).rev().find(|base| {
+                self.runners[*base].is_some() && dest_code(run_dest(event, *base)) == Some(DEST_OUT)
+            });
+            if let Some(runner) = forced.and_then(|base| self.runners[base].as_ref()) {
+                batter_resp_pit = runner.resp_pit_id.clone();
+            }
+        }
+
+        let batter = BaseRunner {
+            runner_id: event.bat_id.clone(),
+            resp_pit_id: batter_resp_pit,
+        };
+        let mut movers: Vec<(BaseRunner, u8)> = Vec::with_capacity(4);
+        if batter_safe && let Some(dest) = dest_code(&event.bat_dest_id) {
+            movers.push((batter, dest));
+        }
+        for (base, runner) in self.runners.iter().enumerate() {
+            if let Some(runner) = runner && let Some(dest) = dest_code(run_dest(event, base)) {
+                movers.push((runner.clone(), dest));
+            }
+        }
+
+        for (runner, dest) in movers {
+            match dest {
+                1..=3 => after.runners[dest as usize - 1] = Some(runner),
+                DEST_SCORED | DEST_UNEARNED | DEST_TEAM_UNEARNED => {
+                    runs.push(RunScored {
+                        runner_id: runner.runner_id,
+                        resp_pit_id: runner.resp_pit_id,
+                        unearned: dest != DEST_SCORED,
+                        team_unearned: dest == DEST_TEAM_UNEARNED,
+                    });
+                }
+                _ => {}
+            }
+        }
+        if after.inning_over() {
+            after.runners = Default::default();
+        }
+
+        StateChange {
+            game_id: event.game_id.clone(),
+            event_id: event.event_id.clone(),
+            before: self.clone(),
+            after,
+            runs,
+            mismatch: None,
+        }
+    }
+}
+
+
+fn run_dest(event: &FullEvent, base: usize) -> &str {
+    match base {
+        0 => &event.run1_dest_id,
+        1 => &event.run2_dest_id,
+        _ => &event.run3_dest_id,
+    }
+}
+
+
+fn half_inning(event: &FullEvent) -> (String, u8, String) {
+    (event.game_id.clone(), event.inn_ct, event.bat_home_id.clone())
+}
+
+
+impl StateChange {
+    pub fn runs_scored(&self) -> u8 {
+        self.runs.len() as u8
+    }
+}
+
+
+impl BaseOutTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// State after the last event applied.
+    pub fn state(&self) -> &BaseOutState {
+        &self.state
+    }
+
+    pub fn apply(&mut self, event: &FullEvent) -> StateChange {
+        let half_inning = half_inning(event);
+        if self.half_inning.as_ref() != Some(&half_inning) {
+            self.state = BaseOutState::from_event(event);
+            self.half_inning = Some(half_inning);
+        }
+        else {
+            self.state = self.with_pinch_runners(event);
+        }
+        let change = self.state.apply(event);
+        self.state = change.after.clone();
+        self.pinch_runners = [event.pr_run1_fl, event.pr_run2_fl, event.pr_run3_fl];
+        change
+    }
+
+    /// The replayed state with any pinch runners from the next event put on base.
+    fn with_pinch_runners(&self, next: &FullEvent) -> BaseOutState {
+        let mut state = self.state.clone();
+        let next_runners = [&next.base1_run_id, &next.base2_run_id, &next.base3_run_id];
+        for (base, runner) in state.runners.iter_mut().enumerate() {
+            if self.pinch_runners[base] && let Some(runner) = runner && !next_runners[base].is_empty() {
+                runner.runner_id = next_runners[base].clone();
+            }
+        }
+        state
+    }
+
+    /// Compare the replayed state with the state recorded on the next event. Only events in the
+    /// same half-inning can be checked.
+    pub fn check(&self, next: &FullEvent) -> Option<StateMismatch> {
+        if self.half_inning.as_ref() != Some(&half_inning(next)) {
+            return None;
+        }
+        let recorded = BaseOutState::from_event(next);
+        let replayed = self.with_pinch_runners(next);
+        if recorded == replayed {
+            None
+        }
+        else {
+            Some(StateMismatch {
+                next_event_id: next.event_id.clone(),
+                replayed,
+                recorded,
+            })
+        }
+    }
+}
+
+
+/// Replay a sequence of events, flagging any whose outcome doesn't match the next event.
+pub fn replay(events: &[FullEvent]) -> Vec<StateChange> {
+    let mut tracker = BaseOutTracker::new();
+    let mut changes = Vec::with_capacity(events.len());
+    for (idx, event) in events.iter().enumerate() {
+        let mut change = tracker.apply(event);
+        if let Some(next) = events.get(idx + 1) {
+            change.mismatch = tracker.check(next);
+        }
+        changes.push(change);
+    }
+    changes
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chadwick::events::test_event;
+
+    fn event(event_id: &str, outs: u8, bases: [&str; 3], dests: [&str; 4], event_outs: u8) -> FullEvent {
+        let resp_pit = |runner: &str| if runner.is_empty() { String::new() } else { "pit01".to_string() };
+        FullEvent {
+            base1_run_id: bases[0].to_string(),
+            base2_run_id: bases[1].to_string(),
+            base3_run_id: bases[2].to_string(),
+            run1_resp_pit_id: resp_pit(bases[0]),
+            run2_resp_pit_id: resp_pit(bases[1]),
+            run3_resp_pit_id: resp_pit(bases[2]),
+            event_cd: 20,
+            run1_dest_id: dests[1].to_string(),
+            run2_dest_id: dests[2].to_string(),
+            run3_dest_id: dests[3].to_string(),
+            ..test_event("TST202404010", event_id, 1, false, outs, dests[0], event_outs)
+        }
+    }
+
+    #[test]
+    fn replay_half_inning() {
+        let events = vec![
+            event("1", 0, ["", "", ""], ["1", "0", "0", "0"], 0),
+            event("2", 0, ["bat1", "", ""], ["2", "4", "0", "0"], 0),
+            event("3", 0, ["", "bat2", ""], ["0", "0", "3", "0"], 1),
+            event("4", 1, ["", "", "bat2"], ["0", "0", "0", "5"], 1),
+        ];
+        let changes = replay(&events);
+        assert_eq!(changes[1].after.bases_cd(), 2);
+        assert_eq!(changes[1].runs_scored(), 1);
+        assert_eq!(changes[2].after.outs, 1);
+        assert_eq!(changes[2].after.bases_cd(), 4);
+        assert!(changes[3].runs[0].unearned);
+        assert!(changes.iter().all(|change| change.mismatch.is_none()));
+    }
+
+    #[test]
+    fn mismatch() {
+        let events = vec![
+            event("1", 0, ["", "", ""], ["2", "0", "0", "0"], 0),
+            event("2", 0, ["bat1", "", ""], ["0", "1", "0", "0"], 1),
+        ];
+        let changes = replay(&events);
+        let mismatch = changes[0].mismatch.as_ref().unwrap();
+        assert_eq!(mismatch.replayed.bases_cd(), 2);
+        assert_eq!(mismatch.recorded.bases_cd(), 1);
+    }
+}
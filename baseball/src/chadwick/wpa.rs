@@ -0,0 +1,334 @@
+//! Win expectancy for each game state and the win probability added by every event.
+//!
+//! A game state is the inning, half, outs, bases occupied and score difference at the start of an
+//! event. The model counts how often the home team went on to win from each state. Rare states are
+//! smoothed by shrinking their record toward the same inning and score with the bases and outs
+//! pooled, which is in turn shrunk toward the score difference alone.
+
+use std::collections::HashMap;
+
+use crate::chadwick::events::FullEvent;
+use crate::chadwick::state::{self, BaseOutState, StateChange};
+
+
+/// Extra innings share the ninth inning's states.
+pub const INNING_LIMIT: u8 = 9;
+/// Score differences beyond this many runs are treated as this many.
+pub const MAX_SCORE_DIFF: i8 = 10;
+/// Number of games of the broader state blended into each state's own record.
+pub const SMOOTHING_GAMES: f64 = 20.0;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GameState {
+    pub inning: u8,
+    pub bottom: bool,
+    pub outs: u8,
+    pub bases_cd: u8,
+    /// Home team's runs minus the away team's.
+    pub score_diff: i8,
+}
+
+
+/// Times a state came up and how many of those games the home team won.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WinTotals {
+    pub wins: u32,
+    pub times: u32,
+}
+
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WinExpectancy {
+    states: HashMap<GameState, WinTotals>,
+    /// States pooled over the bases and outs.
+    situations: HashMap<(u8, bool, i8), WinTotals>,
+    /// States pooled over everything but the score.
+    margins: HashMap<i8, WinTotals>,
+}
+
+
+/// Win probability added by a single event. The pitcher's value is the negative of the batter's.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventWinValue {
+    pub game_id: String,
+    pub event_id: String,
+    pub bat_id: String,
+    pub pit_id: String,
+    /// Home team's chance of winning at the start of the event.
+    pub win_expectancy: f32,
+    /// Change in the batting team's chance of winning.
+    pub wpa: f32,
+}
+
+
+impl GameState {
+    pub fn new(inning: u8, bottom: bool, outs: u8, bases_cd: u8, score_diff: i32) -> Self {
+        Self {
+            inning: inning.clamp(1, INNING_LIMIT),
+            bottom,
+            outs,
+            bases_cd,
+            score_diff: score_diff.clamp(-MAX_SCORE_DIFF as i32, MAX_SCORE_DIFF as i32) as i8,
+        }
+    }
+
+    /// State at the start of an event, as recorded on it.
+    pub fn from_event(event: &FullEvent) -> Self {
+        let bases = BaseOutState::from_event(event);
+        Self::new(event.inn_ct, home_batting(event), event.outs_ct, bases.bases_cd(), score_diff(event))
+    }
+
+    pub(crate) fn situation(&self) -> (u8, bool, i8) {
+        (self.inning, self.bottom, self.score_diff)
+    }
+}
+
+
+impl WinTotals {
+    fn add(&mut self, home_win: bool) {
+        self.times += 1;
+        if home_win {
+            self.wins += 1;
+        }
+    }
+
+    /// Blend the record with a prior probability worth `SMOOTHING_GAMES` games.
+    fn smoothed(totals: Option<&WinTotals>, prior: f64) -> f64 {
+        let (wins, times) = totals.map_or((0.0, 0.0), |totals| (totals.wins as f64, totals.times as f64));
+        (wins + SMOOTHING_GAMES * prior) / (times + SMOOTHING_GAMES)
+    }
+}
+
+
+fn home_batting(event: &FullEvent) -> bool {
+    event.bat_home_id == "1"
+}
+
+
+fn score_diff(event: &FullEvent) -> i32 {
+    event.home_score_ct as i32 - event.away_score_ct as i32
+}
+
+
+/// Home team's runs minus the away team's after the event.
+fn score_diff_after(event: &FullEvent, change: &StateChange) -> i32 {
+    let runs = change.runs_scored() as i32;
+    if home_batting(event) {
+        score_diff(event) + runs
+    }
+    else {
+        score_diff(event) - runs
+    }
+}
+
+
+/// Split events into games, which must be in game order.
+fn games(events: &[FullEvent]) -> impl Iterator<Item = std::ops::Range<usize>> + '_ {
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        if start >= events.len() {
+            return None;
+        }
+        let game_id = &events[start].game_id;
+        let end = (start..events.len())
+            .find(|idx| &events[*idx].game_id != game_id)
+            .unwrap_or(events.len());
+        let game = start..end;
+        start = end;
+        Some(game)
+    })
+}
+
+
+impl WinExpectancy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the model from events in game order, such as one or more seasons of cwevent output.
+    pub fn from_events(events: &[FullEvent]) -> Self {
+        let mut we = Self::new();
+        we.add_events(events);
+        we
+    }
+
+    /// Add the states from every game in events that ended with a winner.
+    pub fn add_events(&mut self, events: &[FullEvent]) {
+        let changes = state::replay(events);
+        for game in games(events) {
+            let last = game.end - 1;
+            let final_diff = score_diff_after(&events[last], &changes[last]);
+            if final_diff == 0 {
+                continue;
+            }
+            for idx in game {
+                self.add_state(Self::state_before(&events[idx], &changes[idx]), final_diff > 0);
+            }
+        }
+    }
+
+    fn add_state(&mut self, state: GameState, home_win: bool) {
+        self.states.entry(state).or_default().add(home_win);
+        self.situations.entry(state.situation()).or_default().add(home_win);
+        self.margins.entry(state.score_diff).or_default().add(home_win);
+    }
+
+    /// Combine totals with another model, for building a model over several seasons.
+    pub fn merge(&mut self, other: &WinExpectancy) {
+        for (state, totals) in &other.states {
+            let entry = self.states.entry(*state).or_default();
+            entry.wins += totals.wins;
+            entry.times += totals.times;
+        }
+        for (situation, totals) in &other.situations {
+            let entry = self.situations.entry(*situation).or_default();
+            entry.wins += totals.wins;
+            entry.times += totals.times;
+        }
+        for (margin, totals) in &other.margins {
+            let entry = self.margins.entry(*margin).or_default();
+            entry.wins += totals.wins;
+            entry.times += totals.times;
+        }
+    }
+
+    pub fn totals(&self, state: &GameState) -> Option<&WinTotals> {
+        self.states.get(state)
+    }
+
+    /// Home team's chance of winning from a state, smoothed toward broader states when the
+    /// state itself is rare.
+    pub fn win_probability(&self, state: &GameState) -> f64 {
+        let margin = WinTotals::smoothed(self.margins.get(&state.score_diff), 0.5);
+        let situation = WinTotals::smoothed(self.situations.get(&state.situation()), margin);
+        WinTotals::smoothed(self.states.get(state), situation)
+    }
+
+    fn state_before(event: &FullEvent, change: &StateChange) -> GameState {
+        GameState::new(event.inn_ct, home_batting(event), change.before.outs, change.before.bases_cd(), score_diff(event))
+    }
+
+    /// State after an event that didn't end the game. The third out moves on to the next half.
+    fn state_after(event: &FullEvent, change: &StateChange) -> GameState {
+        let score_diff = score_diff_after(event, change);
+        if change.after.inning_over() {
+            if home_batting(event) {
+                GameState::new(event.inn_ct.saturating_add(1), false, 0, 0, score_diff)
+            }
+            else {
+                GameState::new(event.inn_ct, true, 0, 0, score_diff)
+            }
+        }
+        else {
+            GameState::new(event.inn_ct, home_batting(event), change.after.outs, change.after.bases_cd(), score_diff)
+        }
+    }
+
+    /// Win probability added for every event, which must be in game order. The final event of
+    /// each game moves the win probability to the result, with ties counting as half a win.
+    pub fn event_values(&self, events: &[FullEvent]) -> Vec<EventWinValue> {
+        let changes = state::replay(events);
+        let mut values = Vec::with_capacity(events.len());
+        for game in games(events) {
+            let last = game.end - 1;
+            for idx in game {
+                let (event, change) = (&events[idx], &changes[idx]);
+                let before = self.win_probability(&Self::state_before(event, change));
+                let after = if idx == last {
+                    match score_diff_after(event, change) {
+                        diff if diff > 0 => 1.0,
+                        diff if diff < 0 => 0.0,
+                        _ => 0.5,
+                    }
+                }
+                else {
+                    self.win_probability(&Self::state_after(event, change))
+                };
+                let wpa = if home_batting(event) { after - before } else { before - after };
+                values.push(EventWinValue {
+                    game_id: event.game_id.clone(),
+                    event_id: event.event_id.clone(),
+                    bat_id: event.resp_bat_id.clone(),
+                    pit_id: event.resp_pit_id.clone(),
+                    win_expectancy: before as f32,
+                    wpa: wpa as f32,
+                });
+            }
+        }
+        values
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chadwick::events::test_event;
+
+    fn event(game_id: &str, inn: u8, home: bool, outs: u8, score: (u8, u8), dest: &str, event_outs: u8) -> FullEvent {
+        FullEvent {
+            away_score_ct: score.0,
+            home_score_ct: score.1,
+            ..test_event(game_id, &format!("{}{}", inn, outs), inn, home, outs, dest, event_outs)
+        }
+    }
+
+    /// Three up, three down in the top of the ninth, then a walk-off homer in the bottom.
+    fn walk_off(game_id: &str) -> Vec<FullEvent> {
+        vec![
+            event(game_id, 9, false, 0, (0, 0), "0", 1),
+            event(game_id, 9, false, 1, (0, 0), "0", 1),
+            event(game_id, 9, false, 2, (0, 0), "0", 1),
+            event(game_id, 9, true, 0, (0, 0), "4", 0),
+        ]
+    }
+
+    #[test]
+    fn smoothing() {
+        let we = WinExpectancy::from_events(&walk_off("TST202404010"));
+        let start = GameState::new(9, false, 0, 0, 0);
+        assert_eq!(we.totals(&start), Some(&WinTotals { wins: 1, times: 1 }));
+        // Pooled margins: 4 wins in 4, then shrunk toward a coin flip.
+        let margin = (4.0 + SMOOTHING_GAMES * 0.5) / (4.0 + SMOOTHING_GAMES);
+        let situation = (3.0 + SMOOTHING_GAMES * margin) / (3.0 + SMOOTHING_GAMES);
+        let expected = (1.0 + SMOOTHING_GAMES * situation) / (1.0 + SMOOTHING_GAMES);
+        assert!((we.win_probability(&start) - expected).abs() < 1e-9);
+        // States never seen fall back to the broader states.
+        let unseen = GameState::new(3, true, 1, 7, -4);
+        assert_eq!(we.win_probability(&unseen), 0.5);
+        assert_eq!(GameState::new(14, false, 0, 0, 25).inning, INNING_LIMIT);
+    }
+
+    #[test]
+    fn event_values() {
+        // The away team wins the second game with a home run leading off the ninth.
+        let mut events = walk_off("TST202404010");
+        events.extend(vec![
+            event("TST202404020", 9, false, 0, (0, 0), "4", 0),
+            event("TST202404020", 9, false, 0, (1, 0), "0", 1),
+            event("TST202404020", 9, false, 1, (1, 0), "0", 1),
+            event("TST202404020", 9, false, 2, (1, 0), "0", 1),
+            event("TST202404020", 9, true, 0, (1, 0), "0", 1),
+            event("TST202404020", 9, true, 1, (1, 0), "0", 1),
+            event("TST202404020", 9, true, 2, (1, 0), "0", 1),
+        ]);
+        let we = WinExpectancy::from_events(&events);
+        let values = we.event_values(&events[..4]);
+        assert_eq!(values.len(), 4);
+        // The away team's outs cost them win probability and the walk-off finishes the game.
+        assert!(values[0].wpa < 0.0);
+        assert_eq!(values[3].wpa, 1.0 - values[3].win_expectancy);
+        let values = we.event_values(&events);
+        assert_eq!(values.len(), 11);
+        assert!(values[4].wpa > 0.0);
+        assert_eq!(values[10].wpa, -values[10].win_expectancy);
+        // Adding up the home team's WPA in the first game goes from the starting win expectancy
+        // to the win.
+        let home_total: f32 = values[..4].iter()
+            .zip(events.iter())
+            .map(|(value, event)| if home_batting(event) { value.wpa } else { -value.wpa })
+            .sum();
+        assert!((values[0].win_expectancy + home_total - 1.0).abs() < 1e-5);
+    }
+}
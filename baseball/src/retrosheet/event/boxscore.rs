@@ -0,0 +1,366 @@
+//! Build player box scores by adding up the events of a game, the same lines cwbox reports.
+
+use std::collections::HashMap;
+
+use crate::chadwick::events::{ExtendedEvent, FullEvent};
+use crate::chadwick::gamelogs::{BattingGamelog, FieldingGamelog, PitchingGamelog, PlayerGameLogs};
+use crate::retrosheet::event::EventGame;
+
+
+// Event codes that put the ball in play.
+const IN_PLAY_EVENTS: [u8; 7] = [2, 18, 19, 20, 21, 22, 23];
+
+
+/// Box score for a single game, built up one event at a time.
+pub struct BoxScore {
+    game_id: String,
+    batting: Vec<BattingGamelog>,
+    batting_idx: HashMap<(String, String), usize>,
+    positions: HashMap<(String, String), Vec<u8>>,
+    fielding: Vec<FieldingGamelog>,
+    fielding_idx: HashMap<(String, u8), usize>,
+    pitching: Vec<PitchingGamelog>,
+    pitching_idx: HashMap<String, usize>,
+    /// Pitches already counted in the current plate appearance.
+    pa_pitches: usize,
+    /// Earned runs from the events, used when the game has no "data,er" records.
+    earned_runs: HashMap<String, u8>,
+}
+
+
+impl BoxScore {
+    pub fn new(game_id: &str) -> Self {
+        Self {
+            game_id: game_id.to_string(),
+            batting: Vec::new(),
+            batting_idx: HashMap::new(),
+            positions: HashMap::new(),
+            fielding: Vec::new(),
+            fielding_idx: HashMap::new(),
+            pitching: Vec::new(),
+            pitching_idx: HashMap::new(),
+            pa_pitches: 0,
+            earned_runs: HashMap::new(),
+        }
+    }
+
+    pub fn from_game(game: &EventGame) -> Self {
+        let mut boxscore = Self::new(&game.game_id);
+        for (event, extended) in &game.events {
+            boxscore.add_event(event, extended);
+        }
+        boxscore.finish_pitching(game);
+        boxscore
+    }
+
+    fn batter(&mut self, player_id: &str, team_id: &str) -> &mut BattingGamelog {
+        let key = (player_id.to_string(), team_id.to_string());
+        let idx = match self.batting_idx.get(&key) {
+            Some(idx) => *idx,
+            None => {
+                self.batting.push(BattingGamelog {
+                    player_id: player_id.to_string(),
+                    game_id: self.game_id.clone(),
+                    team_id: team_id.to_string(),
+                    ..Default::default()
+                });
+                self.batting_idx.insert(key, self.batting.len() - 1);
+                self.batting.len() - 1
+            }
+        };
+        &mut self.batting[idx]
+    }
+
+    fn add_position(&mut self, player_id: &str, team_id: &str, pos: u8) {
+        if player_id.is_empty() || pos == 0 {
+            return;
+        }
+        let positions = self.positions.entry((player_id.to_string(), team_id.to_string())).or_default();
+        if positions.last() != Some(&pos) {
+            positions.push(pos);
+        }
+    }
+
+    fn fielder(&mut self, player_id: &str, team_id: &str, pos: u8) -> &mut FieldingGamelog {
+        let key = (player_id.to_string(), pos);
+        let idx = match self.fielding_idx.get(&key) {
+            Some(idx) => *idx,
+            None => {
+                self.fielding.push(FieldingGamelog {
+                    player_id: player_id.to_string(),
+                    game_id: self.game_id.clone(),
+                    team_id: team_id.to_string(),
+                    pos,
+                    ..Default::default()
+                });
+                self.fielding_idx.insert(key, self.fielding.len() - 1);
+                self.fielding.len() - 1
+            }
+        };
+        &mut self.fielding[idx]
+    }
+
+    fn pitcher(&mut self, player_id: &str, team_id: &str) -> &mut PitchingGamelog {
+        let idx = match self.pitching_idx.get(player_id) {
+            Some(idx) => *idx,
+            None => {
+                self.pitching.push(PitchingGamelog {
+                    player_id: player_id.to_string(),
+                    game_id: self.game_id.clone(),
+                    team_id: team_id.to_string(),
+                    ..Default::default()
+                });
+                self.pitching_idx.insert(player_id.to_string(), self.pitching.len() - 1);
+                self.pitching.len() - 1
+            }
+        };
+        &mut self.pitching[idx]
+    }
+
+    pub fn add_event(&mut self, event: &FullEvent, extended: &ExtendedEvent) {
+        let bat_team = extended.bat_team_id.as_str();
+        let fld_team = extended.fld_team_id.as_str();
+        self.add_batting(event, bat_team);
+        self.add_fielding(event, fld_team);
+        self.add_pitching(event, extended, fld_team);
+    }
+
+    fn add_batting(&mut self, event: &FullEvent, team: &str) {
+        // Anyone who bats or runs is in the lineup, even when nothing happens to them.
+        self.batter(&event.bat_id, team);
+        self.add_position(&event.bat_id, team, event.bat_fld_cd);
+        for runner in [&event.base1_run_id, &event.base2_run_id, &event.base3_run_id] {
+            if !runner.is_empty() {
+                self.batter(runner, team);
+            }
+        }
+        let pinch_runners = [
+            (event.pr_run1_fl, &event.removed_for_pr_run1_id),
+            (event.pr_run2_fl, &event.removed_for_pr_run2_id),
+            (event.pr_run3_fl, &event.removed_for_pr_run3_id),
+        ];
+        for (pinch_runner, replaced) in pinch_runners {
+            if pinch_runner && !replaced.is_empty() {
+                self.batter(replaced, team);
+            }
+        }
+
+        if event.bat_event_fl {
+            let two_outs = event.outs_ct == 2;
+            let batting = self.batter(&event.resp_bat_id, team);
+            batting.ab += u8::from(event.ab_fl);
+            batting.h += u8::from(event.h_cd > 0);
+            batting.d += u8::from(event.h_cd == 2);
+            batting.t += u8::from(event.h_cd == 3);
+            batting.hr += u8::from(event.h_cd == 4);
+            batting.rbi += event.rbi_ct;
+            if two_outs {
+                batting.rbi2out += event.rbi_ct;
+            }
+            batting.bb += u8::from(matches!(event.event_cd, 14 | 15));
+            batting.ibb += u8::from(event.event_cd == 15);
+            batting.so += u8::from(event.event_cd == 3);
+            batting.gidp += u8::from(event.dp_fl && event.battedball_cd == "G");
+            batting.hbp += u8::from(event.event_cd == 16);
+            batting.sh += u8::from(event.sh_fl);
+            batting.sf += u8::from(event.sf_fl);
+            batting.pa = batting.ab + batting.bb + batting.hbp + batting.sf + batting.sh;
+        }
+
+        if scored(&event.bat_dest_id) {
+            self.batter(&event.bat_id, team).r += 1;
+        }
+        let runners = [
+            (&event.base1_run_id, &event.run1_dest_id, event.run1_sb_fl, event.run1_cs_fl),
+            (&event.base2_run_id, &event.run2_dest_id, event.run2_sb_fl, event.run2_cs_fl),
+            (&event.base3_run_id, &event.run3_dest_id, event.run3_sb_fl, event.run3_cs_fl),
+        ];
+        for (runner, dest, sb, cs) in runners {
+            if runner.is_empty() {
+                continue;
+            }
+            let batting = self.batter(runner, team);
+            batting.r += u8::from(scored(dest));
+            batting.sb += u8::from(sb);
+            batting.cs += u8::from(cs);
+        }
+    }
+
+    fn add_fielding(&mut self, event: &FullEvent, team: &str) {
+        let fielders = [
+            &event.pit_id,
+            &event.pos2_fld_id,
+            &event.pos3_fld_id,
+            &event.pos4_fld_id,
+            &event.pos5_fld_id,
+            &event.pos6_fld_id,
+            &event.pos7_fld_id,
+            &event.pos8_fld_id,
+            &event.pos9_fld_id,
+        ];
+        let putouts = [event.po1_fld_cd, event.po2_fld_cd, event.po3_fld_cd];
+        let assists = [
+            event.ass1_fld_cd,
+            event.ass2_fld_cd,
+            event.ass3_fld_cd,
+            event.ass4_fld_cd,
+            event.ass5_fld_cd,
+        ];
+        let errors = [event.err1_fld_cd, event.err2_fld_cd, event.err3_fld_cd];
+        let in_play = event.bat_event_fl && IN_PLAY_EVENTS.contains(&event.event_cd);
+
+        for (idx, player_id) in fielders.iter().enumerate() {
+            if player_id.is_empty() {
+                continue;
+            }
+            let pos = idx as u8 + 1;
+            self.add_position(player_id, team, pos);
+            let fielding = self.fielder(player_id, team, pos);
+            fielding.o += event.event_outs_ct;
+            fielding.bf += u8::from(event.bat_event_fl);
+            fielding.bip += u8::from(in_play);
+            let po = putouts.iter().filter(|fld| **fld == pos).count() as u8;
+            let a = u8::from(assists.contains(&pos));
+            fielding.po += po;
+            fielding.a += a;
+            fielding.e += errors.iter().filter(|fld| **fld == pos).count() as u8;
+            if po > 0 || a > 0 {
+                fielding.dp += u8::from(event.dp_fl);
+                fielding.tp += u8::from(event.tp_fl);
+            }
+        }
+    }
+
+    fn add_pitching(&mut self, event: &FullEvent, extended: &ExtendedEvent, team: &str) {
+        if extended.pa_new_fl {
+            self.pa_pitches = 0;
+        }
+        let pitches: Vec<char> = event.pitch_seq_tx.chars().filter(|pitch| is_pitch(*pitch)).collect();
+        let new_pitches = &pitches[self.pa_pitches.min(pitches.len())..];
+        self.pa_pitches = pitches.len();
+
+        let pitching = self.pitcher(&event.pit_id, team);
+        pitching.ipouts += event.event_outs_ct;
+        pitching.wp += u8::from(event.wp_fl);
+        pitching.bk += u8::from(event.event_cd == 11);
+        let strikes = new_pitches.iter().filter(|pitch| is_strike(**pitch)).count();
+        pitching.p = pitching.p.saturating_add(u8::try_from(new_pitches.len()).unwrap_or(u8::MAX));
+        pitching.s = pitching.s.saturating_add(u8::try_from(strikes).unwrap_or(u8::MAX));
+
+        if event.bat_event_fl {
+            let pitching = self.pitcher(&event.resp_pit_id, team);
+            pitching.bf += 1;
+            pitching.ab += u8::from(event.ab_fl);
+            pitching.h += u8::from(event.h_cd > 0);
+            pitching.hr += u8::from(event.h_cd == 4);
+            pitching.bb += u8::from(matches!(event.event_cd, 14 | 15));
+            pitching.ibb += u8::from(event.event_cd == 15);
+            pitching.so += u8::from(event.event_cd == 3);
+            pitching.hbp += u8::from(event.event_cd == 16);
+            if IN_PLAY_EVENTS.contains(&event.event_cd) {
+                match event.battedball_cd.as_str() {
+                    "G" => pitching.gb += 1,
+                    "F" | "P" => pitching.fb += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        let scorers = [
+            (&event.bat_dest_id, &event.resp_pit_id),
+            (&event.run1_dest_id, &event.run1_resp_pit_id),
+            (&event.run2_dest_id, &event.run2_resp_pit_id),
+            (&event.run3_dest_id, &event.run3_resp_pit_id),
+        ];
+        for (dest, resp_pit) in scorers {
+            if !scored(dest) || resp_pit.is_empty() {
+                continue;
+            }
+            let pitching = self.pitcher(resp_pit, team);
+            pitching.r += 1;
+            // Team unearned runs are still earned runs for the pitcher.
+            if dest != "5" {
+                *self.earned_runs.entry(resp_pit.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Fill in what can only be known once the game is over: starts, finishes, earned runs and
+    /// decisions.
+    fn finish_pitching(&mut self, game: &EventGame) {
+        let mut team_pitchers: HashMap<String, Vec<usize>> = HashMap::new();
+        for (idx, pitching) in self.pitching.iter().enumerate() {
+            team_pitchers.entry(pitching.team_id.clone()).or_default().push(idx);
+        }
+
+        for pitchers in team_pitchers.values() {
+            let team_runs: u8 = pitchers.iter().map(|idx| self.pitching[*idx].r).sum();
+            let complete_game = pitchers.len() == 1;
+            for (order, idx) in pitchers.iter().enumerate() {
+                let pitching = &mut self.pitching[*idx];
+                pitching.gs = order == 0;
+                pitching.cg = complete_game;
+                pitching.sho = complete_game && team_runs == 0;
+                pitching.gf = order > 0 && order == pitchers.len() - 1;
+            }
+        }
+
+        for pitching in &mut self.pitching {
+            pitching.er = game.earned_runs.get(&pitching.player_id)
+                .or_else(|| self.earned_runs.get(&pitching.player_id))
+                .copied()
+                .unwrap_or_default();
+            let decision = [("wp", "W"), ("lp", "L"), ("save", "S")]
+                .iter()
+                .find(|(info, _)| game.info.get(*info) == Some(&pitching.player_id))
+                .map(|(_, decision)| decision.to_string());
+            pitching.decision = decision.unwrap_or_default();
+        }
+    }
+
+    pub fn into_gamelogs(self) -> PlayerGameLogs {
+        let mut batting = self.batting;
+        for gamelog in &mut batting {
+            let key = (gamelog.player_id.clone(), gamelog.team_id.clone());
+            if let Some(positions) = self.positions.get(&key) {
+                let positions: Vec<String> = positions.iter().map(|pos| pos.to_string()).collect();
+                gamelog.pos = positions.join("-");
+            }
+        }
+        (batting, self.fielding, self.pitching)
+    }
+}
+
+
+fn scored(dest: &str) -> bool {
+    matches!(dest, "4" | "5" | "6")
+}
+
+
+fn is_pitch(pitch: char) -> bool {
+    matches!(pitch, 'A' | 'B' | 'C' | 'F' | 'H' | 'I' | 'K' | 'L' | 'M' | 'O' | 'P' | 'Q' | 'R' | 'S' | 'T' | 'U' | 'V' | 'X' | 'Y')
+}
+
+
+fn is_strike(pitch: char) -> bool {
+    matches!(pitch, 'A' | 'C' | 'F' | 'K' | 'L' | 'M' | 'O' | 'Q' | 'R' | 'S' | 'T' | 'X' | 'Y')
+}
+
+
+/// Build the batting, fielding and pitching lines for every game.
+pub fn gamelogs_from_games<'a, I>(games: I) -> PlayerGameLogs
+    where I: IntoIterator<Item = &'a EventGame>
+{
+    let mut batting_gamelogs = Vec::new();
+    let mut fielding_gamelogs = Vec::new();
+    let mut pitching_gamelogs = Vec::new();
+
+    for game in games {
+        let (batting, fielding, pitching) = BoxScore::from_game(game).into_gamelogs();
+        batting_gamelogs.extend(batting);
+        fielding_gamelogs.extend(fielding);
+        pitching_gamelogs.extend(pitching);
+    }
+
+    (batting_gamelogs, fielding_gamelogs, pitching_gamelogs)
+}
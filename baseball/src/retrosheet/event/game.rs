@@ -0,0 +1,832 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::default::Default;
+
+use csv::StringRecord;
+
+use crate::chadwick::events::{ExtendedEvent, FullEvent, Handedness};
+use crate::chadwick::pitches::{PitchKind, PitchSequence};
+use crate::retrosheet::event::{CombinedEvent, EventGame};
+use crate::retrosheet::event::play::{self, PlayEffect};
+use crate::retrosheet::event::roster::Roster;
+
+
+#[derive(Clone, Default)]
+struct Player {
+    id: String,
+    pos: u8,
+}
+
+
+#[derive(Clone)]
+struct Runner {
+    id: String,
+    resp_pit: String,
+    resp_cat: String,
+    origin_event: u32,
+    /// Follows the runner through pinch runners so fates can be filled in at the end of the half.
+    token: usize,
+}
+
+
+#[derive(Default)]
+struct Team {
+    /// Batting order. Slot 0 holds the pitcher when a DH is used.
+    lineup: [Player; 10],
+    /// Player at each fielding position, 1-9.
+    fielders: [String; 10],
+    starters: HashSet<String>,
+    /// Who a pinch hitter in each slot replaced.
+    ph_replaced: [Option<Player>; 10],
+    score: u8,
+    pa_ct: u8,
+}
+
+
+impl Team {
+    fn find(&self, id: &str) -> Option<(usize, &Player)> {
+        self.lineup.iter()
+            .enumerate()
+            .find(|(_slot, player)| player.id == id)
+    }
+}
+
+
+struct PendingEvent {
+    full: FullEvent,
+    extended: ExtendedEvent,
+    bat_token: Option<usize>,
+    run_tokens: [Option<usize>; 4],
+    runs: u8,
+}
+
+
+#[derive(Default)]
+struct PitchCounts {
+    called_ball: u8,
+    intent_ball: u8,
+    pitchout_ball: u8,
+    hitbatter_ball: u8,
+    other_ball: u8,
+    called_strike: u8,
+    swingmiss_strike: u8,
+    foul_strike: u8,
+    inplay_strike: u8,
+    other_strike: u8,
+}
+
+
+impl PitchCounts {
+    fn from_sequence(sequence: &PitchSequence) -> Self {
+        let mut counts = PitchCounts::default();
+        for pitch in sequence {
+            match pitch.kind {
+                PitchKind::Ball => counts.called_ball += 1,
+                PitchKind::IntentionalBall => counts.intent_ball += 1,
+                PitchKind::Pitchout => counts.pitchout_ball += 1,
+                PitchKind::HitBatter => counts.hitbatter_ball += 1,
+                PitchKind::AutomaticBall => counts.other_ball += 1,
+                PitchKind::CalledStrike => counts.called_strike += 1,
+                PitchKind::SwingingStrike | PitchKind::MissedBunt | PitchKind::SwingingPitchout => {
+                    counts.swingmiss_strike += 1
+                }
+                PitchKind::Foul | PitchKind::FoulBunt | PitchKind::FoulTipBunt | PitchKind::FoulPitchout |
+                PitchKind::FoulTip => counts.foul_strike += 1,
+                PitchKind::InPlay | PitchKind::InPlayPitchout => counts.inplay_strike += 1,
+                PitchKind::Strike | PitchKind::AutomaticStrike => counts.other_strike += 1,
+                PitchKind::NoPitch | PitchKind::Unknown => {}
+            }
+        }
+        counts
+    }
+
+    fn balls(&self) -> u8 {
+        self.called_ball + self.intent_ball + self.pitchout_ball + self.hitbatter_ball + self.other_ball
+    }
+
+    fn strikes(&self) -> u8 {
+        self.called_strike + self.swingmiss_strike + self.foul_strike + self.inplay_strike + self.other_strike
+    }
+}
+
+
+/// Balls and strikes from the count field of a play record, falling back to the pitches when
+/// the count isn't known.
+fn parse_count(count: &str, sequence: &PitchSequence) -> (u8, u8) {
+    let mut chars = count.chars();
+    if let (Some(balls), Some(strikes)) = (chars.next().and_then(|c| c.to_digit(10)), chars.next().and_then(|c| c.to_digit(10))) {
+        return (balls as u8, strikes as u8);
+    }
+    sequence.final_count()
+}
+
+
+fn bases_cd(bases: &[Option<Runner>; 4]) -> u8 {
+    (1..=3).filter(|base| bases[*base].is_some())
+        .map(|base| 1 << (base - 1))
+        .sum()
+}
+
+
+/// Replays the records of a single game, producing the same events as cwevent.
+pub struct GameState<'r> {
+    roster: &'r Roster,
+    game_id: String,
+    away_team: String,
+    home_team: String,
+    htbf: bool,
+    teams: [Team; 2],
+    /// Inning and batting team of the current half inning.
+    half: Option<(u8, usize)>,
+    outs: u8,
+    bases: [Option<Runner>; 4],
+    inn_runs: u8,
+    inn_pa: u8,
+    start_scores: [u8; 2],
+    next_event_id: u32,
+    next_token: usize,
+    fates: HashMap<usize, u8>,
+    half_events: Vec<PendingEvent>,
+    events: Vec<CombinedEvent>,
+    /// Batter of the last play record and the count at that point, for substitutions made in
+    /// the middle of a plate appearance.
+    pa_batter: Option<String>,
+    pa_count: Option<(u8, u8)>,
+    prev_batter: Option<(String, (u8, u8))>,
+    prev_pitcher: Option<(String, (u8, u8))>,
+    /// Batter of the last event if the plate appearance hasn't finished.
+    pa_event_batter: Option<String>,
+    bat_hand_adj: Option<(String, Handedness)>,
+    pit_hand_adj: Option<(String, Handedness)>,
+    pending_runners: Vec<(String, usize)>,
+    info: HashMap<String, String>,
+    earned_runs: HashMap<String, u8>,
+}
+
+
+impl<'r> GameState<'r> {
+    pub fn new(game_id: &str, roster: &'r Roster) -> Self {
+        GameState {
+            roster,
+            game_id: game_id.to_string(),
+            away_team: String::new(),
+            home_team: String::new(),
+            htbf: false,
+            teams: Default::default(),
+            half: None,
+            outs: 0,
+            bases: Default::default(),
+            inn_runs: 0,
+            inn_pa: 0,
+            start_scores: [0, 0],
+            next_event_id: 1,
+            next_token: 0,
+            fates: HashMap::new(),
+            half_events: Vec::new(),
+            events: Vec::new(),
+            pa_batter: None,
+            pa_count: None,
+            prev_batter: None,
+            prev_pitcher: None,
+            pa_event_batter: None,
+            bat_hand_adj: None,
+            pit_hand_adj: None,
+            pending_runners: Vec::new(),
+            info: HashMap::new(),
+            earned_runs: HashMap::new(),
+        }
+    }
+
+    /// Apply one record of the game, everything after its id record.
+    pub fn apply(&mut self, record: &StringRecord) -> Result<(), String> {
+        let field = |idx: usize| record.get(idx).unwrap_or("").trim();
+        match field(0) {
+            "version" | "com" | "ladj" => {}
+            "info" => {
+                match field(1) {
+                    "visteam" => self.away_team = field(2).to_string(),
+                    "hometeam" => self.home_team = field(2).to_string(),
+                    "htbf" => self.htbf = field(2) == "true",
+                    _ => {}
+                }
+                self.info.insert(field(1).to_string(), field(2).to_string());
+            }
+            "data" => {
+                if field(1) == "er" {
+                    let earned_runs = field(3).parse()
+                        .map_err(|_| format!("bad earned run count \"{}\"", field(3)))?;
+                    self.earned_runs.insert(field(2).to_string(), earned_runs);
+                }
+            }
+            "start" | "sub" => {
+                let team = parse_team(field(3))?;
+                let slot: usize = field(4).parse()
+                    .ok()
+                    .filter(|slot| *slot <= 9)
+                    .ok_or_else(|| format!("bad batting order slot \"{}\"", field(4)))?;
+                let pos: u8 = field(5).parse()
+                    .ok()
+                    .filter(|pos| (1..=12).contains(pos))
+                    .ok_or_else(|| format!("bad position \"{}\"", field(5)))?;
+                self.substitute(field(1), team, slot, pos, field(0) == "start");
+            }
+            "play" => {
+                if record.len() < 7 {
+                    return Err("play record is missing fields".to_string());
+                }
+                let inning: u8 = field(1).parse().map_err(|_| format!("bad inning \"{}\"", field(1)))?;
+                let team = parse_team(field(2))?;
+                self.play(inning, team, field(3), field(4), field(5), field(6))?;
+            }
+            "badj" => {
+                self.bat_hand_adj = Some((field(1).to_string(), Handedness::from_code(field(2))));
+            }
+            "padj" => {
+                self.pit_hand_adj = Some((field(1).to_string(), Handedness::from_code(field(2))));
+            }
+            "radj" | "presadj" => {
+                let base: usize = field(2).parse()
+                    .ok()
+                    .filter(|base| (1..=3).contains(base))
+                    .ok_or_else(|| format!("bad base \"{}\"", field(2)))?;
+                self.pending_runners.push((field(1).to_string(), base));
+            }
+            other => return Err(format!("unknown record type \"{}\"", other)),
+        }
+        Ok(())
+    }
+
+    /// Close out the game and return its events.
+    pub fn finish(mut self) -> EventGame {
+        self.end_half();
+        if let Some((last, _)) = self.events.last_mut() {
+            last.game_end_fl = true;
+        }
+        EventGame {
+            game_id: self.game_id,
+            info: self.info,
+            earned_runs: self.earned_runs,
+            events: self.events,
+        }
+    }
+
+    fn substitute(&mut self, id: &str, team: usize, slot: usize, pos: u8, start: bool) {
+        if start {
+            self.teams[team].starters.insert(id.to_string());
+        }
+
+        let batting = self.half.map(|(_, bat_team)| bat_team);
+        let old = self.teams[team].lineup[slot].clone();
+        match pos {
+            11 => {
+                if batting == Some(team) && self.pa_batter.as_deref() == Some(old.id.as_str()) {
+                    if let Some(count) = self.pa_count {
+                        self.prev_batter = Some((old.id.clone(), count));
+                    }
+                    self.pa_batter = Some(id.to_string());
+                }
+                self.teams[team].ph_replaced[slot] = Some(old);
+            }
+            12 => {
+                let base = (1..=3).find(|base| {
+                    self.bases[*base].as_ref().is_some_and(|runner| runner.id == old.id)
+                });
+                if let Some(base) = base {
+                    if let Some(runner) = self.bases[base].as_mut() {
+                        runner.id = id.to_string();
+                    }
+                    if let Some(last) = self.half_events.last_mut() {
+                        let full = &mut last.full;
+                        match base {
+                            1 => { full.pr_run1_fl = true; full.removed_for_pr_run1_id = old.id.clone(); }
+                            2 => { full.pr_run2_fl = true; full.removed_for_pr_run2_id = old.id.clone(); }
+                            _ => { full.pr_run3_fl = true; full.removed_for_pr_run3_id = old.id.clone(); }
+                        }
+                    }
+                }
+            }
+            1..=9 => {
+                let fielding = batting.map(|bat_team| 1 - bat_team);
+                let pitcher = &self.teams[team].fielders[1];
+                if pos == 1 && fielding == Some(team) && pitcher != id && self.prev_pitcher.is_none()
+                    && let Some(count) = self.pa_count
+                {
+                    self.prev_pitcher = Some((pitcher.clone(), count));
+                }
+                let fielders = &mut self.teams[team].fielders;
+                for fielder in fielders.iter_mut().skip(1) {
+                    if fielder == id {
+                        fielder.clear();
+                    }
+                }
+                fielders[pos as usize] = id.to_string();
+            }
+            _ => {}
+        }
+
+        self.teams[team].lineup[slot] = Player { id: id.to_string(), pos };
+    }
+
+    fn start_half(&mut self, inning: u8, team: usize) {
+        self.half = Some((inning, team));
+        self.outs = 0;
+        self.bases = Default::default();
+        self.inn_runs = 0;
+        self.inn_pa = 0;
+        self.start_scores = [self.teams[0].score, self.teams[1].score];
+        self.fates.clear();
+        self.pa_batter = None;
+        self.pa_count = None;
+        self.prev_batter = None;
+        self.prev_pitcher = None;
+        self.pa_event_batter = None;
+    }
+
+    fn end_half(&mut self) {
+        let total_runs: u8 = self.half_events.iter().map(|event| event.runs).sum();
+        let mut runs_so_far = 0;
+        let last_idx = self.half_events.len().saturating_sub(1);
+        for (idx, mut event) in self.half_events.drain(..).enumerate() {
+            runs_so_far += event.runs;
+            let fate = |token: Option<usize>| {
+                token.and_then(|token| self.fates.get(&token))
+                    .copied()
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            event.extended.bat_fate_id = fate(event.bat_token);
+            event.extended.run1_fate_id = fate(event.run_tokens[1]);
+            event.extended.run2_fate_id = fate(event.run_tokens[2]);
+            event.extended.run3_fate_id = fate(event.run_tokens[3]);
+            event.extended.fate_runs_ct = total_runs - runs_so_far;
+            if idx == last_idx {
+                event.extended.inn_end_fl = true;
+                event.extended.pa_trunc_fl = !event.full.bat_event_fl;
+            }
+            self.events.push((event.full, event.extended));
+        }
+    }
+
+    fn place_pending_runners(&mut self) {
+        let Some((_, bat_team)) = self.half
+        else {
+            return;
+        };
+        let fielders = &self.teams[1 - bat_team].fielders;
+        let (pitcher, catcher) = (fielders[1].clone(), fielders[2].clone());
+        for (id, base) in std::mem::take(&mut self.pending_runners) {
+            let token = self.next_token;
+            self.next_token += 1;
+            self.fates.insert(token, base as u8);
+            self.bases[base] = Some(Runner {
+                id,
+                resp_pit: pitcher.clone(),
+                resp_cat: catcher.clone(),
+                origin_event: 0,
+                token,
+            });
+        }
+    }
+
+    fn pitcher_hand(&self, id: &str) -> Handedness {
+        match &self.pit_hand_adj {
+            Some((adj_id, hand)) if adj_id == id => *hand,
+            _ => self.roster.throws(id),
+        }
+    }
+
+    fn batter_hand(&self, id: &str, pitcher_hand: Handedness) -> Handedness {
+        let hand = match &self.bat_hand_adj {
+            Some((adj_id, hand)) if adj_id == id => *hand,
+            _ => self.roster.bats(id),
+        };
+        match (hand, pitcher_hand) {
+            (Handedness::Both, Handedness::Left) => Handedness::Right,
+            (Handedness::Both, Handedness::Right) => Handedness::Left,
+            (hand, _) => hand,
+        }
+    }
+
+    fn play(&mut self, inning: u8, team: usize, batter: &str, count: &str, pitches: &str, event_tx: &str) -> Result<(), String> {
+        if self.half != Some((inning, team)) {
+            self.end_half();
+            self.start_half(inning, team);
+        }
+        if !self.pending_runners.is_empty() {
+            self.place_pending_runners();
+        }
+
+        if self.pa_batter.as_deref() != Some(batter) {
+            self.pa_batter = Some(batter.to_string());
+            self.prev_batter = None;
+            self.prev_pitcher = None;
+        }
+        let sequence = PitchSequence::parse(pitches).map_err(|e| e.to_string())?;
+        self.pa_count = Some(parse_count(count, &sequence));
+
+        let effect = play::parse_play(event_tx)?;
+        if effect.event_cd == play::EVENT_NONE {
+            return Ok(());
+        }
+
+        let pending = self.build_event(inning, team, batter, count, pitches, &sequence, event_tx, &effect)?;
+        self.half_events.push(pending);
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_event(&mut self, inning: u8, bat_team: usize, batter: &str, count: &str, pitches: &str, sequence: &PitchSequence, event_tx: &str, effect: &PlayEffect) -> Result<PendingEvent, String> {
+        let fld_team = 1 - bat_team;
+        let event_id = self.next_event_id;
+
+        for base in 1..=3 {
+            if effect.moves[base].dest.is_some() && self.bases[base].is_none() {
+                return Err(format!("no runner on base {} for \"{}\"", base, event_tx));
+            }
+        }
+
+        // Work out where everyone ends up, filling in what the play leaves implicit.
+        let mut dest: [Option<u8>; 4] = [None, None, None, None];
+        for (idx, movement) in effect.moves.iter().enumerate() {
+            dest[idx] = movement.dest;
+        }
+        if effect.force_runners && self.bases[1].is_some() && dest[1].is_none() {
+            dest[1] = Some(2);
+            if self.bases[2].is_some() && dest[2].is_none() {
+                dest[2] = Some(3);
+                if self.bases[3].is_some() && dest[3].is_none() {
+                    dest[3] = Some(play::DEST_SCORED);
+                }
+            }
+        }
+        for (base, to) in dest.iter_mut().enumerate().skip(1) {
+            if self.bases[base].is_some() && to.is_none() {
+                *to = Some(if effect.clear_bases { play::DEST_SCORED } else { base as u8 });
+            }
+        }
+        if effect.batter_event {
+            dest[0] = Some(dest[0].unwrap_or(play::DEST_OUT));
+        }
+        else {
+            dest[0] = None;
+        }
+
+        let fielders = self.teams[fld_team].fielders.clone();
+        let pitcher = fielders[1].clone();
+        let catcher = fielders[2].clone();
+
+        // Batters reaching on a force take over the pitcher responsibility of the runner put out.
+        let mut batter_resp_pit = pitcher.clone();
+        if matches!(effect.event_cd, play::EVENT_GENERIC_OUT | play::EVENT_FIELDERS_CHOICE)
+            && dest[0].is_some_and(|d| d != play::DEST_OUT)
+            && let Some(base) = (1..=3).rev().find(|base| self.bases[*base].is_some() && dest[*base] == Some(play::DEST_OUT))
+            && let Some(runner) = &self.bases[base]
+        {
+            batter_resp_pit = runner.resp_pit.clone();
+        }
+
+        let mut new_bases: [Option<Runner>; 4] = Default::default();
+        let mut outs = 0;
+        let mut runs = 0;
+        let mut rbi = 0;
+        let mut dest_codes = [0u8; 4];
+        let mut bat_token = None;
+        let mut run_tokens = [None; 4];
+        let rbi_default = effect.batter_event
+            && !effect.dp
+            && !matches!(effect.event_cd, play::EVENT_ERROR | play::EVENT_STRIKEOUT);
+        for idx in 0..4 {
+            let Some(to) = dest[idx]
+            else {
+                continue;
+            };
+            let runner = if idx == 0 {
+                let token = self.next_token;
+                self.next_token += 1;
+                bat_token = Some(token);
+                Runner {
+                    id: batter.to_string(),
+                    resp_pit: batter_resp_pit.clone(),
+                    resp_cat: catcher.clone(),
+                    origin_event: event_id,
+                    token,
+                }
+            }
+            else {
+                let runner = self.bases[idx].clone().expect("runner checked above");
+                run_tokens[idx] = Some(runner.token);
+                runner
+            };
+
+            let movement = &effect.moves[idx];
+            dest_codes[idx] = match to {
+                play::DEST_SCORED => movement.unearned.unwrap_or(play::DEST_SCORED),
+                to => to,
+            };
+            self.fates.insert(runner.token, dest_codes[idx]);
+            match to {
+                play::DEST_OUT => outs += 1,
+                1..=3 => {
+                    if new_bases[to as usize].is_some() {
+                        return Err(format!("two runners end up on base {} in \"{}\"", to, event_tx));
+                    }
+                    new_bases[to as usize] = Some(runner);
+                }
+                _ => {
+                    runs += 1;
+                    if movement.rbi.unwrap_or(rbi_default) {
+                        rbi += 1;
+                    }
+                }
+            }
+        }
+        if self.outs + outs > 3 {
+            return Err(format!("more than three outs after \"{}\"", event_tx));
+        }
+
+        let (slot, bat_player) = self.teams[bat_team].find(batter)
+            .map(|(slot, player)| (slot, player.clone()))
+            .unwrap_or_default();
+        let ph_fl = bat_player.pos == 11;
+        let removed_for_ph = if ph_fl { self.teams[bat_team].ph_replaced[slot].clone() } else { None };
+
+        let walk = matches!(effect.event_cd, play::EVENT_WALK | play::EVENT_INTENTIONAL_WALK);
+        let resp_pit = match &self.prev_pitcher {
+            Some((prev, (balls, strikes))) if walk && *balls >= 2 && (*balls, *strikes) != (2, 2) => prev.clone(),
+            _ => pitcher.clone(),
+        };
+        let resp_bat = match &self.prev_batter {
+            Some((prev, (_, 2))) if effect.event_cd == play::EVENT_STRIKEOUT => prev.clone(),
+            _ => batter.to_string(),
+        };
+        let pit_hand = self.pitcher_hand(&pitcher);
+        let resp_pit_hand = self.pitcher_hand(&resp_pit);
+        let bat_hand = self.batter_hand(batter, pit_hand);
+        let resp_bat_hand = self.batter_hand(&resp_bat, pit_hand);
+
+        let mut chars = count.chars();
+        let balls_ct = chars.next().and_then(|c| c.to_digit(10)).unwrap_or(0) as u8;
+        let strikes_ct = chars.next().and_then(|c| c.to_digit(10)).unwrap_or(0) as u8;
+
+        let runner_id = |base: usize| self.bases[base].as_ref().map(|r| r.id.clone()).unwrap_or_default();
+        let runner_resp_pit = |base: usize| self.bases[base].as_ref().map(|r| r.resp_pit.clone()).unwrap_or_default();
+        let lineup_slot = |offset: usize| {
+            if slot == 0 {
+                String::new()
+            }
+            else {
+                self.teams[bat_team].lineup[(slot - 1 + offset) % 9 + 1].id.clone()
+            }
+        };
+        let putout = |idx: usize| effect.putouts.get(idx).copied().unwrap_or_default();
+        let assist = |idx: usize| effect.assists.get(idx).copied().unwrap_or_default();
+        let error = |idx: usize| effect.errors.get(idx).copied().unwrap_or((0, 'N'));
+        let out_at = |idx: usize| dest[idx] == Some(play::DEST_OUT);
+        let ab_fl = effect.batter_event && !effect.sh && !effect.sf && !matches!(
+            effect.event_cd,
+            play::EVENT_WALK | play::EVENT_INTENTIONAL_WALK | play::EVENT_HIT_BY_PITCH | play::EVENT_INTERFERENCE
+        );
+        let h_cd = match effect.event_cd {
+            play::EVENT_SINGLE => 1,
+            play::EVENT_DOUBLE => 2,
+            play::EVENT_TRIPLE => 3,
+            play::EVENT_HOME_RUN => 4,
+            _ => 0,
+        };
+        let bat_home_id = bat_team.to_string();
+        let bat_last = (bat_team == 1) != self.htbf;
+        let fld_id = if (1..=9).contains(&effect.fielded_by) {
+            fielders[effect.fielded_by as usize].clone()
+        }
+        else {
+            String::new()
+        };
+
+        let full = FullEvent {
+            game_id: self.game_id.clone(),
+            away_team_id: self.away_team.clone(),
+            inn_ct: inning,
+            bat_home_id: bat_home_id.clone(),
+            outs_ct: self.outs,
+            balls_ct,
+            strikes_ct,
+            pitch_seq_tx: pitches.to_string(),
+            away_score_ct: self.teams[0].score,
+            home_score_ct: self.teams[1].score,
+            bat_id: batter.to_string(),
+            bat_hand_cd: bat_hand,
+            resp_bat_id: resp_bat.clone(),
+            resp_bat_hand_cd: resp_bat_hand,
+            pit_id: pitcher.clone(),
+            pit_hand_cd: pit_hand,
+            resp_pit_id: resp_pit.clone(),
+            resp_pit_hand_cd: resp_pit_hand,
+            pos2_fld_id: fielders[2].clone(),
+            pos3_fld_id: fielders[3].clone(),
+            pos4_fld_id: fielders[4].clone(),
+            pos5_fld_id: fielders[5].clone(),
+            pos6_fld_id: fielders[6].clone(),
+            pos7_fld_id: fielders[7].clone(),
+            pos8_fld_id: fielders[8].clone(),
+            pos9_fld_id: fielders[9].clone(),
+            base1_run_id: runner_id(1),
+            base2_run_id: runner_id(2),
+            base3_run_id: runner_id(3),
+            event_tx: event_tx.to_string(),
+            leadoff_fl: self.inn_pa == 0,
+            ph_fl,
+            bat_fld_cd: bat_player.pos,
+            bat_lineup_id: slot as u8,
+            event_cd: effect.event_cd,
+            bat_event_fl: effect.batter_event,
+            ab_fl,
+            h_cd,
+            sh_fl: effect.sh,
+            sf_fl: effect.sf,
+            event_outs_ct: outs,
+            dp_fl: effect.dp,
+            tp_fl: effect.tp,
+            rbi_ct: rbi,
+            wp_fl: effect.wp,
+            pb_fl: effect.pb,
+            fld_cd: effect.fielded_by,
+            battedball_cd: effect.batted_ball.clone(),
+            bunt_fl: effect.bunt,
+            foul_fl: effect.foul,
+            battedball_loc_tx: effect.location.clone(),
+            err_ct: effect.errors.len() as u8,
+            err1_fld_cd: error(0).0,
+            err1_cd: error(0).1.to_string(),
+            err2_fld_cd: error(1).0,
+            err2_cd: error(1).1.to_string(),
+            err3_fld_cd: error(2).0,
+            err3_cd: error(2).1.to_string(),
+            bat_dest_id: dest_codes[0].to_string(),
+            run1_dest_id: dest_codes[1].to_string(),
+            run2_dest_id: dest_codes[2].to_string(),
+            run3_dest_id: dest_codes[3].to_string(),
+            bat_play_tx: effect.moves[0].play.clone(),
+            run1_play_tx: effect.moves[1].play.clone(),
+            run2_play_tx: effect.moves[2].play.clone(),
+            run3_play_tx: effect.moves[3].play.clone(),
+            run1_sb_fl: effect.stolen[1] && !out_at(1),
+            run2_sb_fl: effect.stolen[2] && !out_at(2),
+            run3_sb_fl: effect.stolen[3] && !out_at(3),
+            run1_cs_fl: effect.caught[1] && out_at(1),
+            run2_cs_fl: effect.caught[2] && out_at(2),
+            run3_cs_fl: effect.caught[3] && out_at(3),
+            run1_pk_fl: effect.picked[1] && out_at(1),
+            run2_pk_fl: effect.picked[2] && out_at(2),
+            run3_pk_fl: effect.picked[3] && out_at(3),
+            run1_resp_pit_id: runner_resp_pit(1),
+            run2_resp_pit_id: runner_resp_pit(2),
+            run3_resp_pit_id: runner_resp_pit(3),
+            game_new_fl: event_id == 1,
+            game_end_fl: false,
+            pr_run1_fl: false,
+            pr_run2_fl: false,
+            pr_run3_fl: false,
+            removed_for_pr_run1_id: String::new(),
+            removed_for_pr_run2_id: String::new(),
+            removed_for_pr_run3_id: String::new(),
+            removed_for_ph_bat_id: removed_for_ph.as_ref().map(|p| p.id.clone()).unwrap_or_default(),
+            removed_for_ph_bat_fld_cd: removed_for_ph.as_ref().map(|p| p.pos).unwrap_or_default(),
+            po1_fld_cd: putout(0),
+            po2_fld_cd: putout(1),
+            po3_fld_cd: putout(2),
+            ass1_fld_cd: assist(0),
+            ass2_fld_cd: assist(1),
+            ass3_fld_cd: assist(2),
+            ass4_fld_cd: assist(3),
+            ass5_fld_cd: assist(4),
+            event_id: event_id.to_string(),
+        };
+
+        let runner_detail = |base: usize| -> (String, String, String, String) {
+            match &self.bases[base] {
+                Some(runner) => {
+                    let (slot, pos) = self.teams[bat_team].find(&runner.id)
+                        .map(|(slot, player)| (slot, player.pos))
+                        .unwrap_or_default();
+                    (pos.to_string(), slot.to_string(), runner.origin_event.to_string(), runner.resp_cat.clone())
+                }
+                None => ("0".to_string(), "0".to_string(), "0".to_string(), String::new()),
+            }
+        };
+        let (run1_fld_cd, run1_lineup_cd, run1_origin_event_id, run1_resp_cat_id) = runner_detail(1);
+        let (run2_fld_cd, run2_lineup_cd, run2_origin_event_id, run2_resp_cat_id) = runner_detail(2);
+        let (run3_fld_cd, run3_lineup_cd, run3_origin_event_id, run3_resp_cat_id) = runner_detail(3);
+        let pitch_counts = PitchCounts::from_sequence(sequence);
+        let start_bases_cd = bases_cd(&self.bases);
+        let end_bases_cd = bases_cd(&new_bases);
+        let starters = &self.teams[bat_team].starters;
+        let fld_starters = &self.teams[fld_team].starters;
+        let assist_tx = |idx: usize| assist(idx).to_string();
+
+        let extended = ExtendedEvent {
+            home_team_id: self.home_team.clone(),
+            bat_team_id: if bat_team == 0 { self.away_team.clone() } else { self.home_team.clone() },
+            fld_team_id: if bat_team == 0 { self.home_team.clone() } else { self.away_team.clone() },
+            bat_last_id: if bat_last { "1".to_string() } else { "0".to_string() },
+            inn_new_fl: self.half_events.is_empty(),
+            inn_end_fl: false,
+            start_bat_score_ct: self.start_scores[bat_team],
+            start_fld_score_ct: self.start_scores[fld_team],
+            inn_runs_ct: self.inn_runs,
+            game_pa_ct: self.teams[bat_team].pa_ct,
+            inn_pa_ct: self.inn_pa,
+            pa_new_fl: self.pa_event_batter.as_deref() != Some(batter),
+            pa_trunc_fl: false,
+            start_bases_cd: start_bases_cd.to_string(),
+            end_bases_cd: end_bases_cd.to_string(),
+            bat_start_fl: starters.contains(batter),
+            resp_bat_start_fl: starters.contains(&resp_bat),
+            bat_on_deck_id: lineup_slot(1),
+            bat_in_hold_id: lineup_slot(2),
+            pit_start_fl: fld_starters.contains(&pitcher),
+            resp_pit_start_fl: fld_starters.contains(&resp_pit),
+            run1_fld_cd,
+            run1_lineup_cd,
+            run1_origin_event_id,
+            run2_fld_cd,
+            run2_lineup_cd,
+            run2_origin_event_id,
+            run3_fld_cd,
+            run3_lineup_cd,
+            run3_origin_event_id,
+            run1_resp_cat_id,
+            run2_resp_cat_id,
+            run3_resp_cat_id,
+            pa_ball_ct: pitch_counts.balls(),
+            pa_called_ball_ct: pitch_counts.called_ball,
+            pa_intent_ball_ct: pitch_counts.intent_ball,
+            pa_pitchout_ball_ct: pitch_counts.pitchout_ball,
+            pa_hitbatter_ball_ct: pitch_counts.hitbatter_ball,
+            pa_other_ball_ct: pitch_counts.other_ball,
+            pa_strike_ct: pitch_counts.strikes(),
+            pa_called_strike_ct: pitch_counts.called_strike,
+            pa_swingmiss_strike_ct: pitch_counts.swingmiss_strike,
+            pa_foul_strike_ct: pitch_counts.foul_strike,
+            pa_inplay_strike_ct: pitch_counts.inplay_strike,
+            pa_other_strike_ct: pitch_counts.other_strike,
+            event_runs_ct: runs,
+            fld_id,
+            base2_force_fl: start_bases_cd & 0b001 == 0b001,
+            base3_force_fl: start_bases_cd & 0b011 == 0b011,
+            base4_force_fl: start_bases_cd == 0b111,
+            bat_safe_err_fl: effect.batter_event && dest[0].is_some_and(|d| d != play::DEST_OUT)
+                && (effect.event_cd == play::EVENT_ERROR || effect.moves[0].on_error),
+            bat_fate_id: String::new(),
+            run1_fate_id: String::new(),
+            run2_fate_id: String::new(),
+            run3_fate_id: String::new(),
+            fate_runs_ct: 0,
+            ass6_fld_cd: assist_tx(5),
+            ass7_fld_cd: assist_tx(6),
+            ass8_fld_cd: assist_tx(7),
+            ass9_fld_cd: assist_tx(8),
+            ass10_fld_cd: assist_tx(9),
+            unknown_out_exc_fl: effect.unknown_out,
+            uncertain_play_exc_fl: effect.uncertain,
+            count_tx: count.to_string(),
+        };
+
+        // Now move the game along.
+        self.next_event_id += 1;
+        self.bases = new_bases;
+        self.outs += outs;
+        self.teams[bat_team].score += runs;
+        self.inn_runs += runs;
+        if effect.batter_event {
+            self.teams[bat_team].pa_ct += 1;
+            self.inn_pa += 1;
+            self.pa_event_batter = None;
+            self.pa_batter = None;
+            self.pa_count = None;
+            self.prev_batter = None;
+            self.prev_pitcher = None;
+            self.bat_hand_adj = None;
+            self.pit_hand_adj = None;
+        }
+        else {
+            self.pa_event_batter = Some(batter.to_string());
+        }
+
+        Ok(PendingEvent {
+            full,
+            extended,
+            bat_token,
+            run_tokens,
+            runs,
+        })
+    }
+}
+
+
+fn parse_team(team: &str) -> Result<usize, String> {
+    match team {
+        "0" => Ok(0),
+        "1" => Ok(1),
+        _ => Err(format!("bad team \"{}\"", team)),
+    }
+}
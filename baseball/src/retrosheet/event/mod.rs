@@ -0,0 +1,386 @@
+//! Read Retrosheet event files (.EVN, .EVA, .EDN, ...) directly, producing the same events as
+//! `cwevent -f 0-96 -x 0-63` without needing Chadwick installed.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use csv::{ReaderBuilder, StringRecord, StringRecordsIntoIter};
+
+use crate::chadwick::events::{ExtendedEvent, FullEvent};
+
+pub mod boxscore;
+mod game;
+mod play;
+mod roster;
+mod team;
+
+pub use game::GameState;
+pub use roster::{Roster, RosterEntry};
+pub use team::{TeamEntry, find_team_file, load_teams};
+
+
+/// The standard and extended cwevent fields for one event.
+pub type CombinedEvent = (FullEvent, ExtendedEvent);
+
+
+/// A game from an event file along with the records that don't end up in its events.
+pub struct EventGame {
+    pub game_id: String,
+    /// Values of the info records, such as "wp", "lp" and "save".
+    pub info: HashMap<String, String>,
+    /// Earned runs for each pitcher from the "data,er" records.
+    pub earned_runs: HashMap<String, u8>,
+    pub events: Vec<CombinedEvent>,
+}
+
+
+#[derive(Debug)]
+pub struct EventFileError {
+    pub file: PathBuf,
+    pub line: u64,
+    pub game_id: String,
+    pub message: String,
+}
+
+
+impl fmt::Display for EventFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.game_id.is_empty() {
+            write!(f, "{}:{}: {}", self.file.display(), self.line, self.message)
+        }
+        else {
+            write!(f, "{}:{}: game {}: {}", self.file.display(), self.line, self.game_id, self.message)
+        }
+    }
+}
+
+
+impl Error for EventFileError {}
+
+
+/// Iterate over the games in an event file.
+pub struct EventFileIter<'r> {
+    file: PathBuf,
+    roster: &'r Roster,
+    records: StringRecordsIntoIter<fs::File>,
+    /// The id record of the next game, already read while finishing the previous one.
+    next_game: Option<(String, u64)>,
+    done: bool,
+}
+
+
+impl<'r> EventFileIter<'r> {
+    pub fn new(file: &Path, roster: &'r Roster) -> Result<Self, EventFileError> {
+        let csv_reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(file)
+            .map_err(|e| EventFileError {
+                file: file.to_path_buf(),
+                line: 0,
+                game_id: String::new(),
+                message: e.to_string(),
+            })?;
+        Ok(EventFileIter {
+            file: file.to_path_buf(),
+            roster,
+            records: csv_reader.into_records(),
+            next_game: None,
+            done: false,
+        })
+    }
+
+    fn error(&self, line: u64, game_id: &str, message: String) -> EventFileError {
+        EventFileError {
+            file: self.file.clone(),
+            line,
+            game_id: game_id.to_string(),
+            message,
+        }
+    }
+
+    fn next_record(&mut self) -> Option<Result<(StringRecord, u64), EventFileError>> {
+        self.records.next().map(|result| {
+            result.map(|record| {
+                let line = record.position().map_or(0, |pos| pos.line());
+                (record, line)
+            })
+            .map_err(|e| {
+                let line = e.position().map_or(0, |pos| pos.line());
+                self.error(line, "", e.to_string())
+            })
+        })
+    }
+
+    fn read_game(&mut self, game_id: String) -> Result<EventGame, EventFileError> {
+        let mut game = GameState::new(&game_id, self.roster);
+        while let Some(result) = self.next_record() {
+            let (record, line) = result?;
+            if record.get(0) == Some("id") {
+                self.next_game = Some((record.get(1).unwrap_or("").to_string(), line));
+                break;
+            }
+            game.apply(&record).map_err(|message| self.error(line, &game_id, message))?;
+        }
+        Ok(game.finish())
+    }
+}
+
+
+impl Iterator for EventFileIter<'_> {
+    type Item = Result<EventGame, EventFileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        // Skip anything before the first id record.
+        while self.next_game.is_none() {
+            match self.next_record()? {
+                Ok((record, line)) if record.get(0) == Some("id") => {
+                    self.next_game = Some((record.get(1).unwrap_or("").to_string(), line));
+                }
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        let (game_id, _line) = self.next_game.take()?;
+        let result = self.read_game(game_id);
+        // Stop at the first bad game since the rest of the file can't be trusted.
+        if result.is_err() {
+            self.done = true;
+        }
+        Some(result)
+    }
+}
+
+
+/// Load every event in an event file.
+pub fn load_event_file(file: &Path, roster: &Roster) -> Result<Vec<CombinedEvent>, EventFileError> {
+    let mut events = Vec::new();
+    for game in EventFileIter::new(file, roster)? {
+        events.extend(game?.events);
+    }
+    Ok(events)
+}
+
+
+/// Find the event files in a season directory, in name order.
+pub fn find_event_files(season_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(season_dir)? {
+        let path = entry?.path();
+        let extension = path.extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_ascii_uppercase();
+        if extension.len() == 3 && (extension.starts_with("EV") || extension.starts_with("ED")) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+
+/// Load every game of a season from a Retrosheet season directory, using the rosters in the
+/// same directory for handedness.
+pub fn load_season_games(season_dir: &Path) -> Result<Vec<EventGame>, EventFileError> {
+    let io_error = |e: io::Error| EventFileError {
+        file: season_dir.to_path_buf(),
+        line: 0,
+        game_id: String::new(),
+        message: e.to_string(),
+    };
+    let roster = Roster::from_dir(season_dir).map_err(io_error)?;
+    let mut games = Vec::new();
+    for file in find_event_files(season_dir).map_err(io_error)? {
+        for game in EventFileIter::new(&file, &roster)? {
+            games.push(game?);
+        }
+    }
+    Ok(games)
+}
+
+
+/// Load every event of a season from a Retrosheet season directory.
+pub fn load_season_events(season_dir: &Path) -> Result<Vec<CombinedEvent>, EventFileError> {
+    let games = load_season_games(season_dir)?;
+    Ok(games.into_iter().flat_map(|game| game.events).collect())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME: &str = "\
+id,TST202404010
+version,2
+info,visteam,VIS
+info,hometeam,TST
+start,vis01,\"Visitor One\",0,1,8
+start,vis02,\"Visitor Two\",0,2,6
+start,vis03,\"Visitor Three\",0,3,3
+start,vis04,\"Visitor Four\",0,4,7
+start,vis05,\"Visitor Five\",0,5,9
+start,vis06,\"Visitor Six\",0,6,5
+start,vis07,\"Visitor Seven\",0,7,4
+start,vis08,\"Visitor Eight\",0,8,2
+start,vis09,\"Visitor Nine\",0,9,1
+start,hom01,\"Home One\",1,1,8
+start,hom02,\"Home Two\",1,2,6
+start,hom03,\"Home Three\",1,3,3
+start,hom04,\"Home Four\",1,4,7
+start,hom05,\"Home Five\",1,5,9
+start,hom06,\"Home Six\",1,6,5
+start,hom07,\"Home Seven\",1,7,4
+start,hom08,\"Home Eight\",1,8,2
+start,hom09,\"Home Nine\",1,9,1
+play,1,0,vis01,21,BCBX,S8/L8
+play,1,0,vis02,00,1,SB2
+play,1,0,vis02,32,1BBCFBB,W
+play,1,0,vis03,12,BCSX,64(1)3/GDP.2-3
+play,1,0,vis04,00,X,HR/F7
+play,1,0,vis05,01,CX,8/F8
+play,1,1,hom01,30,BBBB,W
+sub,hom10,\"Home Ten\",1,1,12
+play,1,1,hom02,02,CSS,K
+play,1,1,hom03,11,BCX,D7/L7LD.1-H
+play,1,1,hom04,00,X,63/G6
+play,1,1,hom05,00,X,53/G5
+";
+
+    fn load(text: &str) -> Vec<CombinedEvent> {
+        let dir = std::env::temp_dir().join(format!("baseball-event-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("2024TST.EVN");
+        fs::write(&file, text).unwrap();
+        let events = load_event_file(&file, &Roster::default());
+        fs::remove_dir_all(&dir).unwrap();
+        events.unwrap()
+    }
+
+    #[test]
+    fn replay_game() {
+        let events = load(GAME);
+        assert_eq!(events.len(), 11);
+
+        let (single, single_ext) = &events[0];
+        assert!(single.game_new_fl);
+        assert!(single.leadoff_fl);
+        assert_eq!(single.event_cd, 20);
+        assert_eq!(single.bat_dest_id, "1");
+        assert_eq!(single.battedball_cd, "L");
+        assert_eq!(single.fld_cd, 8);
+        assert_eq!(single_ext.fld_id, "hom01");
+        assert_eq!(single_ext.bat_fate_id, "4");
+        assert_eq!(single_ext.bat_on_deck_id, "vis02");
+
+        let (steal, steal_ext) = &events[1];
+        assert!(steal.run1_sb_fl);
+        assert_eq!(steal.run1_dest_id, "2");
+        assert!(!steal.bat_event_fl);
+        assert!(steal_ext.pa_new_fl);
+
+        let (walk, walk_ext) = &events[2];
+        assert!(!walk_ext.pa_new_fl);
+        assert_eq!(walk.run2_dest_id, "2");
+        assert_eq!(walk_ext.pa_ball_ct, 4);
+        assert_eq!(walk_ext.pa_strike_ct, 2);
+
+        let (dp, dp_ext) = &events[3];
+        assert!(dp.dp_fl);
+        assert_eq!(dp.event_outs_ct, 2);
+        assert_eq!(dp.run1_dest_id, "0");
+        assert_eq!(dp.run1_play_tx, "64");
+        assert_eq!(dp.bat_play_tx, "43");
+        assert_eq!((dp.po1_fld_cd, dp.po2_fld_cd), (4, 3));
+        assert_eq!((dp.ass1_fld_cd, dp.ass2_fld_cd), (6, 4));
+        assert_eq!(dp.rbi_ct, 0);
+        assert!(dp_ext.base2_force_fl);
+
+        let (hr, hr_ext) = &events[4];
+        assert_eq!(hr.rbi_ct, 2);
+        assert_eq!(hr.run3_dest_id, "4");
+        assert_eq!(hr_ext.event_runs_ct, 2);
+        assert_eq!(hr_ext.start_bases_cd, "4");
+
+        let (flyout, flyout_ext) = &events[5];
+        assert_eq!(flyout.away_score_ct, 2);
+        assert!(flyout_ext.inn_end_fl);
+        assert_eq!(flyout_ext.fate_runs_ct, 0);
+
+        let (home_walk, home_walk_ext) = &events[6];
+        assert!(home_walk.pr_run1_fl);
+        assert_eq!(home_walk.removed_for_pr_run1_id, "hom01");
+        assert_eq!(home_walk_ext.fate_runs_ct, 1);
+
+        let (double, _) = &events[8];
+        assert_eq!(double.base1_run_id, "hom10");
+        assert_eq!(double.run1_dest_id, "4");
+        assert_eq!(double.rbi_ct, 1);
+
+        let (last, _) = events.last().unwrap();
+        assert!(last.game_end_fl);
+
+        let full: Vec<FullEvent> = events.iter().map(|(full, _)| full.clone()).collect();
+        let changes = crate::chadwick::state::replay(&full);
+        assert!(changes.iter().all(|change| change.mismatch.is_none()));
+        assert_eq!(changes.iter().map(|change| change.runs_scored()).sum::<u8>(), 3);
+        assert_eq!(changes[4].runs[0].resp_pit_id, "hom09");
+    }
+
+    #[test]
+    fn boxscore() {
+        let dir = std::env::temp_dir().join(format!("baseball-event-box-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("2024TST.EVN");
+        fs::write(&file, GAME.replace("version,2", "version,2\ninfo,wp,hom09")).unwrap();
+        let roster = Roster::default();
+        let games: Vec<EventGame> = EventFileIter::new(&file, &roster).unwrap().flatten().collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let (batting, fielding, pitching) = boxscore::gamelogs_from_games(&games);
+        let vis04 = batting.iter().find(|b| b.player_id == "vis04").unwrap();
+        assert_eq!((vis04.pa, vis04.ab, vis04.h, vis04.hr, vis04.rbi, vis04.r), (1, 1, 1, 1, 2, 1));
+        assert_eq!(vis04.pos, "7");
+        let vis03 = batting.iter().find(|b| b.player_id == "vis03").unwrap();
+        assert_eq!(vis03.gidp, 1);
+        let hom01 = batting.iter().find(|b| b.player_id == "hom01").unwrap();
+        assert_eq!((hom01.bb, hom01.r), (1, 0));
+        let hom10 = batting.iter().find(|b| b.player_id == "hom10").unwrap();
+        assert_eq!((hom10.pa, hom10.r), (0, 1));
+
+        let hom09 = pitching.iter().find(|p| p.player_id == "hom09").unwrap();
+        assert_eq!((hom09.ipouts, hom09.bf, hom09.h, hom09.r, hom09.er, hom09.bb), (3, 5, 2, 2, 2, 1));
+        assert!(hom09.gs && hom09.cg && !hom09.sho);
+        assert_eq!(hom09.decision, "W");
+        let vis09 = pitching.iter().find(|p| p.player_id == "vis09").unwrap();
+        assert_eq!((vis09.ipouts, vis09.so, vis09.p), (3, 1, 12));
+
+        let hom02 = fielding.iter().find(|f| f.player_id == "hom02" && f.pos == 6).unwrap();
+        assert_eq!((hom02.a, hom02.dp), (1, 1));
+        let hom07 = fielding.iter().find(|f| f.player_id == "hom07" && f.pos == 4).unwrap();
+        assert_eq!((hom07.po, hom07.a, hom07.dp), (1, 1, 1));
+    }
+
+    #[test]
+    fn bad_play() {
+        let text = GAME.replace("SB2", "SB3");
+        let dir = std::env::temp_dir().join(format!("baseball-event-bad-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("2024TST.EVN");
+        fs::write(&file, text).unwrap();
+        let error = load_event_file(&file, &Roster::default()).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(error.game_id, "TST202404010");
+        assert_eq!(error.line, 24);
+    }
+}
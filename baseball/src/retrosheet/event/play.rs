@@ -0,0 +1,461 @@
+//! Turn the event text of a play record into the effects needed to fill out an event: the event
+//! type, where the batter and runners end up, and who gets fielding credit.
+
+use std::default::Default;
+
+use crate::chadwick::play::{Advance, AdvanceNote, Base, BasicPlay, FieldedOut, Fielding, Modifier, Play};
+
+
+// Event type codes used by Chadwick.
+pub const EVENT_UNKNOWN: u8 = 0;
+pub const EVENT_NONE: u8 = 1;
+pub const EVENT_GENERIC_OUT: u8 = 2;
+pub const EVENT_STRIKEOUT: u8 = 3;
+pub const EVENT_STOLEN_BASE: u8 = 4;
+pub const EVENT_DEFENSIVE_INDIFFERENCE: u8 = 5;
+pub const EVENT_CAUGHT_STEALING: u8 = 6;
+pub const EVENT_PICKOFF: u8 = 8;
+pub const EVENT_WILD_PITCH: u8 = 9;
+pub const EVENT_PASSED_BALL: u8 = 10;
+pub const EVENT_BALK: u8 = 11;
+pub const EVENT_OTHER_ADVANCE: u8 = 12;
+pub const EVENT_FOUL_ERROR: u8 = 13;
+pub const EVENT_WALK: u8 = 14;
+pub const EVENT_INTENTIONAL_WALK: u8 = 15;
+pub const EVENT_HIT_BY_PITCH: u8 = 16;
+pub const EVENT_INTERFERENCE: u8 = 17;
+pub const EVENT_ERROR: u8 = 18;
+pub const EVENT_FIELDERS_CHOICE: u8 = 19;
+pub const EVENT_SINGLE: u8 = 20;
+pub const EVENT_DOUBLE: u8 = 21;
+pub const EVENT_TRIPLE: u8 = 22;
+pub const EVENT_HOME_RUN: u8 = 23;
+
+// Destination codes used by Chadwick. Bases 1-3 are themselves.
+pub const DEST_OUT: u8 = 0;
+pub const DEST_SCORED: u8 = 4;
+pub const DEST_UNEARNED: u8 = 5;
+pub const DEST_TEAM_UNEARNED: u8 = 6;
+
+
+/// What happened to the batter (index 0) or a runner (index 1-3) on the play.
+#[derive(Clone, Default)]
+pub struct Movement {
+    /// Base reached (0 when put out, 4 when scoring). None if the play didn't move them.
+    pub dest: Option<u8>,
+    /// Set to DEST_UNEARNED or DEST_TEAM_UNEARNED when marked (UR) or (TUR).
+    pub unearned: Option<u8>,
+    /// Explicit (RBI) or (NR) marker.
+    pub rbi: Option<bool>,
+    /// Fielders involved in putting out this player.
+    pub play: String,
+    /// The advance was made on an error.
+    pub on_error: bool,
+}
+
+
+#[derive(Default)]
+pub struct PlayEffect {
+    pub event_cd: u8,
+    pub batter_event: bool,
+    pub moves: [Movement; 4],
+    /// Walks, hit batters and interference push forced runners ahead.
+    pub force_runners: bool,
+    /// Home runs score every runner not otherwise accounted for.
+    pub clear_bases: bool,
+    pub putouts: Vec<u8>,
+    pub assists: Vec<u8>,
+    pub errors: Vec<(u8, char)>,
+    pub fielded_by: u8,
+    pub batted_ball: String,
+    pub location: String,
+    pub bunt: bool,
+    pub foul: bool,
+    pub sh: bool,
+    pub sf: bool,
+    pub dp: bool,
+    pub tp: bool,
+    pub wp: bool,
+    pub pb: bool,
+    pub stolen: [bool; 4],
+    pub caught: [bool; 4],
+    pub picked: [bool; 4],
+    pub uncertain: bool,
+    pub unknown_out: bool,
+}
+
+
+impl PlayEffect {
+    fn credit_out(&mut self, runner: usize, fielders: &[u8]) {
+        self.moves[runner].dest = Some(DEST_OUT);
+        self.moves[runner].play = fielders.iter().map(|f| f.to_string()).collect();
+        if let Some((putout, assists)) = fielders.split_last() {
+            self.putouts.push(*putout);
+            for fielder in assists {
+                if !self.assists.contains(fielder) && fielder != putout {
+                    self.assists.push(*fielder);
+                }
+            }
+        }
+    }
+
+    fn credit_error(&mut self, fielders: &[u8], error: u8, throwing: bool) {
+        for fielder in fielders {
+            if !self.assists.contains(fielder) {
+                self.assists.push(*fielder);
+            }
+        }
+        self.errors.push((error, if throwing { 'T' } else { 'F' }));
+    }
+}
+
+
+pub fn parse_play(event_text: &str) -> Result<PlayEffect, String> {
+    let play = Play::parse(event_text).map_err(|e| e.to_string())?;
+    Ok(PlayEffect::from_play(&play))
+}
+
+
+impl PlayEffect {
+    pub fn from_play(play: &Play) -> Self {
+        let mut effect = PlayEffect {
+            uncertain: play.uncertain,
+            ..Default::default()
+        };
+        apply_basic(&play.basic, &mut effect);
+        if let Some(additional) = &play.additional {
+            // The secondary event of K+ and W+ plays never changes the event type.
+            let mut secondary = PlayEffect::default();
+            apply_basic(additional, &mut secondary);
+            merge_secondary(&mut effect, secondary);
+        }
+        for modifier in &play.modifiers {
+            apply_modifier(modifier, &mut effect);
+        }
+        for advance in &play.advances {
+            apply_advance(advance, &mut effect);
+        }
+        effect
+    }
+}
+
+
+fn merge_secondary(effect: &mut PlayEffect, secondary: PlayEffect) {
+    for (idx, movement) in secondary.moves.into_iter().enumerate() {
+        if movement.dest.is_some() {
+            effect.moves[idx] = movement;
+        }
+    }
+    effect.putouts.extend(secondary.putouts);
+    for fielder in secondary.assists {
+        if !effect.assists.contains(&fielder) {
+            effect.assists.push(fielder);
+        }
+    }
+    effect.errors.extend(secondary.errors);
+    effect.wp |= secondary.event_cd == EVENT_WILD_PITCH;
+    effect.pb |= secondary.event_cd == EVENT_PASSED_BALL;
+    for idx in 0..4 {
+        effect.stolen[idx] |= secondary.stolen[idx];
+        effect.caught[idx] |= secondary.caught[idx];
+        effect.picked[idx] |= secondary.picked[idx];
+    }
+    effect.unknown_out |= secondary.unknown_out;
+}
+
+
+/// Runner index for the runner that ends up on, or was thrown out at, a base when stealing.
+fn stealing_runner(base: Base) -> usize {
+    base.number().saturating_sub(1) as usize
+}
+
+
+/// Apply the (UR), (TUR), (NR) and (RBI) markers.
+fn apply_markers(notes: &[AdvanceNote], movement: &mut Movement) {
+    for note in notes {
+        match note {
+            AdvanceNote::Unearned => movement.unearned = Some(DEST_UNEARNED),
+            AdvanceNote::TeamUnearned => movement.unearned = Some(DEST_TEAM_UNEARNED),
+            AdvanceNote::NoRbi => movement.rbi = Some(false),
+            AdvanceNote::Rbi => movement.rbi = Some(true),
+            _ => {}
+        }
+    }
+}
+
+
+fn reach_first(event_cd: u8, effect: &mut PlayEffect) {
+    effect.event_cd = event_cd;
+    effect.batter_event = true;
+    effect.moves[0].dest = Some(1);
+}
+
+
+fn apply_basic(basic: &BasicPlay, effect: &mut PlayEffect) {
+    match basic {
+        BasicPlay::NoPlay => effect.event_cd = EVENT_NONE,
+        BasicPlay::Balk => effect.event_cd = EVENT_BALK,
+        BasicPlay::DefensiveIndifference => effect.event_cd = EVENT_DEFENSIVE_INDIFFERENCE,
+        BasicPlay::OtherAdvance => effect.event_cd = EVENT_OTHER_ADVANCE,
+        BasicPlay::PassedBall => {
+            effect.event_cd = EVENT_PASSED_BALL;
+            effect.pb = true;
+        }
+        BasicPlay::WildPitch => {
+            effect.event_cd = EVENT_WILD_PITCH;
+            effect.wp = true;
+        }
+        BasicPlay::UnknownOut => {
+            effect.event_cd = EVENT_UNKNOWN;
+            effect.unknown_out = true;
+        }
+        BasicPlay::PickoffCaughtStealing { base, fielding, notes } => {
+            effect.event_cd = EVENT_CAUGHT_STEALING;
+            apply_caught_stealing(*base, fielding, notes, true, effect);
+        }
+        BasicPlay::CaughtStealing { base, fielding, notes } => {
+            effect.event_cd = EVENT_CAUGHT_STEALING;
+            apply_caught_stealing(*base, fielding, notes, false, effect);
+        }
+        BasicPlay::Pickoff { base, fielding, .. } => {
+            effect.event_cd = EVENT_PICKOFF;
+            let runner = base.number() as usize;
+            for sequence in fielding {
+                if let Some(error) = sequence.error {
+                    effect.credit_error(&sequence.fielders, error, false);
+                }
+                else {
+                    effect.credit_out(runner, &sequence.fielders);
+                    effect.picked[runner] = true;
+                }
+            }
+        }
+        BasicPlay::StolenBase(steals) => {
+            effect.event_cd = EVENT_STOLEN_BASE;
+            for steal in steals {
+                let runner = stealing_runner(steal.base);
+                effect.moves[runner].dest = Some(steal.base.number());
+                apply_markers(&steal.notes, &mut effect.moves[runner]);
+                effect.stolen[runner] = true;
+            }
+        }
+        BasicPlay::Walk => {
+            reach_first(EVENT_WALK, effect);
+            effect.force_runners = true;
+        }
+        BasicPlay::IntentionalWalk => {
+            reach_first(EVENT_INTENTIONAL_WALK, effect);
+            effect.force_runners = true;
+        }
+        BasicPlay::HitByPitch => {
+            reach_first(EVENT_HIT_BY_PITCH, effect);
+            effect.force_runners = true;
+        }
+        BasicPlay::Interference(_) => {
+            reach_first(EVENT_INTERFERENCE, effect);
+            effect.force_runners = true;
+        }
+        BasicPlay::FoulFlyError(fielder) => {
+            effect.event_cd = EVENT_FOUL_ERROR;
+            effect.fielded_by = *fielder;
+            effect.credit_error(&[], *fielder, false);
+            effect.foul = true;
+        }
+        BasicPlay::FieldersChoice(fielder) => {
+            reach_first(EVENT_FIELDERS_CHOICE, effect);
+            effect.fielded_by = fielder.unwrap_or_default();
+        }
+        BasicPlay::HomeRun(fielder) => {
+            apply_hit(EVENT_HOME_RUN, DEST_SCORED, *fielder, effect);
+            effect.clear_bases = true;
+        }
+        BasicPlay::GroundRuleDouble => apply_hit(EVENT_DOUBLE, 2, None, effect),
+        BasicPlay::Single(fielder) => apply_hit(EVENT_SINGLE, 1, *fielder, effect),
+        BasicPlay::Double(fielder) => apply_hit(EVENT_DOUBLE, 2, *fielder, effect),
+        BasicPlay::Triple(fielder) => apply_hit(EVENT_TRIPLE, 3, *fielder, effect),
+        BasicPlay::Strikeout(sequence) => {
+            effect.event_cd = EVENT_STRIKEOUT;
+            effect.batter_event = true;
+            if let Some(error) = sequence.error {
+                effect.credit_error(&sequence.fielders, error, false);
+            }
+            else if sequence.fielders.is_empty() {
+                effect.credit_out(0, &[2]);
+            }
+            else {
+                effect.credit_out(0, &sequence.fielders);
+            }
+        }
+        BasicPlay::Error(fielder) => {
+            reach_first(EVENT_ERROR, effect);
+            effect.fielded_by = *fielder;
+            effect.credit_error(&[], *fielder, false);
+            effect.moves[0].on_error = true;
+        }
+        BasicPlay::FieldedOut(outs) => {
+            effect.event_cd = EVENT_GENERIC_OUT;
+            effect.batter_event = true;
+            apply_fielded_outs(outs, effect);
+        }
+    }
+}
+
+
+fn apply_hit(event_cd: u8, base: u8, fielder: Option<u8>, effect: &mut PlayEffect) {
+    effect.event_cd = event_cd;
+    effect.batter_event = true;
+    effect.fielded_by = fielder.unwrap_or_default();
+    effect.moves[0].dest = Some(base);
+}
+
+
+fn apply_caught_stealing(base: Base, fielding: &[Fielding], notes: &[AdvanceNote], pickoff: bool, effect: &mut PlayEffect) {
+    let runner = stealing_runner(base);
+    let mut out = true;
+    for sequence in fielding {
+        if let Some(error) = sequence.error {
+            effect.credit_error(&sequence.fielders, error, false);
+            out = false;
+        }
+        else {
+            effect.credit_out(runner, &sequence.fielders);
+        }
+    }
+
+    if out {
+        effect.caught[runner] = true;
+        effect.picked[runner] = pickoff;
+        if effect.moves[runner].dest.is_none() {
+            effect.moves[runner].dest = Some(DEST_OUT);
+        }
+    }
+    else {
+        // Notes like (UR) can follow CSH when the runner scores on an error.
+        effect.moves[runner].dest = Some(base.number());
+        effect.moves[runner].on_error = true;
+        apply_markers(notes, &mut effect.moves[runner]);
+    }
+}
+
+
+/// Credit a fielded out such as "8", "63", "64(1)3", or "8(B)84(2)".
+fn apply_fielded_outs(outs: &[FieldedOut], effect: &mut PlayEffect) {
+    effect.fielded_by = outs.first()
+        .and_then(|out| out.fielding.fielders.first())
+        .copied()
+        .unwrap_or_default();
+
+    let mut previous_fielder = None;
+    for out in outs {
+        let runner = out.runner.number() as usize;
+        let mut fielders = out.fielding.fielders.clone();
+        // A single fielder after an earlier out received the throw from the last fielder of that out.
+        if fielders.len() == 1 && let Some(previous) = previous_fielder && previous != fielders[0] {
+            fielders.insert(0, previous);
+        }
+        if let Some(error) = out.fielding.error {
+            effect.credit_error(&fielders, error, false);
+            effect.moves[runner].dest = Some(if runner == 0 { 1 } else { runner as u8 });
+            effect.moves[runner].on_error = true;
+        }
+        else {
+            effect.credit_out(runner, &fielders);
+        }
+        previous_fielder = fielders.last().copied();
+    }
+
+    if !outs.iter().any(|out| out.runner == Base::Batter) {
+        // Force out of a runner with the batter reaching first.
+        effect.moves[0].dest = Some(1);
+    }
+}
+
+
+fn apply_modifier(modifier: &Modifier, effect: &mut PlayEffect) {
+    match modifier {
+        Modifier::SacrificeHit => {
+            effect.sh = true;
+            effect.bunt = true;
+        }
+        Modifier::SacrificeFly => effect.sf = true,
+        Modifier::DoublePlay { batted_ball, bunt } => {
+            effect.dp = true;
+            if let Some(batted_ball) = batted_ball {
+                effect.batted_ball = batted_ball.code().to_string();
+            }
+            effect.bunt |= bunt;
+        }
+        Modifier::TriplePlay { batted_ball, bunt } => {
+            effect.tp = true;
+            if let Some(batted_ball) = batted_ball {
+                effect.batted_ball = batted_ball.code().to_string();
+            }
+            effect.bunt |= bunt;
+        }
+        Modifier::Foul => effect.foul = true,
+        Modifier::Throw(_) => {
+            if let Some(error) = effect.errors.last_mut() {
+                error.1 = 'T';
+            }
+        }
+        Modifier::Error(fielder) => effect.errors.push((*fielder, 'F')),
+        Modifier::BattedBall { batted_ball, bunt, location } => {
+            if let Some(batted_ball) = batted_ball {
+                effect.batted_ball = batted_ball.code().to_string();
+            }
+            effect.bunt |= bunt;
+            if let Some(location) = location {
+                effect.location = location.clone();
+                if location.ends_with('F') {
+                    effect.foul = true;
+                }
+            }
+        }
+        // Modifiers that describe the play without changing the event.
+        Modifier::NoDoublePlay | Modifier::Relay(_) | Modifier::Other(_) => {}
+    }
+}
+
+
+fn apply_advance(advance: &Advance, effect: &mut PlayEffect) {
+    let runner = advance.runner.number() as usize;
+    let mut movement = Movement {
+        dest: Some(advance.to.number()),
+        ..Default::default()
+    };
+    let mut out = advance.thrown_out;
+    apply_markers(&advance.notes, &mut movement);
+
+    for note in &advance.notes {
+        match note {
+            AdvanceNote::UnknownFielding => effect.unknown_out = true,
+            AdvanceNote::Fielding { fielding, throw } => {
+                if let Some(error) = fielding.error {
+                    effect.credit_error(&fielding.fielders, error, *throw);
+                    movement.on_error = true;
+                    out = false;
+                }
+                else if out {
+                    effect.credit_out(runner, &fielding.fielders);
+                    movement.play = effect.moves[runner].play.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if out {
+        movement.dest = Some(DEST_OUT);
+        if movement.play.is_empty() {
+            movement.play = effect.moves[runner].play.clone();
+        }
+    }
+    else if effect.moves[runner].dest == Some(DEST_OUT) {
+        // A safe advance overrides an out implied by the main play, so take back the putout.
+        let putout = effect.moves[runner].play.chars().last().and_then(|ch| ch.to_digit(10));
+        if let Some(idx) = putout.and_then(|p| effect.putouts.iter().rposition(|f| u32::from(*f) == p)) {
+            effect.putouts.remove(idx);
+        }
+    }
+    effect.moves[runner] = movement;
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::default::Default;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use csv::ReaderBuilder;
+use serde_derive::Deserialize;
+
+use crate::chadwick::Handedness;
+
+
+/// Batting and throwing hands of every player found in a season's TEAMYYYY.ROS files.
+#[derive(Default)]
+pub struct Roster {
+    players: HashMap<String, RosterEntry>,
+}
+
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RosterEntry {
+    pub player_id: String,
+    pub last_name: String,
+    pub first_name: String,
+    pub bats: String,
+    pub throws: String,
+    pub team: String,
+    pub pos: String,
+}
+
+
+impl Roster {
+    pub fn load<T: io::Read>(&mut self, file: T) {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(file);
+        for entry in reader.deserialize().flatten() {
+            let entry: RosterEntry = entry;
+            self.players.insert(entry.player_id.clone(), entry);
+        }
+    }
+
+    /// Load every roster file in a Retrosheet season directory.
+    pub fn from_dir(season_dir: &Path) -> io::Result<Self> {
+        let mut roster = Roster::default();
+        for entry in fs::read_dir(season_dir)? {
+            let path = entry?.path();
+            let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+            if extension.eq_ignore_ascii_case("ROS") {
+                roster.load(fs::File::open(&path)?);
+            }
+        }
+        Ok(roster)
+    }
+
+    pub fn get(&self, player_id: &str) -> Option<&RosterEntry> {
+        self.players.get(player_id)
+    }
+
+    pub fn bats(&self, player_id: &str) -> Handedness {
+        self.players.get(player_id)
+            .map_or(Handedness::Unknown, |entry| Handedness::from_code(&entry.bats))
+    }
+
+    pub fn throws(&self, player_id: &str) -> Handedness {
+        self.players.get(player_id)
+            .map_or(Handedness::Unknown, |entry| Handedness::from_code(&entry.throws))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+}
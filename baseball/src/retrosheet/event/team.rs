@@ -0,0 +1,46 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use csv::ReaderBuilder;
+use serde_derive::Deserialize;
+
+
+/// A team's league and name from a season's TEAMYYYY file.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TeamEntry {
+    pub team_id: String,
+    pub league: String,
+    pub city: String,
+    pub nickname: String,
+}
+
+
+/// The TEAMYYYY file in a Retrosheet season directory, if it has one.
+pub fn find_team_file(season_dir: &Path, season: &str) -> Option<PathBuf> {
+    [format!("TEAM{}", season), format!("team{}", season)].into_iter()
+        .map(|team_file| season_dir.join(team_file))
+        .find(|team_path| team_path.exists())
+}
+
+
+pub fn load_teams<T: io::Read>(file: T) -> Vec<TeamEntry> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(file);
+    reader.deserialize().flatten().collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn teams() {
+        let teams = load_teams("ANA,A,Anaheim,Angels\nARI,N,Arizona,Diamondbacks\n".as_bytes());
+        assert_eq!(teams.len(), 2);
+        assert_eq!((teams[1].team_id.as_str(), teams[1].league.as_str()), ("ARI", "N"));
+        assert_eq!((teams[1].city.as_str(), teams[1].nickname.as_str()), ("Arizona", "Diamondbacks"));
+    }
+}
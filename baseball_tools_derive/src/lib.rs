@@ -0,0 +1,488 @@
+//! Derive macros for the `Sql`, `SqlColumns` and `CelEval` traits in baseball_tools.
+//!
+//! The generated code refers to the traits through `crate::database` and `crate::search`, so the
+//! derives only work inside baseball_tools.
+//!
+//! Struct attributes:
+//!
+//! * `#[sql(table = "games", create = "sql/create_games.sql")]` names the table and the file with
+//!   its `CREATE TABLE` statement, relative to the deriving file.
+//! * `#[sql(table = "games", read_only)]` for types only read from a table another type writes.
+//! * `#[cel(method(hit = is_hit))]` adds a CEL variable from calling a method on the type.
+//!
+//! Field attributes:
+//!
+//! * `#[sql(column = "bavg")]` stores the field in a column with a different name.
+//! * `#[sql(flatten = "low_")]` stores a `SqlColumns` type inline with its columns prefixed.
+//! * `#[sql(skip)]` leaves the field out of the table, reading it as its default.
+//! * `#[cel(name = "bavg")]` exposes the field to CEL under a different name.
+//! * `#[cel(with = date_value)]` converts the field to a CEL value with a function.
+//! * `#[cel(skip)]` leaves the field out of CEL.
+//!
+//! Fields are added to CEL through their `Serialize` impl, except that `u8`, `u16` and `u32` fields
+//! (and options of them) are added as `i64`, since CEL doesn't consider `uint` values equal to
+//! `int` literals. `f32` fields read SQL NULLs as NaN.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Ident, LitStr, Path, Type, parse_macro_input};
+
+
+#[proc_macro_derive(Sql, attributes(sql))]
+pub fn derive_sql(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_sql(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+
+#[proc_macro_derive(SqlColumns, attributes(sql))]
+pub fn derive_sql_columns(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_sql_columns(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+
+#[proc_macro_derive(CelEval, attributes(cel))]
+pub fn derive_cel_eval(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_cel_eval(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+
+/// How a table is created and written.
+enum TableSource {
+    Create(LitStr),
+    ReadOnly,
+}
+
+
+struct SqlStruct {
+    table: LitStr,
+    source: TableSource,
+}
+
+
+#[derive(Default)]
+struct SqlField {
+    column: Option<String>,
+    flatten: Option<String>,
+    skip: bool,
+}
+
+
+#[derive(Default)]
+struct CelField {
+    name: Option<String>,
+    with: Option<Path>,
+    skip: bool,
+}
+
+
+fn named_fields(input: &DeriveInput) -> syn::Result<Vec<&syn::Field>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(fields.named.iter().collect()),
+            _ => Err(syn::Error::new_spanned(&input.ident, "only structs with named fields are supported")),
+        },
+        _ => Err(syn::Error::new_spanned(&input.ident, "only structs are supported")),
+    }
+}
+
+
+fn field_ident(field: &syn::Field) -> &Ident {
+    field.ident.as_ref().expect("named field")
+}
+
+
+fn is_f32(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("f32"))
+}
+
+
+fn is_unsigned(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && ["u8", "u16", "u32"].iter().any(|name| path.path.is_ident(name)))
+}
+
+
+/// The type in an `Option<T>`, or `None` for other types.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty
+    else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if path.qself.is_some() || segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+
+fn parse_sql_struct(input: &DeriveInput) -> syn::Result<SqlStruct> {
+    let mut table = None;
+    let mut create = None;
+    let mut read_only = false;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("sql")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse::<LitStr>()?);
+            }
+            else if meta.path.is_ident("create") {
+                create = Some(meta.value()?.parse::<LitStr>()?);
+            }
+            else if meta.path.is_ident("read_only") {
+                read_only = true;
+            }
+            else {
+                return Err(meta.error("unknown sql attribute"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let table = table.ok_or_else(|| syn::Error::new_spanned(&input.ident, "missing #[sql(table = \"...\")]"))?;
+    let source = match (create, read_only) {
+        (Some(create), false) => TableSource::Create(create),
+        (None, true) => TableSource::ReadOnly,
+        _ => return Err(syn::Error::new_spanned(&input.ident, "expected exactly one of create or read_only")),
+    };
+    Ok(SqlStruct { table, source })
+}
+
+
+fn parse_sql_field(field: &syn::Field) -> syn::Result<SqlField> {
+    let mut sql = SqlField::default();
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("sql")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("column") {
+                sql.column = Some(meta.value()?.parse::<LitStr>()?.value());
+            }
+            else if meta.path.is_ident("flatten") {
+                sql.flatten = Some(meta.value()?.parse::<LitStr>()?.value());
+            }
+            else if meta.path.is_ident("skip") {
+                sql.skip = true;
+            }
+            else {
+                return Err(meta.error("unknown sql attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(sql)
+}
+
+
+fn parse_cel_field(field: &syn::Field) -> syn::Result<CelField> {
+    let mut cel = CelField::default();
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("cel")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                cel.name = Some(meta.value()?.parse::<LitStr>()?.value());
+            }
+            else if meta.path.is_ident("with") {
+                cel.with = Some(meta.value()?.parse::<Path>()?);
+            }
+            else if meta.path.is_ident("skip") {
+                cel.skip = true;
+            }
+            else {
+                return Err(meta.error("unknown cel attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(cel)
+}
+
+
+/// Variables computed by methods, from `#[cel(method(name = method))]`.
+fn parse_cel_methods(input: &DeriveInput) -> syn::Result<Vec<(String, Ident)>> {
+    let mut methods = Vec::new();
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("cel")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("method") {
+                meta.parse_nested_meta(|method| {
+                    let name = method.path.get_ident()
+                        .ok_or_else(|| method.error("expected a variable name"))?
+                        .to_string();
+                    methods.push((name, method.value()?.parse::<Ident>()?));
+                    Ok(())
+                })
+            }
+            else {
+                Err(meta.error("unknown cel attribute"))
+            }
+        })?;
+    }
+    Ok(methods)
+}
+
+
+/// The pieces of the column handling shared by `Sql` and `SqlColumns`.
+struct Columns {
+    /// Expressions pushing each field's column names onto `names`, a `Vec<String>`.
+    names: Vec<TokenStream2>,
+    /// Field initializers reading from `row`, advancing `index`.
+    reads: Vec<TokenStream2>,
+    /// Expressions pushing each field's values onto `values`, a `Vec<&dyn ToSql>`.
+    values: Vec<TokenStream2>,
+    /// Literal names and values when no field is flattened, for `named_params!`.
+    named: Option<Vec<(String, TokenStream2)>>,
+    /// Number of columns, as an expression.
+    count: TokenStream2,
+}
+
+
+fn columns(input: &DeriveInput) -> syn::Result<Columns> {
+    let mut names = Vec::new();
+    let mut reads = Vec::new();
+    let mut values = Vec::new();
+    let mut named = Some(Vec::new());
+    let mut plain_count = 0usize;
+    let mut flatten_counts = Vec::new();
+
+    for field in named_fields(input)? {
+        let ident = field_ident(field);
+        let ty = &field.ty;
+        let sql = parse_sql_field(field)?;
+        if sql.skip {
+            reads.push(quote! { #ident: ::std::default::Default::default() });
+        }
+        else if let Some(prefix) = sql.flatten {
+            names.push(quote! {
+                names.extend(<#ty as crate::database::SqlColumns>::column_names(#prefix));
+            });
+            reads.push(quote! {
+                #ident: {
+                    let value = <#ty as crate::database::SqlColumns>::read_columns(row, index)?;
+                    index += <#ty as crate::database::SqlColumns>::COLUMN_COUNT;
+                    value
+                }
+            });
+            values.push(quote! {
+                values.extend(crate::database::SqlColumns::column_values(&self.#ident));
+            });
+            flatten_counts.push(quote! { <#ty as crate::database::SqlColumns>::COLUMN_COUNT });
+            named = None;
+        }
+        else {
+            let column = sql.column.unwrap_or_else(|| ident.to_string());
+            names.push(quote! { names.push(::std::string::String::from(#column)); });
+            let read = if is_f32(ty) {
+                quote! { crate::database::map_sql_real_to_f32(row.get_ref(index)?) }
+            }
+            else {
+                quote! { row.get(index)? }
+            };
+            reads.push(quote! {
+                #ident: {
+                    let value = #read;
+                    index += 1;
+                    value
+                }
+            });
+            values.push(quote! { values.push(&self.#ident); });
+            if let Some(named) = named.as_mut() {
+                named.push((column, quote! { &self.#ident }));
+            }
+            plain_count += 1;
+        }
+    }
+
+    let count = quote! { #plain_count #(+ #flatten_counts)* };
+    Ok(Columns { names, reads, values, named, count })
+}
+
+
+fn expand_sql(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let table = parse_sql_struct(input)?;
+    let Columns { names, reads, values, named, count } = columns(input)?;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let table_name = &table.table;
+
+    let (create_table, write_row) = match &table.source {
+        TableSource::Create(create) => {
+            let drop = format!("DROP TABLE IF EXISTS {}", table_name.value());
+            let create_table = quote! {
+                fn create_table(tx: &mut ::rusqlite::Transaction) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error>> {
+                    tx.execute(#drop, ())?;
+                    tx.execute(include_str!(#create), ())?;
+                    Ok(())
+                }
+            };
+            let write_row = match named {
+                Some(named) => {
+                    let params = named.iter().map(|(column, value)| {
+                        let param = format!(":{}", column);
+                        quote! { #param: #value }
+                    });
+                    quote! {
+                        fn write_row(&self, statement: &mut ::rusqlite::Statement) -> ::std::result::Result<usize, ::rusqlite::Error> {
+                            statement.execute(::rusqlite::named_params! { #(#params,)* })
+                        }
+                    }
+                }
+                None => quote! {
+                    fn write_row(&self, statement: &mut ::rusqlite::Statement) -> ::std::result::Result<usize, ::rusqlite::Error> {
+                        let params: ::std::vec::Vec<::std::string::String> = <Self as crate::database::Sql>::column_names()
+                            .iter()
+                            .map(|name| format!(":{}", name))
+                            .collect();
+                        let mut values: ::std::vec::Vec<&dyn ::rusqlite::ToSql> = ::std::vec::Vec::with_capacity(#count);
+                        #(#values)*
+                        let named: ::std::vec::Vec<(&str, &dyn ::rusqlite::ToSql)> = params.iter()
+                            .map(|param| param.as_str())
+                            .zip(values)
+                            .collect();
+                        statement.execute(named.as_slice())
+                    }
+                },
+            };
+            (create_table, write_row)
+        }
+        TableSource::ReadOnly => {
+            let create_table = quote! {
+                // Should never be used.
+                fn create_table(_tx: &mut ::rusqlite::Transaction) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error>> {
+                    Ok(())
+                }
+            };
+            let write_row = quote! {
+                // Should never be used.
+                fn write_row(&self, _statement: &mut ::rusqlite::Statement) -> ::std::result::Result<usize, ::rusqlite::Error> {
+                    Ok(0)
+                }
+            };
+            (create_table, write_row)
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics crate::database::Sql for #ident #ty_generics #where_clause {
+            #create_table
+
+            fn table_name<'a>() -> &'a str { #table_name }
+
+            #[allow(unused_assignments)]
+            fn read_row(row: &::rusqlite::Row, offset: usize) -> ::std::result::Result<Self, ::rusqlite::Error> {
+                let mut index = offset;
+                Ok(Self {
+                    #(#reads,)*
+                })
+            }
+
+            #write_row
+
+            fn column_names() -> ::std::vec::Vec<::std::string::String> {
+                let mut names = ::std::vec::Vec::with_capacity(#count);
+                #(#names)*
+                names
+            }
+        }
+    })
+}
+
+
+fn expand_sql_columns(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Columns { names, reads, values, count, .. } = columns(input)?;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    // Plain columns are pushed unprefixed and prefixed afterwards.
+    Ok(quote! {
+        impl #impl_generics crate::database::SqlColumns for #ident #ty_generics #where_clause {
+            const COLUMN_COUNT: usize = #count;
+
+            fn column_names(prefix: &str) -> ::std::vec::Vec<::std::string::String> {
+                let mut names: ::std::vec::Vec<::std::string::String> = ::std::vec::Vec::with_capacity(#count);
+                #(#names)*
+                names.into_iter().map(|name| format!("{}{}", prefix, name)).collect()
+            }
+
+            #[allow(unused_assignments)]
+            fn read_columns(row: &::rusqlite::Row, offset: usize) -> ::std::result::Result<Self, ::rusqlite::Error> {
+                let mut index = offset;
+                Ok(Self {
+                    #(#reads,)*
+                })
+            }
+
+            fn column_values(&self) -> ::std::vec::Vec<&dyn ::rusqlite::ToSql> {
+                let mut values: ::std::vec::Vec<&dyn ::rusqlite::ToSql> = ::std::vec::Vec::with_capacity(#count);
+                #(#values)*
+                values
+            }
+        }
+    })
+}
+
+
+fn expand_cel_eval(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut variables = Vec::new();
+    let mut adds = Vec::new();
+
+    for field in named_fields(input)? {
+        let cel = parse_cel_field(field)?;
+        if cel.skip {
+            continue;
+        }
+        let field_ident = field_ident(field);
+        let name = cel.name.unwrap_or_else(|| field_ident.to_string());
+        let add = match cel.with {
+            Some(with) => quote! { context.add_variable_from_value(#name, #with(&self.#field_ident)) },
+            None if is_unsigned(&field.ty) => quote! { context.add_variable(#name, i64::from(self.#field_ident))? },
+            None if option_inner(&field.ty).is_some_and(is_unsigned) => {
+                quote! { context.add_variable(#name, self.#field_ident.map(i64::from))? }
+            },
+            None => quote! { context.add_variable(#name, &self.#field_ident)? },
+        };
+        adds.push(quote! { #name => #add });
+        variables.push(name);
+    }
+    for (name, method) in parse_cel_methods(input)? {
+        adds.push(quote! { #name => context.add_variable(#name, self.#method())? });
+        variables.push(name);
+    }
+
+    for (idx, name) in variables.iter().enumerate() {
+        if variables[..idx].contains(name) {
+            return Err(syn::Error::new_spanned(ident, format!("duplicate CEL variable {}", name)));
+        }
+    }
+
+    let check = if variables.is_empty() {
+        quote! { variables.is_empty() }
+    }
+    else {
+        quote! { variables.iter().all(|name| matches!(*name, #(#variables)|*)) }
+    };
+
+    Ok(quote! {
+        impl #impl_generics crate::search::CelEval for #ident #ty_generics #where_clause {
+            fn add_cel_variables(&self, context: &mut ::cel::Context, variables: &[&str]) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error>> {
+                for name in variables {
+                    match *name {
+                        #(#adds,)*
+                        _ => {}
+                    }
+                }
+
+                Ok(())
+            }
+
+            fn check_cel_variables(variables: &[&str]) -> bool {
+                #check
+            }
+        }
+    })
+}
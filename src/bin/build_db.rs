@@ -4,20 +4,77 @@ use std::collections::HashSet;
 use std::error::Error;
 use std::fs;
 use std::io;
+use std::panic;
 use std::path;
 use std::process::{ChildStdout, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
 
 use baseball::register::Person;
+use baseball::retrosheet::event::{self, EventGame};
 use baseball::retrosheet::game;
+use baseball::chadwick::events::FullEvent;
 use baseball::chadwick::gamelogs::{gamelogs_from_boxscores, PlayerGameLogs};
-use baseball_tools::database::Sql;
+use baseball::chadwick::leverage::{LeverageIndex, LeverageLevel, PlateAppearanceLeverage};
+use baseball::chadwick::re24::RunExpectancy;
+use baseball::chadwick::wpa::WinExpectancy;
+use baseball_tools::database::{self, Sql};
+use baseball_tools::events::Event;
 use baseball_tools::games;
 use baseball_tools::player;
-use baseball_tools::internals::Guts;
+use baseball_tools::internals::{Guts, LeagueTotals, WobaWeights};
+use baseball_tools::parks::{ParkFactor, ParkFactors, ParkGame};
 
 use clap::Parser;
 use csv::ReaderBuilder;
-use rusqlite::{Connection, Result, Transaction, named_params};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use rayon::prelude::*;
+use rusqlite::{Connection, OptionalExtension, Result, Transaction, named_params};
+use sha2::{Digest, Sha256};
+
+
+/// Rosters giving the handedness of the players in the event files.
+const ROSTER_EXTENSIONS: &[&str] = &["ROS"];
+/// Box score files, which the player game logs fall back to for games missing from the event
+/// files.
+const BOXSCORE_EXTENSIONS: &[&str] = &["EBA", "EBN", "EBR"];
+/// Tables whose career game numbers are updated by `order_career_games`.
+const CAREER_GAME_TABLES: [&str; 3] = ["batting_gamelogs", "fielding_gamelogs", "pitching_gamelogs"];
+
+/// Career totals written with the career games of the batting game logs, as the column of the
+/// total and the expression added up for it.
+const BATTING_CAREER_TOTALS: &[(&str, &str)] = &[
+    ("career_pa", "pa"),
+    ("career_ab", "ab"),
+    ("career_r", "r"),
+    ("career_h", "h"),
+    ("career_d", "d"),
+    ("career_t", "t"),
+    ("career_hr", "hr"),
+    ("career_rbi", "rbi"),
+    ("career_bb", "bb"),
+    ("career_so", "so"),
+    ("career_sb", "sb"),
+];
+
+/// Career totals written with the career games of the pitching game logs.
+const PITCHING_CAREER_TOTALS: &[(&str, &str)] = &[
+    ("career_gs", "gs"),
+    ("career_w", "decision = 'W'"),
+    ("career_l", "decision = 'L'"),
+    ("career_sv", "decision = 'S'"),
+    ("career_ipouts", "ipouts"),
+    ("career_h", "h"),
+    ("career_er", "er"),
+    ("career_hr", "hr"),
+    ("career_bb", "bb"),
+    ("career_so", "so"),
+];
+
+
+/// Errors from the threads preparing seasons, which get sent back to the writer.
+type SendError = Box<dyn Error + Send + Sync>;
 
 
 #[derive(Parser)]
@@ -34,9 +91,37 @@ struct DatabaseArgs {
     #[arg(long)]
     count_career_games: bool,
 
+    /// Reload seasons even when their source files haven't changed since they were loaded.
+    #[arg(long)]
+    force: bool,
+
+    /// Number of seasons to parse at once. Defaults to the number of CPUs.
+    #[arg(short, long)]
+    jobs: Option<usize>,
+
+    /// Build player game logs from the event files directly instead of running cwbox.
+    #[arg(long)]
+    native_boxscores: bool,
+
+    /// Build each season's run expectancy matrix and add RE24 to the player game logs.
+    #[arg(long)]
+    re24: bool,
+
+    /// Build each season's win expectancy model and add WPA to the player game logs.
+    #[arg(long)]
+    wpa: bool,
+
+    /// Find the leverage index of each plate appearance and split the player game logs by it.
+    #[arg(long)]
+    leverage: bool,
+
     #[arg(short, long)]
     games: bool,
 
+    /// Load every play-by-play event into the events table.
+    #[arg(short = 'E', long)]
+    events: bool,
+
     #[arg(short = 'R', long)]
     register_dir: Option<path::PathBuf>,
 
@@ -53,6 +138,28 @@ struct GameLoader<'a> {
 }
 
 
+/// A season of games ready to be written.
+struct SeasonGames {
+    season: String,
+    season_load: SeasonLoad,
+    games: Vec<games::GameLog>,
+    teams: Vec<games::Team>,
+    guts: Guts,
+}
+
+
+/// A season loaded from a set of source files, used to skip seasons that haven't changed.
+struct SeasonLoad {
+    /// Which tables were loaded: games, events or gamelogs.
+    source: &'static str,
+    season: u16,
+    /// Hash of the source files.
+    hash: String,
+    /// Options changing what gets loaded, like --re24.
+    options: String,
+}
+
+
 impl<'a> GameLoader<'a> {
     fn new(conn: &'a mut Connection, retrosheet_dir: path::PathBuf) -> Self {
         Self {
@@ -91,24 +198,12 @@ impl<'a> GameLoader<'a> {
         Ok(())
     }
 
-    fn load_season_gamelog(&self, season: &String) -> Result<Vec<games::GameLog>, Box<dyn Error>> {
-        let season_dir = self.retrosheet_dir.join(season);
-        // Chadwick's Retrosheet seasons either have a GLYYYY.TXT or glYYYY.txt file.
-        let gl_file_names = [format!("GL{}.TXT", season), format!("gl{}.txt", season)];
-        let mut gamelog_file = None;
-        for gl_file in gl_file_names {
-            let gl_path = season_dir.join(gl_file);
-            if gl_path.exists() {
-                gamelog_file = Some(gl_path);
-                break;
-            }
-        }
-
+    fn load_season_gamelog(gamelog_file: Option<&path::PathBuf>) -> Result<Vec<games::GameLog>, SendError> {
         let mut games = Vec::new();
         if let Some(gl_path) = gamelog_file {
             let mut reader = ReaderBuilder::new()
                 .has_headers(false)
-                .from_path(&gl_path)?;
+                .from_path(gl_path)?;
             for result in reader.deserialize() {
                 match result {
                     Ok(game) => {
@@ -124,32 +219,159 @@ impl<'a> GameLoader<'a> {
         Ok(games)
     }
 
-    fn load(&mut self, seasons: &[String], initialize: bool) -> Result<(), Box<dyn Error>> {
+    fn prepare_season(retrosheet_dir: &path::Path, season: String, season_load: SeasonLoad) -> Result<SeasonGames, SendError> {
+        let season_dir = retrosheet_dir.join(&season);
+        let gamelog_file = find_gamelog_file(&season_dir, &season);
+        let games = Self::load_season_gamelog(gamelog_file.as_ref())?;
+        // The league totals give the FIP constant and run environment while the run values of the
+        // season's plate appearances give the wOBA weights.
+        let totals = games.iter().fold(LeagueTotals::new(), |mut totals, g| {
+            totals.add_game(g);
+            totals
+        });
+        // Without events the weights are unknown, which doesn't stop the games from loading.
+        let events: Vec<FullEvent> = match event::load_season_games(&season_dir) {
+            Ok(event_games) => {
+                event_games.into_iter()
+                    .flat_map(|game| game.events.into_iter().map(|(full, _)| full))
+                    .collect()
+            }
+            Err(err) => {
+                eprintln!("Could not load events of {} season: {}", season, err);
+                Vec::new()
+            }
+        };
+        let guts = Guts::from_season(season_load.season, &totals, &events);
+        let teams = match event::find_team_file(&season_dir, &season) {
+            Some(team_file) => {
+                event::load_teams(fs::File::open(team_file)?).into_iter()
+                    .map(|team| games::Team::new(season_load.season, team))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        Ok(SeasonGames {
+            season,
+            season_load,
+            games,
+            teams,
+            guts,
+        })
+    }
+
+    fn write_season(&mut self, season: SeasonGames) -> Result<(), Box<dyn Error>> {
+        println!("Found {} games in {} season", season.games.len(), season.season);
+        let guts = &season.guts;
+        println!("Season {} FIP constant: {}, runs per PA: {}, runs per win: {}", season.season, guts.fip_constant, guts.runs_per_pa, guts.runs_per_win);
+        if guts.woba.scale.is_nan() {
+            println!("Season {} has no events for wOBA weights", season.season);
+        }
+        else {
+            println!("Season {} wOBA scale: {}", season.season, guts.woba.scale);
+        }
+        let tx = self.conn.transaction().expect("Could not create transaction");
+        update_guts(&tx, guts)?;
+        // Replace the season's games if it was loaded before.
+        tx.execute("DELETE FROM games WHERE strftime('%Y', date) = :season", named_params! {":season": season.season})?;
+        Self::insert_games(&tx, &season.games)?;
+        tx.execute("DELETE FROM teams WHERE season = :season", named_params! {":season": season.season})?;
+        let placeholders: Vec<String> = games::Team::column_names().iter().map(|name| format!(":{}", name)).collect();
+        let mut insert = tx.prepare(&format!("INSERT INTO teams VALUES ({})", placeholders.join(", ")))?;
+        for team in &season.teams {
+            team.write_row(&mut insert)?;
+        }
+        drop(insert);
+        season.season_load.record(&tx)?;
+        // The season's player game logs read their team games and guts from these tables.
+        SeasonLoad::forget(&tx, "gamelogs", season.season_load.season)?;
+        tx.commit().expect("Failed to commit transaction");
+        Ok(())
+    }
+
+    /// Recompute the park factors from every game, forgetting the player game logs of the seasons
+    /// whose run factors changed since they're adjusted by them.
+    fn update_park_factors(&mut self) -> Result<(), Box<dyn Error>> {
+        let tx = self.conn.transaction()?;
+        // Unknown hits and home runs are NULL or negative.
+        let mut statement = tx.prepare(
+            "SELECT CAST(strftime('%Y', date) AS INTEGER), park_id, home_team, visitor_team,
+                visitor_score + home_score,
+                visitor_hits + home_hits,
+                CASE WHEN visitor_homeruns >= 0 AND home_homeruns >= 0 THEN visitor_homeruns + home_homeruns END
+            FROM games"
+        )?;
+        let games: Vec<ParkGame> = statement.query_map((), |row| Ok(ParkGame {
+            season: row.get(0)?,
+            park_id: row.get(1)?,
+            home_team: row.get(2)?,
+            visitor_team: row.get(3)?,
+            runs: row.get(4)?,
+            hits: row.get(5)?,
+            hr: row.get(6)?,
+        }))?
+            .collect::<Result<_, _>>()?;
+        drop(statement);
+
+        let park_factors = ParkFactor::from_games(&games);
+        println!("Updating {} park factors", park_factors.len());
+        // Compare the bits to treat every unknown factor as the same.
+        let run_factor = |runs: f32| (!runs.is_nan()).then(|| runs.to_bits());
+        let mut statement = tx.prepare("SELECT season, park_id, runs FROM park_factors")?;
+        let previous: HashMap<(u16, String), Option<u32>> = statement.query_map((), |row| {
+            Ok(((row.get(0)?, row.get(1)?), run_factor(database::map_sql_real_to_f32(row.get_ref(2)?))))
+        })?
+            .collect::<Result<_, _>>()?;
+        drop(statement);
+        let current: HashMap<(u16, String), Option<u32>> = park_factors.iter()
+            .map(|factor| ((factor.season, factor.park_id.clone()), run_factor(factor.runs)))
+            .collect();
+        let changed: HashSet<u16> = previous.iter()
+            .filter(|(park, runs)| current.get(park) != Some(runs))
+            .chain(current.iter().filter(|(park, runs)| previous.get(park) != Some(runs)))
+            .map(|((season, _), _)| *season)
+            .collect();
+        for season in changed {
+            SeasonLoad::forget(&tx, "gamelogs", season)?;
+        }
+        tx.execute("DELETE FROM park_factors", ())?;
+        let placeholders: Vec<String> = ParkFactor::column_names().iter().map(|name| format!(":{}", name)).collect();
+        let mut insert = tx.prepare(&format!("INSERT INTO park_factors VALUES ({})", placeholders.join(", ")))?;
+        for park_factor in &park_factors {
+            park_factor.write_row(&mut insert)?;
+        }
+        drop(insert);
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load(&mut self, seasons: &[String], pool: &ThreadPool, initialize: bool, force: bool) -> Result<(), Box<dyn Error>> {
         if initialize {
             println!("Creating games tables");
             self.create_games_table()?;
+            SeasonLoad::clear(self.conn, "games")?;
         }
 
-        for season in seasons {
-            println!("Loading games from {} season", season);
-            let games = self.load_season_gamelog(season)?;
-            println!("Found {} games", games.len());
-            // Iterate one more time through every pitching game to calculate the league ERA and the
-            // unscaled FIP values to get the FIP constant for this season.
-            let league_stats = games.iter().fold(PitcherStats::new_with_fip(0.0), |mut lgstats, g| {
-                lgstats.add_team_gamelog(g);
-                lgstats
-            });
-            let league_fip_constant = league_stats.era() - league_stats.fip();
-            println!("Season {} ERA: {}, FIP constant: {}", season, league_stats.era(), league_fip_constant);
-            let season_numeric = season.parse::<u16>()?;
-            let mut guts = Guts::new(season_numeric);
-            guts.fip_constant = league_fip_constant;
-
-            let tx = self.conn.transaction().expect("Could not create transaction");
-            update_fip_constant(&tx, &guts)?;
-            Self::insert_games(&tx, &games)?;
-            tx.commit().expect("Failed to commit transaction");
+        let retrosheet_dir = self.retrosheet_dir.clone();
+        let pending = seasons_to_load(self.conn, pool, seasons, force, "Games", |season| {
+            // The season's events give the wOBA weights, so they're part of the source.
+            let season_dir = retrosheet_dir.join(season);
+            let mut source_files = event_source_files(&season_dir)?;
+            source_files.extend(find_gamelog_file(&season_dir, season));
+            source_files.extend(event::find_team_file(&season_dir, season));
+            SeasonLoad::new("games", season.parse::<u16>()?, &source_files, String::new())
+        })?;
+        let loaded = pending.len();
+        load_in_parallel(
+            pool,
+            "games",
+            pending,
+            |(season, season_load)| Self::prepare_season(&retrosheet_dir, season, season_load),
+            |season| self.write_season(season),
+        )?;
+        // A season's games change the park factors of the seasons after it, so redo them all.
+        if loaded > 0 {
+            self.update_park_factors()?;
         }
 
         if initialize {
@@ -169,22 +391,166 @@ impl<'a> GameLoader<'a> {
     }
 }
 
+struct EventLoader<'a> {
+    conn: &'a mut Connection,
+    retrosheet_dir: path::PathBuf,
+}
+
+
+impl<'a> EventLoader<'a> {
+    fn new(conn: &'a mut Connection, retrosheet_dir: path::PathBuf) -> Self {
+        Self {
+            conn,
+            retrosheet_dir
+        }
+    }
+
+    fn insert_events(tx: &Transaction, events: &[Event]) -> Result<(), Box<dyn Error>> {
+        let mut insert_sql = String::with_capacity(1000);
+        insert_sql.push_str("INSERT INTO ");
+        insert_sql.push_str(Event::table_name());
+        insert_sql.push_str(" VALUES (");
+        for (idx, name) in Event::column_names().iter().enumerate() {
+            if idx > 0 {
+                insert_sql.push_str(", ");
+            }
+            insert_sql.push(':');
+            insert_sql.push_str(name);
+        }
+        insert_sql.push(')');
+
+        let mut insert = tx.prepare(&insert_sql)?;
+        for event in events {
+            event.write_row(&mut insert)?;
+        }
+
+        Ok(())
+    }
+
+    fn load(&mut self, seasons: &[String], pool: &ThreadPool, initialize: bool, force: bool) -> Result<(), Box<dyn Error>> {
+        if initialize {
+            println!("Creating events table");
+            let mut tx = self.conn.transaction()?;
+            Event::create_table(&mut tx)?;
+            tx.commit()?;
+            SeasonLoad::clear(self.conn, "events")?;
+        }
+
+        let retrosheet_dir = self.retrosheet_dir.clone();
+        let pending = seasons_to_load(self.conn, pool, seasons, force, "Events", |season| {
+            let source_files = event_source_files(&retrosheet_dir.join(season))?;
+            SeasonLoad::new("events", season.parse::<u16>()?, &source_files, String::new())
+        })?;
+        load_in_parallel(
+            pool,
+            "events",
+            pending,
+            |(season, season_load)| {
+                let event_games = event::load_season_games(&retrosheet_dir.join(&season))?;
+                let events: Vec<Event> = event_games.iter()
+                    .flat_map(|game| game.events.iter().map(Event::from))
+                    .collect();
+                Ok((season, season_load, events))
+            },
+            |(season, season_load, events)| {
+                println!("Found {} events in {} season", events.len(), season);
+                let tx = self.conn.transaction().expect("Could not create transaction");
+                // Replace the season's events if it was loaded before. The year is part of the game ID.
+                tx.execute("DELETE FROM events WHERE substr(game_id, 4, 4) = :season", named_params! {":season": season})?;
+                Self::insert_events(&tx, &events)?;
+                season_load.record(&tx)?;
+                tx.commit().expect("Failed to commit transaction");
+                Ok(())
+            },
+        )?;
+
+        if initialize {
+            println!("Creating event indexes");
+            self.conn.execute_batch(include_str!("../sql/create_events_indexes.sql"))?;
+        }
+
+        Ok(())
+    }
+}
+
+
 struct PlayerGamelogLoader<'a> {
     conn: &'a mut Connection,
+    settings: GamelogSettings,
+}
+
+
+/// How player game logs get loaded, shared with the threads preparing each season.
+struct GamelogSettings {
     retrosheet_dir: path::PathBuf,
+    native_boxscores: bool,
+    re24: bool,
+    wpa: bool,
+    leverage: bool,
+}
+
+
+/// What a season needs from the database before its game logs can be prepared.
+struct SeasonGamelogInput {
+    season: String,
+    season_load: SeasonLoad,
+    game_ids: HashSet<String>,
+    team_games: HashMap<TeamGameLogKey, TeamGameLogValue>,
+    guts: Guts,
+    park_factors: ParkFactors,
+}
+
+
+/// A season of player game logs ready to be written.
+struct SeasonGamelogs {
+    season: String,
+    season_load: SeasonLoad,
+    run_expectancy: Option<RunExpectancy>,
+    batting: Vec<player::BattingGamelog>,
+    fielding: Vec<player::FieldingGamelog>,
+    pitching: Vec<player::PitchingGamelog>,
 }
 
 
 type DatedPlayerGamelogs<T> = (T, chrono::NaiveDate);
 
 
+/// Average run factor of the parks a player has played in.
+struct ParkAverage {
+    total: f32,
+    games: u16,
+}
+
+
 struct BattingSlashLine {
     ab: u16,
     h: u16,
+    d: u16,
+    t: u16,
+    hr: u16,
     tb: u16,
-    bb: u8,
-    hbp: u8,
-    sf: u8,
+    bb: u16,
+    ibb: u16,
+    hbp: u16,
+    sf: u16,
+}
+
+
+/// A player's plate appearances in a game split by leverage.
+#[derive(Default)]
+struct GameLeverage {
+    li_total: f32,
+    pa: u16,
+    low: player::LeverageSplit,
+    medium: player::LeverageSplit,
+    high: player::LeverageSplit,
+}
+
+
+/// Running total of a per-game value for game logs ordered by player.
+struct SeasonTotal {
+    player_id: String,
+    total: f32,
 }
 
 
@@ -200,6 +566,14 @@ struct PitcherStats {
 }
 
 
+/// Totals of an event value, like RE24 or WPA, for each (player, game).
+type PlayerGameTotals = HashMap<(String, String), f32>;
+
+
+/// Leverage splits for each (player, game).
+type PlayerGameLeverage = HashMap<(String, String), GameLeverage>;
+
+
 #[derive(Eq, Hash, PartialEq)]
 struct TeamGameLogKey {
     game_id: String,
@@ -218,14 +592,22 @@ struct CareerGame {
     player_id: String,
     game_id: String,
     date: chrono::NaiveDate,
+    /// Values of the table's career totals in this game.
+    totals: Vec<u32>,
 }
 
 
 impl<'a> PlayerGamelogLoader<'a> {
-    fn new(conn: &'a mut Connection, retrosheet_dir: path::PathBuf) -> Self {
+    fn new(conn: &'a mut Connection, retrosheet_dir: path::PathBuf, native_boxscores: bool, re24: bool, wpa: bool, leverage: bool) -> Self {
         Self {
             conn,
-            retrosheet_dir
+            settings: GamelogSettings {
+                retrosheet_dir,
+                native_boxscores,
+                re24,
+                wpa,
+                leverage,
+            },
         }
     }
 
@@ -251,64 +633,6 @@ impl<'a> PlayerGamelogLoader<'a> {
         Ok(())
     }
 
-    fn load_team_gamelogs(&self, season: &str) -> Result<(HashSet<String>, HashMap<TeamGameLogKey, TeamGameLogValue>), Box<dyn Error>> {
-        let mut statement = self.conn.prepare(
-            "SELECT game_id, date, visitor_team, visitor_team_game_number, home_team, home_team_game_number
-            FROM games
-            WHERE strftime('%Y', games.date) = :season"
-        )?;
-        let mut game_ids = HashSet::new();
-        let mut games = HashMap::new();
-        let mut rows = statement.query(&[(":season", season)])?;
-        while let Some(row) = rows.next()? {
-            let game_id: String = row.get(0)?;
-            game_ids.insert(game_id.clone());
-            let date: chrono::NaiveDate = row.get(1)?;
-            let home_team = TeamGameLogKey {
-                game_id: game_id.clone(),
-                team_id: row.get(4)?,
-            };
-            let home_team_value = TeamGameLogValue {
-                date,
-                team_game_number: row.get(5)?,
-            };
-            games.insert(home_team, home_team_value);
-
-            let visitor_team = TeamGameLogKey {
-                game_id: game_id.clone(),
-                team_id: row.get(2)?,
-            };
-            let visitor_team_value = TeamGameLogValue {
-                date,
-                team_game_number: row.get(3)?,
-            };
-            games.insert(visitor_team, visitor_team_value);
-        }
-        Ok((game_ids, games))
-    }
-
-    fn load_season_boxscores(&self, season: &str, event_files: bool) -> Result<ChildStdout, Box<dyn Error>> {
-        let season_dir = self.retrosheet_dir.join(season);
-        let mut cwbox = Command::new("cwbox");
-        cwbox.args(["-q", "-y", season, "-X"]).current_dir(&season_dir);
-        if event_files {
-            cwbox.args(find_event_files(&season_dir)?);
-        }
-        else {
-            cwbox.args(find_boxscore_files(&season_dir)?);
-        }
-        let command = cwbox.stdin(Stdio::null()).stdout(Stdio::piped());
-        match command.spawn() {
-            Ok(mut child) => {
-                let stdout = child.stdout.take().expect("cwbox stdout handle not available");
-                Ok(stdout)
-            }
-            Err(err) => {
-                Err(Box::new(err))
-            }
-        }
-    }
-
     fn dated_gamelog_cmp<T: player::PlayerGamelog>(a: &DatedPlayerGamelogs<T>, b: &DatedPlayerGamelogs<T>) -> cmp::Ordering {
         let player_cmp = a.0.player_id().cmp(b.0.player_id());
         match player_cmp {
@@ -347,10 +671,11 @@ impl<'a> PlayerGamelogLoader<'a> {
         internal_gamelogs.into_iter().map(|entry| entry.0).collect()
     }
 
-    fn order_batting_gamelogs(mut gamelogs: Vec<player::BattingGamelog>) -> Vec<player::BattingGamelog> {
+    fn order_batting_gamelogs(mut gamelogs: Vec<player::BattingGamelog>, guts: &Guts, park_factors: &ParkFactors) -> Vec<player::BattingGamelog> {
         let mut player = "";
         let mut last_game = "";
         let mut slash_line = BattingSlashLine::new();
+        let mut parks = ParkAverage::new();
         // Start at zero because whether the current game is the same as the previous is check
         // before setting the player's season game count.
         let mut season_game = 0;
@@ -360,23 +685,30 @@ impl<'a> PlayerGamelogLoader<'a> {
             }
             if player == gl.player_id {
                 slash_line.add_gamelog(gl);
+                parks.add(park_factors.get(&gl.game_id));
                 let stats = slash_line.slash_line();
                 gl.season_game = season_game;
                 gl.avg = stats.0;
                 gl.obp = stats.1;
                 gl.slg = stats.2;
+                gl.woba = slash_line.woba(&guts.woba);
             }
             else {
                 player = gl.player_id.as_str();
                 slash_line.clear();
                 slash_line.add_gamelog(gl);
+                parks.clear();
+                parks.add(park_factors.get(&gl.game_id));
                 let stats = slash_line.slash_line();
                 gl.season_game = 1;
                 gl.avg = stats.0;
                 gl.obp = stats.1;
                 gl.slg = stats.2;
+                gl.woba = slash_line.woba(&guts.woba);
                 season_game = 1;
             }
+            gl.ops_plus = guts.ops_plus(gl.obp, gl.slg, parks.factor());
+            gl.wrc_plus = guts.wrc_plus(gl.woba, parks.factor());
             last_game = gl.game_id.as_str();
         }
         gamelogs
@@ -403,10 +735,11 @@ impl<'a> PlayerGamelogLoader<'a> {
         gamelogs
     }
 
-    fn order_pitching_gamelogs(mut gamelogs: Vec<player::PitchingGamelog>, fip_constant: f32) -> Vec<player::PitchingGamelog> {
+    fn order_pitching_gamelogs(mut gamelogs: Vec<player::PitchingGamelog>, guts: &Guts, park_factors: &ParkFactors) -> Vec<player::PitchingGamelog> {
         let mut player = "";
         let mut last_game = "";
-        let mut pitcher_stats = PitcherStats::new_with_fip(fip_constant);
+        let mut pitcher_stats = PitcherStats::new_with_fip(guts.fip_constant);
+        let mut parks = ParkAverage::new();
         let mut season_game = 0;
         for gl in gamelogs.iter_mut() {
             if last_game != gl.game_id {
@@ -414,6 +747,7 @@ impl<'a> PlayerGamelogLoader<'a> {
             }
             if player == gl.player_id {
                 pitcher_stats.add_gamelog(gl);
+                parks.add(park_factors.get(&gl.game_id));
                 gl.season_game = season_game;
                 gl.era = pitcher_stats.era();
                 gl.fip = pitcher_stats.fip();
@@ -422,47 +756,94 @@ impl<'a> PlayerGamelogLoader<'a> {
                 player = gl.player_id.as_str();
                 pitcher_stats.clear();
                 pitcher_stats.add_gamelog(gl);
+                parks.clear();
+                parks.add(park_factors.get(&gl.game_id));
                 gl.season_game = 1;
                 gl.era = pitcher_stats.era();
                 gl.fip = pitcher_stats.fip();
                 season_game = 1;
             }
+            gl.era_plus = guts.era_plus(gl.era, parks.factor());
+            gl.fip_minus = guts.fip_minus(gl.fip, parks.factor());
             last_game = gl.game_id.as_str();
         }
         gamelogs
     }
 
-    fn load(&mut self, seasons: &Vec<String>, initialize: bool) -> Result<(), Box<dyn Error>> {
+    fn write_season(conn: &mut Connection, season: SeasonGamelogs) -> Result<(), Box<dyn Error>> {
+        let season_numeric = season.season_load.season;
+        let tx = conn.transaction().expect("Could not create transaction");
+        if let Some(re) = &season.run_expectancy {
+            update_run_expectancy(&tx, season_numeric, re)?;
+        }
+        // Replace the season's game logs if it was loaded before, and mark the career game
+        // numbers of its players as needing an update.
+        for table in CAREER_GAME_TABLES {
+            tx.execute(&format!("DELETE FROM {} WHERE substr(game_id, 4, 4) = :season", table), named_params! {":season": season.season})?;
+            tx.execute(
+                "INSERT OR IGNORE INTO career_game_updates (table_name, season) VALUES (:table, :season)",
+                named_params! {":table": table, ":season": season_numeric},
+            )?;
+        }
+        Self::insert_player_game_logs(&tx, &season.batting)?;
+        Self::insert_player_game_logs(&tx, &season.fielding)?;
+        Self::insert_player_game_logs(&tx, &season.pitching)?;
+        season.season_load.record(&tx)?;
+        tx.commit().expect("Failed to commit transaction");
+        Ok(())
+    }
+
+    fn load(&mut self, seasons: &[String], pool: &ThreadPool, initialize: bool, force: bool) -> Result<(), Box<dyn Error>> {
         if initialize {
             println!("Creating gamelog tables");
             let mut tx = self.conn.transaction()?;
             player::BattingGamelog::create_table(&mut tx)?;
             player::FieldingGamelog::create_table(&mut tx)?;
             player::PitchingGamelog::create_table(&mut tx)?;
+            tx.execute("DELETE FROM career_game_updates", ())?;
             tx.commit()?;
+            SeasonLoad::clear(self.conn, "gamelogs")?;
         }
 
-        for season in seasons {
-            // Load team gamelogs.
-            println!("Loading team game logs from {} season", season);
-            let (game_ids, team_games) = self.load_team_gamelogs(season)?;
-            let (batting_gamelogs, fielding_gamelogs, pitching_gamelogs) = self.load_player_game_logs(season, &game_ids)?;
-
-            // Transform Chadwick gamelogs into internal version for the database and sort to allow
-            // marking which game number in the season this is for a player.
-            let season_numeric = season.parse::<u16>()?;
-            let fip_constant = get_fip_constant(self.conn, season_numeric)?.unwrap_or_default();
-            let batting_gamelogs = Self::order_batting_gamelogs(Self::order_dated_gamelogs(season_numeric.into(), batting_gamelogs, &team_games));
-            let fielding_gamelogs = Self::order_fielding_gamelogs(Self::order_dated_gamelogs(season_numeric.into(), fielding_gamelogs, &team_games));
-            let pitching_gamelogs = Self::order_pitching_gamelogs(Self::order_dated_gamelogs(season_numeric.into(), pitching_gamelogs, &team_games), fip_constant);
-
-            let tx = self.conn.transaction().expect("Could not create transaction");
-            Self::insert_player_game_logs(&tx, &batting_gamelogs)?;
-            Self::insert_player_game_logs(&tx, &fielding_gamelogs)?;
-            Self::insert_player_game_logs(&tx, &pitching_gamelogs)?;
-            tx.commit().expect("Failed to commit transaction");
+        let settings = &self.settings;
+        let pending = seasons_to_load(self.conn, pool, seasons, force, "Player game logs", |season| {
+            let source_files = gamelog_source_files(&settings.retrosheet_dir.join(season), season)?;
+            SeasonLoad::new("gamelogs", season.parse::<u16>()?, &source_files, settings.options())
+        })?;
+
+        // The team games, guts and park factors come from the database, so read them up front for the
+        // threads preparing the seasons.
+        let mut inputs = Vec::with_capacity(pending.len());
+        for (season, season_load) in pending {
+            let (game_ids, team_games) = load_team_gamelogs(self.conn, &season)?;
+            let guts = match get_guts(self.conn, season_load.season)? {
+                Some(guts) => guts,
+                None => {
+                    eprintln!("No guts for {} season, load its games first", season);
+                    Guts::new(season_load.season)
+                }
+            };
+            let season_numeric = Some(i32::from(season_load.season));
+            let park_factors = ParkFactors::load(self.conn, season_numeric, season_numeric)?;
+            inputs.push(SeasonGamelogInput {
+                season,
+                season_load,
+                game_ids,
+                team_games,
+                guts,
+                park_factors,
+            });
         }
 
+        let conn = &mut *self.conn;
+        load_in_parallel(
+            pool,
+            "player game logs",
+            inputs,
+            |input| settings.prepare_season(input),
+            |season| Self::write_season(conn, season),
+        )?;
+
         if initialize {
             println!("Creating gamelog indexes");
             self.conn.execute_batch(
@@ -483,70 +864,27 @@ impl<'a> PlayerGamelogLoader<'a> {
         Ok(())
     }
 
-    fn load_player_game_logs(&self, season: &str, game_ids: &HashSet<String>) -> Result<PlayerGameLogs, Box<dyn Error>> {
-            // Load boxscores from the event files to get more accurate data.
-            println!("Loading player game logs from {} season", season);
-            let stdout = self.load_season_boxscores(season, true)?;
-            let (mut batting_gamelogs, mut fielding_gamelogs, mut pitching_gamelogs) = gamelogs_from_boxscores(io::BufReader::new(stdout));
-
-            // Collect all games found from loading the event files and then check the overall list
-            // against what was found to detect any missing games.
-            let mut found_game_ids = HashSet::with_capacity(game_ids.len());
-            for game_log in &pitching_gamelogs {
-                found_game_ids.insert(game_log.game_id.clone());
-            }
-
-            let mut missing_game_ids = HashSet::new();
-            for game_id in game_ids {
-                if !found_game_ids.contains(game_id) {
-                    missing_game_ids.insert(game_id.clone());
-                }
-            }
-
-            // Missing some games from the event files. Load the box score event files to get the
-            // missing games.
-            if !missing_game_ids.is_empty() {
-                println!("Missing {} games from event files. Loading box score files.", missing_game_ids.len());
-                let stdout = self.load_season_boxscores(season, false)?;
-                let (be_batting_logs, be_fielding_logs, be_pitching_logs) = gamelogs_from_boxscores(io::BufReader::new(stdout));
-
-                for game_log in be_batting_logs.into_iter() {
-                    if missing_game_ids.contains(&game_log.game_id) {
-                        batting_gamelogs.push(game_log);
-                    }
-                }
-
-                for game_log in be_fielding_logs.into_iter() {
-                    if missing_game_ids.contains(&game_log.game_id) {
-                        fielding_gamelogs.push(game_log);
-                    }
-                }
-
-                for game_log in be_pitching_logs.into_iter() {
-                    if missing_game_ids.contains(&game_log.game_id) {
-                        pitching_gamelogs.push(game_log);
-                    }
-                }
-            }
-
-            Ok((batting_gamelogs, fielding_gamelogs, pitching_gamelogs))
-    }
-
+    /// Update the career game numbers of the players in the seasons plus any seasons loaded since
+    /// their tables were last ordered. Each table is committed with its pending seasons cleared, so
+    /// an interrupted run picks up with the tables it didn't finish.
     fn order_career_games(&mut self, seasons: &[String]) -> Result<(), Box<dyn Error>> {
-        if seasons.is_empty() {
-            return Ok(());
-        }
-        // Convert the seasons to integers to ensure a consistent sort.
-        let mut seasons: Vec<u16> = seasons.iter().flat_map(|s| s.parse::<u16>()).collect();
-        seasons.sort_unstable();
-        let oldest = seasons.first().expect("Expected a season");
-        let newest = seasons.last().expect("Expected a season");
-        let start_date = format!("{}-01-01", oldest);
-        let end_date = format!("{}-12-31", newest);
+        for table in CAREER_GAME_TABLES {
+            let mut statement = self.conn.prepare("SELECT season FROM career_game_updates WHERE table_name = :table")?;
+            // Convert the seasons to integers to ensure a consistent sort.
+            let mut table_seasons: Vec<u16> = statement.query_map(named_params! {":table": table}, |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            drop(statement);
+            table_seasons.extend(seasons.iter().flat_map(|s| s.parse::<u16>()));
+            table_seasons.sort_unstable();
+            let (Some(oldest), Some(newest)) = (table_seasons.first(), table_seasons.last())
+            else {
+                continue;
+            };
+            let start_date = format!("{}-01-01", oldest);
+            let end_date = format!("{}-12-31", newest);
 
-        self.order_table("batting_gamelogs", &start_date, &end_date)?;
-        self.order_table("fielding_gamelogs", &start_date, &end_date)?;
-        self.order_table("pitching_gamelogs", &start_date, &end_date)?;
+            self.order_table(table, &start_date, &end_date)?;
+        }
 
         Ok(())
     }
@@ -571,15 +909,27 @@ impl<'a> PlayerGamelogLoader<'a> {
         // Update those players.
         println!("Ordering table {} with {} players", table, players.len());
 
+        let totals = match table {
+            "batting_gamelogs" => BATTING_CAREER_TOTALS,
+            "pitching_gamelogs" => PITCHING_CAREER_TOTALS,
+            _ => &[],
+        };
+        let total_values: String = totals.iter()
+            .map(|(_, value)| format!(", IFNULL({}, 0)", value))
+            .collect();
+        let total_updates: String = totals.iter()
+            .map(|(column, _)| format!(", {} = :{}", column, column))
+            .collect();
+        let total_params: Vec<String> = totals.iter().map(|(column, _)| format!(":{}", column)).collect();
         let select_player = format!(
-            "SELECT gl.game_id, games.date
+            "SELECT gl.game_id, games.date{}
             FROM {} AS gl JOIN games ON gl.game_id = games.game_id
             WHERE player_id = :player",
-            table);
+            total_values, table);
         let update_player = format!(
-            "UPDATE {} SET career_game = :game_number
+            "UPDATE {} SET career_game = :game_number{}
             WHERE player_id = :player AND game_id = :game_id",
-            table);
+            table, total_updates);
         let mut select_statement = tx.prepare(&select_player)?;
         let mut update_statement = tx.prepare(&update_player)?;
         let mut games_updated = 0;
@@ -590,6 +940,7 @@ impl<'a> PlayerGamelogLoader<'a> {
                     player_id: player_id.clone(),
                     game_id: row.get(0)?,
                     date: row.get(1)?,
+                    totals: (0..totals.len()).map(|i| row.get(i + 2)).collect::<Result<_>>()?,
                 })
             )?
                 .flatten()
@@ -598,28 +949,28 @@ impl<'a> PlayerGamelogLoader<'a> {
 
             // The fielding game log table will have a row for every position a player plays in a
             // game. Each career game should only increment for different games so update all
-            // matching (player, game) options (even if they have a different team) and skip to the
-            // next unique game.
-            let mut game_number = 1;
-            let mut last_game = "";
-            for game in games.iter() {
-                if game.game_id == last_game {
-                    continue;
-                }
-                update_statement.execute(
-                    named_params! {
-                        ":game_number": game_number,
-                        ":player": &player_id,
-                        ":game_id": &game.game_id,
+            // matching (player, game) options (even if they have a different team) once, after
+            // adding every row of the game to the career totals.
+            let mut career_totals = vec![0; totals.len()];
+            for (game_number, game_rows) in (1_u32..).zip(games.chunk_by(|a, b| a.game_id == b.game_id)) {
+                for row in game_rows {
+                    for (total, value) in career_totals.iter_mut().zip(&row.totals) {
+                        *total += value;
                     }
-                )?;
-                game_number += 1;
+                }
+                let mut params: Vec<(&str, &dyn rusqlite::ToSql)> = vec![
+                    (":game_number", &game_number),
+                    (":player", player_id),
+                    (":game_id", &game_rows[0].game_id),
+                ];
+                params.extend(total_params.iter().map(String::as_str).zip(career_totals.iter().map(|t| t as &dyn rusqlite::ToSql)));
+                update_statement.execute(params.as_slice())?;
                 games_updated += 1;
-                last_game = game.game_id.as_str();
             }
         }
         drop(select_statement);
         drop(update_statement);
+        tx.execute("DELETE FROM career_game_updates WHERE table_name = :table", named_params! {":table": table})?;
         tx.commit().expect("Failed to commit transaction");
         println!("Updated {} games for {}", games_updated, table);
 
@@ -628,13 +979,324 @@ impl<'a> PlayerGamelogLoader<'a> {
 }
 
 
+impl GamelogSettings {
+    /// Options changing the loaded game logs, so changing them reloads the seasons.
+    fn options(&self) -> String {
+        let options = [
+            ("native_boxscores", self.native_boxscores),
+            ("re24", self.re24),
+            ("wpa", self.wpa),
+            ("leverage", self.leverage),
+        ];
+        let enabled: Vec<&str> = options.iter().filter(|option| option.1).map(|option| option.0).collect();
+        enabled.join(",")
+    }
+
+    fn load_season_boxscores(&self, season: &str, event_files: bool) -> Result<ChildStdout, SendError> {
+        let season_dir = self.retrosheet_dir.join(season);
+        let mut cwbox = Command::new("cwbox");
+        cwbox.args(["-q", "-y", season, "-X"]).current_dir(&season_dir);
+        if event_files {
+            cwbox.args(find_event_files(&season_dir)?);
+        }
+        else {
+            cwbox.args(find_boxscore_files(&season_dir)?);
+        }
+        let command = cwbox.stdin(Stdio::null()).stdout(Stdio::piped());
+        match command.spawn() {
+            Ok(mut child) => {
+                let stdout = child.stdout.take().expect("cwbox stdout handle not available");
+                Ok(stdout)
+            }
+            Err(err) => {
+                Err(Box::new(err))
+            }
+        }
+    }
+
+    fn prepare_season(&self, input: SeasonGamelogInput) -> Result<SeasonGamelogs, SendError> {
+        let SeasonGamelogInput { season, season_load, game_ids, team_games, guts, park_factors } = input;
+        let event_games = if self.native_boxscores || self.re24 || self.wpa || self.leverage {
+            Some(event::load_season_games(&self.retrosheet_dir.join(&season))?)
+        }
+        else {
+            None
+        };
+        let (batting_gamelogs, fielding_gamelogs, pitching_gamelogs) = self.load_player_game_logs(&season, &game_ids, event_games.as_deref())?;
+
+        // Transform Chadwick gamelogs into internal version for the database and sort to allow
+        // marking which game number in the season this is for a player.
+        let season_numeric = season_load.season;
+        let mut batting_gamelogs = PlayerGamelogLoader::order_batting_gamelogs(PlayerGamelogLoader::order_dated_gamelogs(season_numeric.into(), batting_gamelogs, &team_games), &guts, &park_factors);
+        let fielding_gamelogs = PlayerGamelogLoader::order_fielding_gamelogs(PlayerGamelogLoader::order_dated_gamelogs(season_numeric.into(), fielding_gamelogs, &team_games));
+        let mut pitching_gamelogs = PlayerGamelogLoader::order_pitching_gamelogs(PlayerGamelogLoader::order_dated_gamelogs(season_numeric.into(), pitching_gamelogs, &team_games), &guts, &park_factors);
+
+        let events: Vec<FullEvent> = match &event_games {
+            Some(event_games) if self.re24 || self.wpa || self.leverage => {
+                event_games.iter()
+                    .flat_map(|game| game.events.iter().map(|(full, _)| full.clone()))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        let mut run_expectancy = None;
+        if self.re24 && event_games.is_some() {
+            let re = RunExpectancy::from_events(&events);
+            println!("Season {} run expectancy:\n{}", season, re);
+            let (batting_re24, pitching_re24) = game_re24(&re, &events);
+            for gl in batting_gamelogs.iter_mut() {
+                gl.re24 = batting_re24.get(&(gl.player_id.clone(), gl.game_id.clone())).copied().unwrap_or(f32::NAN);
+            }
+            for gl in pitching_gamelogs.iter_mut() {
+                gl.re24 = pitching_re24.get(&(gl.player_id.clone(), gl.game_id.clone())).copied().unwrap_or(f32::NAN);
+            }
+            run_expectancy = Some(re);
+        }
+        let win_expectancy = if (self.wpa || self.leverage) && event_games.is_some() {
+            Some(WinExpectancy::from_events(&events))
+        }
+        else {
+            None
+        };
+        if self.wpa && let Some(we) = &win_expectancy {
+            let (batting_wpa, pitching_wpa) = game_wpa(we, &events);
+            let mut season_wpa = SeasonTotal::new();
+            for gl in batting_gamelogs.iter_mut() {
+                gl.wpa = batting_wpa.get(&(gl.player_id.clone(), gl.game_id.clone())).copied().unwrap_or(f32::NAN);
+                gl.season_wpa = season_wpa.add(&gl.player_id, gl.wpa);
+            }
+            let mut season_wpa = SeasonTotal::new();
+            for gl in pitching_gamelogs.iter_mut() {
+                gl.wpa = pitching_wpa.get(&(gl.player_id.clone(), gl.game_id.clone())).copied().unwrap_or(f32::NAN);
+                gl.season_wpa = season_wpa.add(&gl.player_id, gl.wpa);
+            }
+        }
+        if self.leverage && let (Some(we), Some(event_games)) = (&win_expectancy, &event_games) {
+            let li = LeverageIndex::from_events(we, &events);
+            let (batting_li, pitching_li) = game_leverage(&li, event_games);
+            for gl in batting_gamelogs.iter_mut() {
+                if let Some(game) = batting_li.get(&(gl.player_id.clone(), gl.game_id.clone())) {
+                    gl.li = game.li();
+                    gl.low_leverage = game.low;
+                    gl.medium_leverage = game.medium;
+                    gl.high_leverage = game.high;
+                }
+            }
+            for gl in pitching_gamelogs.iter_mut() {
+                if let Some(game) = pitching_li.get(&(gl.player_id.clone(), gl.game_id.clone())) {
+                    gl.li = game.li();
+                    gl.low_leverage = game.low;
+                    gl.medium_leverage = game.medium;
+                    gl.high_leverage = game.high;
+                }
+            }
+        }
+
+        Ok(SeasonGamelogs {
+            season,
+            season_load,
+            run_expectancy,
+            batting: batting_gamelogs,
+            fielding: fielding_gamelogs,
+            pitching: pitching_gamelogs,
+        })
+    }
+
+    fn load_player_game_logs(&self, season: &str, game_ids: &HashSet<String>, event_games: Option<&[EventGame]>) -> Result<PlayerGameLogs, SendError> {
+        // Load boxscores from the event files to get more accurate data.
+        println!("Loading player game logs from {} season", season);
+        let (mut batting_gamelogs, mut fielding_gamelogs, mut pitching_gamelogs) = if self.native_boxscores && let Some(event_games) = event_games {
+            event::boxscore::gamelogs_from_games(event_games)
+        }
+        else {
+            let stdout = self.load_season_boxscores(season, true)?;
+            gamelogs_from_boxscores(io::BufReader::new(stdout))
+        };
+
+        // Collect all games found from loading the event files and then check the overall list
+        // against what was found to detect any missing games.
+        let mut found_game_ids = HashSet::with_capacity(game_ids.len());
+        for game_log in &pitching_gamelogs {
+            found_game_ids.insert(game_log.game_id.clone());
+        }
+
+        let mut missing_game_ids = HashSet::new();
+        for game_id in game_ids {
+            if !found_game_ids.contains(game_id) {
+                missing_game_ids.insert(game_id.clone());
+            }
+        }
+
+        // Missing some games from the event files. Load the box score event files to get the
+        // missing games.
+        if !missing_game_ids.is_empty() {
+            println!("Missing {} games from event files. Loading box score files.", missing_game_ids.len());
+            let stdout = self.load_season_boxscores(season, false)?;
+            let (be_batting_logs, be_fielding_logs, be_pitching_logs) = gamelogs_from_boxscores(io::BufReader::new(stdout));
+
+            for game_log in be_batting_logs.into_iter() {
+                if missing_game_ids.contains(&game_log.game_id) {
+                    batting_gamelogs.push(game_log);
+                }
+            }
+
+            for game_log in be_fielding_logs.into_iter() {
+                if missing_game_ids.contains(&game_log.game_id) {
+                    fielding_gamelogs.push(game_log);
+                }
+            }
+
+            for game_log in be_pitching_logs.into_iter() {
+                if missing_game_ids.contains(&game_log.game_id) {
+                    pitching_gamelogs.push(game_log);
+                }
+            }
+        }
+
+        Ok((batting_gamelogs, fielding_gamelogs, pitching_gamelogs))
+    }
+}
+
+
+impl SeasonLoad {
+    fn new(source: &'static str, season: u16, files: &[path::PathBuf], options: String) -> Result<Self, SendError> {
+        Ok(Self {
+            source,
+            season,
+            hash: hash_files(files)?,
+            options,
+        })
+    }
+
+    /// Forget every season loaded into the source's tables, for when they're recreated.
+    fn clear(conn: &Connection, source: &str) -> Result<(), Box<dyn Error>> {
+        conn.execute("DELETE FROM season_loads WHERE source = :source", named_params! {":source": source})?;
+        Ok(())
+    }
+
+    /// Forget one season of the source, for when the tables it reads from change.
+    fn forget(conn: &Connection, source: &str, season: u16) -> Result<(), Box<dyn Error>> {
+        conn.execute(
+            "DELETE FROM season_loads WHERE season = :season AND source = :source",
+            named_params! {":season": season, ":source": source}
+        )?;
+        Ok(())
+    }
+
+    /// Whether the season was already loaded from the same files with the same options.
+    fn is_loaded(&self, conn: &Connection) -> Result<bool, Box<dyn Error>> {
+        let loaded: Option<(String, String)> = conn.query_row(
+            "SELECT source_hash, options FROM season_loads WHERE season = :season AND source = :source",
+            named_params! {":season": self.season, ":source": self.source},
+            |row| Ok((row.get(0)?, row.get(1)?))
+        ).optional()?;
+        Ok(loaded.is_some_and(|(hash, options)| hash == self.hash && options == self.options))
+    }
+
+    /// Record the load in the transaction inserting the season, so an interrupted load leaves the
+    /// season to be loaded again.
+    fn record(&self, tx: &Transaction) -> Result<(), Box<dyn Error>> {
+        tx.execute(
+            "INSERT INTO season_loads (season, source, source_hash, options, loaded_at)
+             VALUES (:season, :source, :hash, :options, datetime('now'))
+             ON CONFLICT (season, source) DO UPDATE SET
+                source_hash=:hash, options=:options, loaded_at=datetime('now')",
+            named_params! {
+                ":season": self.season,
+                ":source": self.source,
+                ":hash": &self.hash,
+                ":options": &self.options,
+            }
+        )?;
+        Ok(())
+    }
+}
+
+
+impl GameLeverage {
+    fn add(&mut self, plate_appearance: &PlateAppearanceLeverage) {
+        self.li_total += plate_appearance.li;
+        self.pa += 1;
+        let split = match plate_appearance.level {
+            LeverageLevel::Low => &mut self.low,
+            LeverageLevel::Medium => &mut self.medium,
+            LeverageLevel::High => &mut self.high,
+        };
+        split.add_event(plate_appearance.event);
+    }
+
+    fn li(&self) -> f32 {
+        self.li_total / self.pa as f32
+    }
+}
+
+
+impl SeasonTotal {
+    fn new() -> Self {
+        Self {
+            player_id: String::new(),
+            total: 0.0,
+        }
+    }
+
+    /// Add a game's value and return the total through it. Games without a value add nothing.
+    fn add(&mut self, player_id: &str, value: f32) -> f32 {
+        if self.player_id != player_id {
+            self.player_id = player_id.to_string();
+            self.total = 0.0;
+        }
+        if !value.is_nan() {
+            self.total += value;
+        }
+        self.total
+    }
+}
+
+
+impl ParkAverage {
+    fn new() -> Self {
+        Self {
+            total: 0.0,
+            games: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.total = 0.0;
+        self.games = 0;
+    }
+
+    /// Games in parks without a known factor are left out.
+    fn add(&mut self, factor: f32) {
+        if !factor.is_nan() {
+            self.total += factor;
+            self.games += 1;
+        }
+    }
+
+    /// Neutral until a game in a park with a known factor.
+    fn factor(&self) -> f32 {
+        if self.games > 0 {
+            self.total / self.games as f32
+        }
+        else {
+            1.0
+        }
+    }
+}
+
+
 impl BattingSlashLine {
     fn new() -> Self {
         Self {
             ab: 0,
             h: 0,
+            d: 0,
+            t: 0,
+            hr: 0,
             tb: 0,
             bb: 0,
+            ibb: 0,
             hbp: 0,
             sf: 0,
         }
@@ -644,8 +1306,12 @@ impl BattingSlashLine {
         self.h = 0;
         self.ab = 0;
         self.h = 0;
+        self.d = 0;
+        self.t = 0;
+        self.hr = 0;
         self.tb = 0;
         self.bb = 0;
+        self.ibb = 0;
         self.hbp = 0;
         self.sf = 0;
     }
@@ -661,9 +1327,17 @@ impl BattingSlashLine {
         // The hits field includes extra-base hits so the game total for each stat includes the
         // number of bases beyond a single.
         self.tb += h + d + t * 2 + hr * 3;
-        self.bb += gamelog.bb;
-        self.hbp += gamelog.hbp;
-        self.sf += gamelog.sf;
+        self.d += d;
+        self.t += t;
+        self.hr += hr;
+        self.bb += u16::from(gamelog.bb);
+        self.ibb += u16::from(gamelog.ibb);
+        self.hbp += u16::from(gamelog.hbp);
+        self.sf += u16::from(gamelog.sf);
+    }
+
+    fn woba(&self, weights: &WobaWeights) -> f32 {
+        weights.woba(self.ab.into(), self.h.into(), self.d.into(), self.t.into(), self.hr.into(), self.bb.into(), self.ibb.into(), self.hbp.into(), self.sf.into())
     }
 
     fn slash_line(&self) -> (f32, f32, f32) {
@@ -728,43 +1402,6 @@ impl PitcherStats {
         self.so = 0;
     }
 
-    fn add_team_gamelog(&mut self, gamelog: &games::GameLog) {
-        macro_rules! unwrap_retro_option {
-            ($field:expr) => {
-                match $field {
-                    games::RetrosheetOption::Some(v) => v,
-                    _ => return,
-                }
-            }
-        }
-
-        let ipouts: u32 = match gamelog.number_of_outs {
-            Some(outs) => outs.into(),
-            None => return,
-        };
-        self.ipouts += ipouts;
-        let v_i_er = unwrap_retro_option!(gamelog.visitor_individual_earned_runs);
-        let h_i_er = unwrap_retro_option!(gamelog.home_individual_earned_runs);
-        let er: u16 = (v_i_er + h_i_er).into();
-        self.er += er;
-        let v_hr = unwrap_retro_option!(gamelog.visitor_homeruns);
-        let h_hr = unwrap_retro_option!(gamelog.home_homeruns);
-        let hr: u16 = (v_hr + h_hr).into();
-        self.hr += hr;
-        let v_bb = unwrap_retro_option!(gamelog.visitor_walks);
-        let h_bb = unwrap_retro_option!(gamelog.home_walks);
-        let bb: u16 = (v_bb + h_bb).into();
-        self.bb += bb;
-        let v_hbp = unwrap_retro_option!(gamelog.visitor_hbp);
-        let h_hbp = unwrap_retro_option!(gamelog.home_hbp);
-        let hbp: u16 = (v_hbp + h_hbp).into();
-        self.hbp += hbp;
-        let v_so = unwrap_retro_option!(gamelog.visitor_strikeouts);
-        let h_so = unwrap_retro_option!(gamelog.home_strikeouts);
-        let so: u16 = (v_so + h_so).into();
-        self.so += so;
-    }
-
     fn add_gamelog(&mut self, gamelog: &player::PitchingGamelog) {
         let ipouts: u32 = gamelog.ipouts.into();
         self.ipouts += ipouts;
@@ -838,7 +1475,166 @@ impl cmp::Ord for CareerGame {
 }
 
 
-fn find_event_files(season_dir: &path::Path) -> Result<Vec<String>, Box<dyn Error>> {
+#[allow(clippy::type_complexity)]
+fn load_team_gamelogs(conn: &Connection, season: &str) -> Result<(HashSet<String>, HashMap<TeamGameLogKey, TeamGameLogValue>), Box<dyn Error>> {
+    let mut statement = conn.prepare(
+        "SELECT game_id, date, visitor_team, visitor_team_game_number, home_team, home_team_game_number
+        FROM games
+        WHERE strftime('%Y', games.date) = :season"
+    )?;
+    let mut game_ids = HashSet::new();
+    let mut games = HashMap::new();
+    let mut rows = statement.query(&[(":season", season)])?;
+    while let Some(row) = rows.next()? {
+        let game_id: String = row.get(0)?;
+        game_ids.insert(game_id.clone());
+        let date: chrono::NaiveDate = row.get(1)?;
+        let home_team = TeamGameLogKey {
+            game_id: game_id.clone(),
+            team_id: row.get(4)?,
+        };
+        let home_team_value = TeamGameLogValue {
+            date,
+            team_game_number: row.get(5)?,
+        };
+        games.insert(home_team, home_team_value);
+
+        let visitor_team = TeamGameLogKey {
+            game_id: game_id.clone(),
+            team_id: row.get(2)?,
+        };
+        let visitor_team_value = TeamGameLogValue {
+            date,
+            team_game_number: row.get(3)?,
+        };
+        games.insert(visitor_team, visitor_team_value);
+    }
+    Ok((game_ids, games))
+}
+
+
+/// Hash the source files of each season on the pool and keep the seasons needing to be loaded,
+/// unless forced to reload them all.
+fn seasons_to_load<F>(conn: &Connection, pool: &ThreadPool, seasons: &[String], force: bool, label: &str, season_load: F) -> Result<Vec<(String, SeasonLoad)>, Box<dyn Error>>
+    where F: Fn(&str) -> Result<SeasonLoad, SendError> + Sync
+{
+    let season_loads = pool.install(|| {
+        seasons.par_iter()
+            .map(|season| season_load(season))
+            .collect::<Result<Vec<_>, _>>()
+    }).map_err(|err| err as Box<dyn Error>)?;
+
+    let mut pending = Vec::with_capacity(seasons.len());
+    for (season, season_load) in seasons.iter().zip(season_loads) {
+        if !force && season_load.is_loaded(conn)? {
+            println!("{} from {} season are unchanged, skipping", label, season);
+        }
+        else {
+            pending.push((season.clone(), season_load));
+        }
+    }
+    Ok(pending)
+}
+
+
+/// Prepare the seasons on the pool and write each one on this thread as soon as it's ready, so
+/// only one thread writes to the database. At most one prepared season per thread waits to be
+/// written, which bounds the memory used.
+fn load_in_parallel<I, T, P, W>(pool: &ThreadPool, label: &str, inputs: Vec<I>, prepare: P, mut write: W) -> Result<(), Box<dyn Error>>
+    where I: Send,
+          T: Send,
+          P: Fn(I) -> Result<T, SendError> + Sync,
+          W: FnMut(T) -> Result<(), Box<dyn Error>>
+{
+    let total = inputs.len();
+    if total == 0 {
+        return Ok(());
+    }
+    println!("Loading {} from {} seasons with {} threads", label, total, pool.current_num_threads());
+    let start = Instant::now();
+    let (sender, receiver) = mpsc::sync_channel(pool.current_num_threads());
+    let prepare = &prepare;
+    thread::scope(|scope| {
+        let preparing = scope.spawn(move || {
+            pool.install(|| {
+                inputs.into_par_iter().try_for_each_with(sender, |sender, input| {
+                    let prepared = prepare(input)?;
+                    // Sending only fails once the writer has stopped on an error of its own.
+                    sender.send(prepared).map_err(|_| SendError::from("season writer stopped"))
+                })
+            })
+        });
+
+        for (written, prepared) in receiver.into_iter().enumerate() {
+            write(prepared)?;
+            println!("Loaded {} for {} of {} seasons ({:.1}s)", label, written + 1, total, start.elapsed().as_secs_f32());
+        }
+
+        preparing.join()
+            .unwrap_or_else(|err| panic::resume_unwind(err))
+            .map_err(|err| err as Box<dyn Error>)
+    })
+}
+
+
+/// Chadwick's Retrosheet seasons either have a GLYYYY.TXT or glYYYY.txt file.
+fn find_gamelog_file(season_dir: &path::Path, season: &str) -> Option<path::PathBuf> {
+    [format!("GL{}.TXT", season), format!("gl{}.txt", season)].into_iter()
+        .map(|gl_file| season_dir.join(gl_file))
+        .find(|gl_path| gl_path.exists())
+}
+
+
+/// Files in the season directory with one of the extensions, sorted by name.
+fn season_files(season_dir: &path::Path, extensions: &[&str]) -> Result<Vec<path::PathBuf>, SendError> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(season_dir)? {
+        let path = entry?.path();
+        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+        if extensions.iter().any(|ext| ext.eq_ignore_ascii_case(extension)) {
+            files.push(path);
+        }
+    }
+    files.sort_unstable();
+
+    Ok(files)
+}
+
+
+/// The event files read for a season, with the rosters read alongside them.
+fn event_source_files(season_dir: &path::Path) -> Result<Vec<path::PathBuf>, SendError> {
+    let mut files = event::find_event_files(season_dir)?;
+    files.extend(season_files(season_dir, ROSTER_EXTENSIONS)?);
+    Ok(files)
+}
+
+
+/// The files read for a season's player game logs. The games table provides the team game numbers,
+/// so its source is included.
+fn gamelog_source_files(season_dir: &path::Path, season: &str) -> Result<Vec<path::PathBuf>, SendError> {
+    let mut files = event_source_files(season_dir)?;
+    files.extend(season_files(season_dir, BOXSCORE_EXTENSIONS)?);
+    files.extend(find_gamelog_file(season_dir, season));
+    Ok(files)
+}
+
+
+/// Hash the names and contents of the files, in order.
+fn hash_files(files: &[path::PathBuf]) -> Result<String, SendError> {
+    let mut hasher = Sha256::new();
+    for file in files {
+        let contents = fs::read(file)?;
+        if let Some(name) = file.file_name() {
+            hasher.update(name.as_encoded_bytes());
+        }
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+
+fn find_event_files(season_dir: &path::Path) -> Result<Vec<String>, SendError> {
     let mut files = Vec::new();
     for entry in fs::read_dir(season_dir)? {
         let entry = entry?;
@@ -858,7 +1654,7 @@ fn find_event_files(season_dir: &path::Path) -> Result<Vec<String>, Box<dyn Erro
 }
 
 
-fn find_boxscore_files(season_dir: &path::Path) -> Result<Vec<String>, Box<dyn Error>> {
+fn find_boxscore_files(season_dir: &path::Path) -> Result<Vec<String>, SendError> {
     let mut files = Vec::new();
     for entry in fs::read_dir(season_dir)? {
         let entry = entry?;
@@ -880,40 +1676,95 @@ fn find_boxscore_files(season_dir: &path::Path) -> Result<Vec<String>, Box<dyn E
 }
 
 
-fn create_internal_tables(conn: &mut Connection) {
-    if let Ok(table_exists) = conn.table_exists(Some("main"), "guts") && !table_exists {
-        let res = conn.execute(include_str!("../sql/create_guts.sql"), ());
-        if let Err(err) = res {
-            eprintln!("Creation of guts table failed: {}", err);
-        }
+/// Sum each plate appearance's RE24 by game for batters and pitchers.
+fn game_re24(re: &RunExpectancy, events: &[FullEvent]) -> (PlayerGameTotals, PlayerGameTotals) {
+    let mut batting = HashMap::new();
+    let mut pitching = HashMap::new();
+    for value in re.plate_appearance_values(events) {
+        *batting.entry((value.bat_id, value.game_id.clone())).or_insert(0.0) += value.re24;
+        *pitching.entry((value.pit_id, value.game_id)).or_insert(0.0) -= value.re24;
     }
+    (batting, pitching)
 }
 
 
-fn get_fip_constant(conn: &mut Connection, season: u16) -> Result<Option<f32>, Box<dyn Error>> {
-    let fip = conn.query_one(
-         "SELECT fip_constant FROM guts WHERE season = :season",
-        &[(":season", &season)],
-        |row| row.get(0)
-    )?;
+/// Sum each event's WPA by game for batters and pitchers.
+fn game_wpa(we: &WinExpectancy, events: &[FullEvent]) -> (PlayerGameTotals, PlayerGameTotals) {
+    let mut batting = HashMap::new();
+    let mut pitching = HashMap::new();
+    for value in we.event_values(events) {
+        *batting.entry((value.bat_id, value.game_id.clone())).or_insert(0.0) += value.wpa;
+        *pitching.entry((value.pit_id, value.game_id)).or_insert(0.0) -= value.wpa;
+    }
+    (batting, pitching)
+}
 
-    Ok(fip)
+
+/// Split each game's plate appearances by leverage for batters and pitchers.
+fn game_leverage(li: &LeverageIndex, event_games: &[EventGame]) -> (PlayerGameLeverage, PlayerGameLeverage) {
+    let mut batting: HashMap<_, GameLeverage> = HashMap::new();
+    let mut pitching: HashMap<_, GameLeverage> = HashMap::new();
+    for game in event_games {
+        for plate_appearance in li.plate_appearances(&game.events) {
+            let event = plate_appearance.event;
+            batting.entry((event.resp_bat_id.clone(), event.game_id.clone())).or_default().add(&plate_appearance);
+            pitching.entry((event.resp_pit_id.clone(), event.game_id.clone())).or_default().add(&plate_appearance);
+        }
+    }
+    (batting, pitching)
 }
 
 
-fn update_fip_constant(tx: &Transaction, guts: &Guts) -> Result<(), Box<dyn Error>> {
+fn update_run_expectancy(tx: &Transaction, season: u16, re: &RunExpectancy) -> Result<(), Box<dyn Error>> {
     let insert_sql = String::from(
-        "INSERT INTO guts (season, fip_constant) VALUES (:season, :fip_constant)
-         ON CONFLICT (season) DO UPDATE SET fip_constant=:fip_constant"
+        "INSERT INTO run_expectancy (season, bases_cd, outs, runs, times, scoreless, expected_runs)
+         VALUES (:season, :bases_cd, :outs, :runs, :times, :scoreless, :expected_runs)
+         ON CONFLICT (season, bases_cd, outs) DO UPDATE SET
+            runs=:runs, times=:times, scoreless=:scoreless, expected_runs=:expected_runs"
     );
 
     let mut insert = tx.prepare(&insert_sql)?;
-    insert.execute(
-        named_params! {
-            ":season": &guts.season,
-            ":fip_constant": &guts.fip_constant,
+    for bases_cd in 0..8 {
+        for outs in 0..3 {
+            let Some(totals) = re.totals(bases_cd, outs)
+            else {
+                continue;
+            };
+            insert.execute(
+                named_params! {
+                    ":season": &season,
+                    ":bases_cd": &bases_cd,
+                    ":outs": &outs,
+                    ":runs": &totals.runs,
+                    ":times": &totals.times,
+                    ":scoreless": &totals.scoreless,
+                    ":expected_runs": &totals.expected_runs(),
+                }
+            )?;
         }
-    )?;
+    }
+
+    Ok(())
+}
+
+
+fn get_guts(conn: &mut Connection, season: u16) -> Result<Option<Guts>, Box<dyn Error>> {
+    let guts = conn.query_one(
+        "SELECT * FROM guts WHERE season = :season",
+        &[(":season", &season)],
+        |row| Guts::read_row(row, 0)
+    ).optional()?;
+
+    Ok(guts)
+}
+
+
+fn update_guts(tx: &Transaction, guts: &Guts) -> Result<(), Box<dyn Error>> {
+    let placeholders: Vec<String> = Guts::column_names().iter().map(|name| format!(":{}", name)).collect();
+    let insert_sql = format!("INSERT OR REPLACE INTO guts VALUES ({})", placeholders.join(", "));
+
+    let mut insert = tx.prepare(&insert_sql)?;
+    guts.write_row(&mut insert)?;
 
     Ok(())
 }
@@ -1046,28 +1897,43 @@ fn run() -> Result<(), Box<dyn Error>> {
     let mut connection = Connection::open(database)?;
     connection.pragma_update(None, "temp_store", "memory")?;
 
+    let version = database::migrate(&mut connection)?;
+    if version < database::SCHEMA_VERSION {
+        println!("Upgraded database schema from version {} to {}", version, database::SCHEMA_VERSION);
+    }
+
     let seasons = args.seasons;
+    // Zero threads lets rayon use the number of CPUs.
+    let pool = ThreadPoolBuilder::new().num_threads(args.jobs.unwrap_or(0)).build()?;
 
     if let Some(register_path) = args.register_dir {
         load_people_files(&mut connection, &register_path, args.init);
     }
 
-    create_internal_tables(&mut connection);
-
     if args.games {
         if let Some(ref retrosheet_dir) = args.retrosheet_dir {
             let mut game_loader = GameLoader::new(&mut connection, retrosheet_dir.to_owned());
-            game_loader.load(&seasons, args.init)?;
+            game_loader.load(&seasons, &pool, args.init, args.force)?;
         }
         else {
             eprintln!("Cannot load games without retrosheet directory.");
         }
     }
 
+    if args.events {
+        if let Some(ref retrosheet_dir) = args.retrosheet_dir {
+            let mut event_loader = EventLoader::new(&mut connection, retrosheet_dir.to_owned());
+            event_loader.load(&seasons, &pool, args.init, args.force)?;
+        }
+        else {
+            eprintln!("Cannot load events without retrosheet directory.");
+        }
+    }
+
     if args.gamelogs {
         if let Some(ref retrosheet_dir) = args.retrosheet_dir {
-            let mut gamelogs = PlayerGamelogLoader::new(&mut connection, retrosheet_dir.to_owned());
-            gamelogs.load(&seasons, args.init)?;
+            let mut gamelogs = PlayerGamelogLoader::new(&mut connection, retrosheet_dir.to_owned(), args.native_boxscores, args.re24, args.wpa, args.leverage);
+            gamelogs.load(&seasons, &pool, args.init, args.force)?;
             if args.count_career_games {
                 gamelogs.order_career_games(&seasons)?;
             }
@@ -1090,3 +1956,199 @@ fn run() -> Result<(), Box<dyn Error>> {
 fn main() -> Result<(), Box<dyn Error>> {
     run()
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    #[test]
+    fn parallel_prepare_error() {
+        let pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let finished = Mutex::new(Vec::new());
+        let mut written = Vec::new();
+        let result = load_in_parallel(&pool, "test", (0..8).collect(), |season: u32| {
+            if season == 5 {
+                return Err(SendError::from("season 5 failed"));
+            }
+            finished.lock().unwrap().push(season);
+            Ok(season)
+        }, |season| {
+            written.push(season);
+            Ok(())
+        });
+        assert_eq!(result.unwrap_err().to_string(), "season 5 failed");
+
+        // Every season prepared before the error stopped the pool is still written.
+        let mut finished = finished.into_inner().unwrap();
+        finished.sort_unstable();
+        written.sort_unstable();
+        assert_eq!(written, finished);
+        assert!(!written.contains(&5));
+    }
+
+    #[test]
+    fn unchanged_seasons_skipped() {
+        let retrosheet_dir = std::env::temp_dir().join(format!("build-db-seasons-{}", std::process::id()));
+        let season_dir = retrosheet_dir.join("2020");
+        fs::create_dir_all(&season_dir).unwrap();
+        for file in fs::read_dir("testdata/events/2020").unwrap() {
+            let file = file.unwrap();
+            fs::copy(file.path(), season_dir.join(file.file_name())).unwrap();
+        }
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        database::migrate(&mut conn).unwrap();
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let seasons = ["2020".to_string()];
+        let pending = |conn: &Connection, force: bool| {
+            seasons_to_load(conn, &pool, &seasons, force, "Player game logs", |season| {
+                let source_files = gamelog_source_files(&retrosheet_dir.join(season), season)?;
+                SeasonLoad::new("gamelogs", season.parse::<u16>()?, &source_files, String::new())
+            }).unwrap()
+        };
+
+        let loads = pending(&conn, false);
+        assert_eq!(loads.len(), 1);
+        let tx = conn.transaction().unwrap();
+        loads[0].1.record(&tx).unwrap();
+        tx.commit().unwrap();
+        assert!(pending(&conn, false).is_empty());
+        assert_eq!(pending(&conn, true).len(), 1);
+
+        // Edited event files are read with the rest, so adding one reloads the season.
+        fs::write(season_dir.join("2020TST.EDN"), "").unwrap();
+        let loads = pending(&conn, false);
+        fs::remove_dir_all(&retrosheet_dir).unwrap();
+        assert_eq!(loads.len(), 1);
+    }
+
+    #[test]
+    fn games_without_events() {
+        let retrosheet_dir = std::env::temp_dir().join(format!("build-db-events-{}", std::process::id()));
+        let season_dir = retrosheet_dir.join("2020");
+        fs::create_dir_all(&season_dir).unwrap();
+        fs::write(season_dir.join("2020TST.EVN"), "id,TST202004010\nplay,1,0,a01\n").unwrap();
+        let season_load = SeasonLoad::new("games", 2020, &[], String::new()).unwrap();
+        let unparseable = GameLoader::prepare_season(&retrosheet_dir, "2020".to_string(), season_load);
+        let season_load = SeasonLoad::new("games", 2019, &[], String::new()).unwrap();
+        let missing = GameLoader::prepare_season(&retrosheet_dir, "2019".to_string(), season_load);
+        fs::remove_dir_all(&retrosheet_dir).unwrap();
+
+        for season in [unparseable.unwrap(), missing.unwrap()] {
+            assert!(season.guts.woba.scale.is_nan());
+        }
+    }
+
+    #[test]
+    fn park_factor_changes_reload_gamelogs() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        database::migrate(&mut conn).unwrap();
+        let mut tx = conn.transaction().unwrap();
+        games::GameLog::create_table(&mut tx).unwrap();
+        for season in [2019, 2020] {
+            tx.execute("INSERT INTO season_loads VALUES (?1, 'gamelogs', 'hash', '', 'now')", [season]).unwrap();
+        }
+        tx.commit().unwrap();
+        let insert_game = |conn: &Connection, game_id: &str, date: &str, runs: u32| {
+            conn.execute(
+                "INSERT INTO games (game_id, date, visitor_team, visitor_league, home_team, home_league, park_id, visitor_score, home_score)
+                VALUES (?1, ?2, 'AWY', 'AL', 'TST', 'AL', 'TST01', ?3, 0)",
+                rusqlite::params![game_id, date, runs],
+            ).unwrap();
+        };
+        let loaded = |conn: &Connection| -> Vec<u16> {
+            conn.prepare("SELECT season FROM season_loads WHERE source = 'gamelogs' ORDER BY season").unwrap()
+                .query_map((), |row| row.get(0)).unwrap()
+                .map(Result::unwrap)
+                .collect()
+        };
+
+        insert_game(&conn, "TST201904010", "2019-04-01", 4);
+        let mut loader = GameLoader::new(&mut conn, path::PathBuf::new());
+        loader.update_park_factors().unwrap();
+        assert_eq!(loaded(&conn), [2020]);
+
+        // Nothing changes when the games are the same.
+        conn.execute("INSERT INTO season_loads VALUES (2019, 'gamelogs', 'hash', '', 'now')", ()).unwrap();
+        let mut loader = GameLoader::new(&mut conn, path::PathBuf::new());
+        loader.update_park_factors().unwrap();
+        assert_eq!(loaded(&conn), [2019, 2020]);
+
+        // A 2020 game adds a park factor for 2020 without changing 2019's.
+        insert_game(&conn, "TST202004010", "2020-04-01", 8);
+        let mut loader = GameLoader::new(&mut conn, path::PathBuf::new());
+        loader.update_park_factors().unwrap();
+        assert_eq!(loaded(&conn), [2019]);
+    }
+
+    #[test]
+    fn career_saves() {
+        // The same pitcher saves a game in the native box scores and one from cwbox.
+        let season_dir = std::env::temp_dir().join(format!("build-db-saves-{}", std::process::id()));
+        fs::create_dir_all(&season_dir).unwrap();
+        for file in fs::read_dir("testdata/events/2020").unwrap() {
+            let file = file.unwrap();
+            fs::copy(file.path(), season_dir.join(file.file_name())).unwrap();
+        }
+        let event_file = season_dir.join("2020TST.EVN");
+        let events = fs::read_to_string(&event_file).unwrap();
+        fs::write(&event_file, events.replace("info,date,2020/04/01\n", "info,date,2020/04/01\ninfo,save,h09\n")).unwrap();
+        let event_games = event::load_season_games(&season_dir);
+        fs::remove_dir_all(&season_dir).unwrap();
+        let (_, _, native) = event::boxscore::gamelogs_from_games(&event_games.unwrap());
+        let cwbox_xml = r#"<boxscore game_id="TST202004020"><pitching team="TST"><pitcher id="h09" outs="3" dec="S"/></pitching></boxscore>"#;
+        let (_, _, cwbox) = gamelogs_from_boxscores(cwbox_xml.as_bytes());
+        let pitching: Vec<player::PitchingGamelog> = native.into_iter().chain(cwbox).map(player::PitchingGamelog::from).collect();
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        database::migrate(&mut conn).unwrap();
+        let mut tx = conn.transaction().unwrap();
+        tx.execute("CREATE TABLE games (game_id TEXT NOT NULL, date TEXT NOT NULL)", ()).unwrap();
+        tx.execute("INSERT INTO games VALUES ('TST202004010', '2020-04-01'), ('TST202004020', '2020-04-02')", ()).unwrap();
+        player::BattingGamelog::create_table(&mut tx).unwrap();
+        player::FieldingGamelog::create_table(&mut tx).unwrap();
+        player::PitchingGamelog::create_table(&mut tx).unwrap();
+        PlayerGamelogLoader::insert_player_game_logs(&tx, &pitching).unwrap();
+        tx.commit().unwrap();
+
+        let mut loader = PlayerGamelogLoader::new(&mut conn, path::PathBuf::new(), true, false, false, false);
+        loader.order_career_games(&["2020".to_string()]).unwrap();
+        let mut statement = conn.prepare("SELECT career_game, career_sv, career_ipouts FROM pitching_gamelogs WHERE player_id = 'h09' ORDER BY game_id").unwrap();
+        let careers: Vec<(u32, u32, u32)> = statement.query_map((), |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))).unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(careers, [(1, 1, 3), (2, 2, 6)]);
+    }
+
+    #[test]
+    fn career_games_resume() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        database::migrate(&mut conn).unwrap();
+        let mut tx = conn.transaction().unwrap();
+        // Ordering only reads the dates of the games.
+        tx.execute("CREATE TABLE games (game_id TEXT NOT NULL, date TEXT NOT NULL)", ()).unwrap();
+        player::BattingGamelog::create_table(&mut tx).unwrap();
+        player::FieldingGamelog::create_table(&mut tx).unwrap();
+        player::PitchingGamelog::create_table(&mut tx).unwrap();
+        for (game_id, date) in [("TST201904010", "2019-04-01"), ("TST201904020", "2019-04-02"), ("TST202004010", "2020-04-01")] {
+            tx.execute("INSERT INTO games (game_id, date) VALUES (?1, ?2)", [game_id, date]).unwrap();
+            tx.execute("INSERT INTO batting_gamelogs (player_id, game_id, team_id, hr) VALUES ('bat01', ?1, 'TST', 1)", [game_id]).unwrap();
+        }
+        // A run interrupted after loading 2019 left its seasons to be ordered.
+        tx.execute("INSERT INTO career_game_updates VALUES ('batting_gamelogs', 2019)", ()).unwrap();
+        tx.commit().unwrap();
+
+        let mut loader = PlayerGamelogLoader::new(&mut conn, path::PathBuf::new(), false, false, false, false);
+        loader.order_career_games(&[]).unwrap();
+        let mut statement = conn.prepare("SELECT career_game, career_hr FROM batting_gamelogs ORDER BY game_id").unwrap();
+        let careers: Vec<(Option<u32>, Option<u32>)> = statement.query_map((), |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(careers, [(Some(1), Some(1)), (Some(2), Some(2)), (Some(3), Some(3))]);
+        let remaining: u32 = conn.query_row("SELECT count(*) FROM career_game_updates", (), |row| row.get(0)).unwrap();
+        assert_eq!(remaining, 0);
+    }
+}
@@ -0,0 +1,213 @@
+use std::error::Error;
+use std::path;
+use std::process::{Command, Stdio};
+
+use baseball::chadwick::events::read_full_extended_events;
+use baseball::retrosheet::event::{self, CombinedEvent, Roster};
+
+use clap::Parser;
+
+
+/// Compare the native event file reader against cwevent, field by field.
+#[derive(Parser)]
+struct CheckArgs {
+    #[arg(short = 'r', long)]
+    retrosheet_dir: path::PathBuf,
+
+    /// Number of mismatches to print for each field.
+    #[arg(short = 'n', long, default_value_t = 5)]
+    examples: usize,
+
+    seasons: Vec<String>,
+}
+
+
+#[derive(Default)]
+struct FieldMismatches {
+    field: &'static str,
+    count: usize,
+    examples: Vec<String>,
+}
+
+
+struct Comparison {
+    examples: usize,
+    mismatches: Vec<FieldMismatches>,
+    events: usize,
+}
+
+
+impl Comparison {
+    fn new(examples: usize) -> Self {
+        Self {
+            examples,
+            mismatches: Vec::new(),
+            events: 0,
+        }
+    }
+
+    fn record(&mut self, event_id: &str, field: &'static str, native: String, cwevent: String) {
+        let idx = match self.mismatches.iter().position(|m| m.field == field) {
+            Some(idx) => idx,
+            None => {
+                self.mismatches.push(FieldMismatches { field, ..Default::default() });
+                self.mismatches.len() - 1
+            }
+        };
+        let mismatch = &mut self.mismatches[idx];
+        mismatch.count += 1;
+        if mismatch.examples.len() < self.examples {
+            mismatch.examples.push(format!("{}: native {} cwevent {}", event_id, native, cwevent));
+        }
+    }
+}
+
+
+macro_rules! compare_fields {
+    ($comparison:expr, $event_id:expr, $native:expr, $cwevent:expr, [$($field:ident),* $(,)?]) => {
+        $(
+            if $native.$field != $cwevent.$field {
+                $comparison.record(
+                    $event_id,
+                    stringify!($field),
+                    format!("{:?}", $native.$field),
+                    format!("{:?}", $cwevent.$field),
+                );
+            }
+        )*
+    };
+}
+
+
+fn compare_event(comparison: &mut Comparison, native: &CombinedEvent, cwevent: &CombinedEvent) {
+    let event_id = format!("{} #{}", cwevent.0.game_id, cwevent.0.event_id);
+    let (native_full, native_ext) = native;
+    let (cw_full, cw_ext) = cwevent;
+    comparison.events += 1;
+    compare_fields!(comparison, &event_id, native_full, cw_full, [
+        game_id, away_team_id, inn_ct, bat_home_id, outs_ct, balls_ct, strikes_ct, pitch_seq_tx,
+        away_score_ct, home_score_ct, bat_id, bat_hand_cd, resp_bat_id, resp_bat_hand_cd, pit_id,
+        pit_hand_cd, resp_pit_id, resp_pit_hand_cd, pos2_fld_id, pos3_fld_id, pos4_fld_id,
+        pos5_fld_id, pos6_fld_id, pos7_fld_id, pos8_fld_id, pos9_fld_id, base1_run_id,
+        base2_run_id, base3_run_id, event_tx, leadoff_fl, ph_fl, bat_fld_cd, bat_lineup_id,
+        event_cd, bat_event_fl, ab_fl, h_cd, sh_fl, sf_fl, event_outs_ct, dp_fl, tp_fl, rbi_ct,
+        wp_fl, pb_fl, fld_cd, battedball_cd, bunt_fl, foul_fl, battedball_loc_tx, err_ct,
+        err1_fld_cd, err1_cd, err2_fld_cd, err2_cd, err3_fld_cd, err3_cd, bat_dest_id,
+        run1_dest_id, run2_dest_id, run3_dest_id, bat_play_tx, run1_play_tx, run2_play_tx,
+        run3_play_tx, run1_sb_fl, run2_sb_fl, run3_sb_fl, run1_cs_fl, run2_cs_fl, run3_cs_fl,
+        run1_pk_fl, run2_pk_fl, run3_pk_fl, run1_resp_pit_id, run2_resp_pit_id,
+        run3_resp_pit_id, game_new_fl, game_end_fl, pr_run1_fl, pr_run2_fl, pr_run3_fl,
+        removed_for_pr_run1_id, removed_for_pr_run2_id, removed_for_pr_run3_id,
+        removed_for_ph_bat_id, removed_for_ph_bat_fld_cd, po1_fld_cd, po2_fld_cd, po3_fld_cd,
+        ass1_fld_cd, ass2_fld_cd, ass3_fld_cd, ass4_fld_cd, ass5_fld_cd, event_id,
+    ]);
+    compare_fields!(comparison, &event_id, native_ext, cw_ext, [
+        home_team_id, bat_team_id, fld_team_id, bat_last_id, inn_new_fl, inn_end_fl,
+        start_bat_score_ct, start_fld_score_ct, inn_runs_ct, game_pa_ct, inn_pa_ct, pa_new_fl,
+        pa_trunc_fl, start_bases_cd, end_bases_cd, bat_start_fl, resp_bat_start_fl,
+        bat_on_deck_id, bat_in_hold_id, pit_start_fl, resp_pit_start_fl, run1_fld_cd,
+        run1_lineup_cd, run1_origin_event_id, run2_fld_cd, run2_lineup_cd, run2_origin_event_id,
+        run3_fld_cd, run3_lineup_cd, run3_origin_event_id, run1_resp_cat_id, run2_resp_cat_id,
+        run3_resp_cat_id, pa_ball_ct, pa_called_ball_ct, pa_intent_ball_ct,
+        pa_pitchout_ball_ct, pa_hitbatter_ball_ct, pa_other_ball_ct, pa_strike_ct,
+        pa_called_strike_ct, pa_swingmiss_strike_ct, pa_foul_strike_ct, pa_inplay_strike_ct,
+        pa_other_strike_ct, event_runs_ct, fld_id, base2_force_fl, base3_force_fl,
+        base4_force_fl, bat_safe_err_fl, bat_fate_id, run1_fate_id, run2_fate_id, run3_fate_id,
+        fate_runs_ct, ass6_fld_cd, ass7_fld_cd, ass8_fld_cd, ass9_fld_cd, ass10_fld_cd,
+        unknown_out_exc_fl, uncertain_play_exc_fl, count_tx,
+    ]);
+}
+
+
+fn run_cwevent(season_dir: &path::Path, season: &str, files: &[path::PathBuf]) -> Result<Vec<CombinedEvent>, Box<dyn Error>> {
+    let mut cwevent = Command::new("cwevent");
+    cwevent.args(["-q", "-y", season, "-f", "0-96", "-x", "0-63"])
+        .args(files)
+        .current_dir(season_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped());
+    let mut child = cwevent.spawn()?;
+    let stdout = child.stdout.take().expect("cwevent stdout handle not available");
+    let events = read_full_extended_events(stdout)?;
+    child.wait()?;
+    Ok(events)
+}
+
+
+fn load_native(season_dir: &path::Path, files: &[path::PathBuf]) -> Result<Vec<CombinedEvent>, Box<dyn Error>> {
+    let roster = Roster::from_dir(season_dir)?;
+    let mut events = Vec::new();
+    for file in files {
+        events.extend(event::load_event_file(file, &roster)?);
+    }
+    Ok(events)
+}
+
+
+fn check_season(args: &CheckArgs, season: &str) -> Result<Comparison, Box<dyn Error>> {
+    let season_dir = args.retrosheet_dir.join(season);
+    let files = event::find_event_files(&season_dir)?;
+    let mut comparison = Comparison::new(args.examples);
+
+    let cwevent_events = run_cwevent(&season_dir, season, &files)?;
+    let native_events = load_native(&season_dir, &files)?;
+
+    if native_events.len() != cwevent_events.len() {
+        eprintln!("Season {}: native reader found {} events, cwevent found {}",
+                  season, native_events.len(), cwevent_events.len());
+    }
+    for (native, cwevent) in native_events.iter().zip(cwevent_events.iter()) {
+        compare_event(&mut comparison, native, cwevent);
+    }
+    Ok(comparison)
+}
+
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = CheckArgs::parse();
+
+    for season in &args.seasons {
+        let comparison = check_season(&args, season)?;
+        println!("Season {}: {} events compared, {} fields differ",
+                 season, comparison.events, comparison.mismatches.len());
+        for mismatch in &comparison.mismatches {
+            println!("  {}: {} events", mismatch.field, mismatch.count);
+            for example in &mismatch.examples {
+                println!("    {}", example);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// testdata/events/2020 holds a one inning game along with its expected events, in cwevent's
+    /// `-f 0-96 -x 0-63` layout, in 2020TST.EVN.expected. The expected rows were worked out by hand
+    /// from the event file rather than produced by cwevent.
+    #[test]
+    fn fixture_matches_expected() {
+        let season_dir = path::Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/events/2020");
+        let files = event::find_event_files(&season_dir).unwrap();
+        assert_eq!(files.len(), 1);
+        let native_events = load_native(&season_dir, &files).unwrap();
+        let expected = fs::File::open(season_dir.join("2020TST.EVN.expected")).unwrap();
+        let expected_events = read_full_extended_events(expected).unwrap();
+        assert_eq!(native_events.len(), expected_events.len());
+
+        let mut comparison = Comparison::new(1);
+        for (native, expected) in native_events.iter().zip(expected_events.iter()) {
+            compare_event(&mut comparison, native, expected);
+        }
+        let mismatches: Vec<&String> = comparison.mismatches.iter()
+            .flat_map(|mismatch| mismatch.examples.iter())
+            .collect();
+        assert!(mismatches.is_empty(), "fields differ from the expected events: {:?}", mismatches);
+        assert_eq!(comparison.events, 9);
+    }
+}
@@ -1,26 +1,44 @@
 use std::cmp::Reverse;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 use std::path;
 use std::time::Instant;
 
-use baseball_tools::database::Sql;
+use baseball_tools::database::{self, Sql};
+use baseball_tools::events::{Event, PlateAppearance};
 use baseball_tools::games;
-use baseball_tools::player;
-use baseball_tools::search::{CelEval, CelExec, Key, SearchKey, StreakSpan, WindowEntry};
+use baseball_tools::output::OutputArgs;
+use baseball_tools::parks::{ParkFactors, WithParkFactor};
+use baseball_tools::player::{self, PlayerGamelog};
+use baseball_tools::search::{Aggregate, CelEval, CelExec, Key, Matchup, SearchKey, SortOrder, StreakSpan, WindowEntry};
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use chrono::Datelike;
 use rayon::prelude::*;
 use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
 use tracing::debug;
 
 
+/// Most streaks or windows to print.
+const DISPLAY_LIMIT: usize = 200;
+
+/// Number of game columns selected before the columns of player game logs.
+const GAME_COLUMNS: usize = 5;
+
+
 #[derive(Parser)]
 struct PlayIndexCelArgs {
     #[arg(long, value_name = "PROGRAM")]
     sort_key: Option<String>,
 
+    /// Print the Retrosheet player, team and game IDs instead of names and dates.
+    #[arg(long)]
+    raw_ids: bool,
+
+    #[command(flatten)]
+    output: OutputArgs,
+
     database: path::PathBuf,
     table: SearchTable,
     #[command(subcommand)]
@@ -34,6 +52,8 @@ enum SearchTable {
     FieldingGameLogs,
     PitchingGameLogs,
     TeamGames,
+    /// Plate appearances from the events table, in order for each batter or pitcher.
+    Events,
 }
 
 #[derive(Clone, Subcommand)]
@@ -42,6 +62,10 @@ enum SearchCommand {
     Search(SearchArgs),
     Streak(StreakArgs),
     Window(WindowArgs),
+    /// The first game of each player where the condition is met, fastest by career game.
+    Milestone(MilestoneArgs),
+    /// Totals of programs for each player or team, grouped by season, career, team or opponent.
+    Aggregate(AggregateArgs),
 }
 
 #[derive(Clone, Args)]
@@ -85,6 +109,34 @@ struct StreakArgs {
     #[arg(short = 'c', long)]
     career: bool,
 
+    /// Follow each pitcher's plate appearances instead of each batter's when searching events.
+    #[arg(long)]
+    pitcher: bool,
+
+    /// Follow the schedule of each player's team, adding the games they missed with `missed` set
+    /// for the condition to decide on. Only for player game logs.
+    #[arg(long)]
+    team_schedule: bool,
+
+    /// Split the games of each player or team by the values of a program, like `park_id`. Repeat
+    /// to split by several.
+    #[arg(short = 'g', long = "group", value_name = "PROGRAM")]
+    groups: Vec<String>,
+
+    /// Make the group values the subject, gathering the games of everyone with the same values,
+    /// like the seasons of managers with `--group manager_id`.
+    #[arg(long, requires = "groups")]
+    group_subject: bool,
+
+    /// Games in a streak allowed to not meet the condition, like 1 for reaching base in 38 of 39
+    /// games.
+    #[arg(long, value_name = "GAMES")]
+    max_misses: Option<u32>,
+
+    /// Games in a row in a streak allowed to not meet the condition.
+    #[arg(long, value_name = "GAMES")]
+    max_consecutive_misses: Option<u32>,
+
     #[arg(long, value_name = "PROGRAM")]
     pre_filter: Option<String>,
 
@@ -115,9 +167,28 @@ struct WindowArgs {
     #[arg(short = 'c', long)]
     career: bool,
 
+    /// Follow each pitcher's plate appearances instead of each batter's when searching events.
+    #[arg(long)]
+    pitcher: bool,
+
+    /// Split the games of each player or team by the values of a program, like `park_id`. Repeat
+    /// to split by several.
+    #[arg(short = 'g', long = "group", value_name = "PROGRAM")]
+    groups: Vec<String>,
+
+    /// Make the group values the subject, gathering the games of everyone with the same values,
+    /// like the seasons of managers with `--group manager_id`.
+    #[arg(long, requires = "groups")]
+    group_subject: bool,
+
     #[arg(long, value_name = "PROGRAM")]
     rate: Option<String>,
 
+    /// Instead of windows of `size` games, find the longest span of at least `size` games where
+    /// this share of the games have a count, like 0.8.
+    #[arg(long, value_name = "RATIO")]
+    density: Option<f64>,
+
     #[arg()]
     size: u8,
 
@@ -125,6 +196,123 @@ struct WindowArgs {
     count: String,
 }
 
+#[derive(Clone, Args)]
+struct MilestoneArgs {
+    #[arg(short = 'n', long)]
+    limit: Option<usize>,
+
+    #[arg(short = 't', long)]
+    team: Option<String>,
+
+    #[arg(short = 's', long, value_name = "YEAR")]
+    year_start: Option<i32>,
+
+    #[arg(short = 'e', long, value_name = "YEAR")]
+    year_end: Option<i32>,
+
+    /// Split the games of each player or team by the values of a program, like `park_id`. Repeat
+    /// to split by several.
+    #[arg(short = 'g', long = "group", value_name = "PROGRAM")]
+    groups: Vec<String>,
+
+    /// Make the group values the subject, gathering the games of everyone with the same values,
+    /// like the seasons of managers with `--group manager_id`.
+    #[arg(long, requires = "groups")]
+    group_subject: bool,
+
+    /// Condition on the career totals through each game, like `career_hr >= 500`.
+    #[arg()]
+    condition: String,
+}
+
+#[derive(Clone, Args)]
+struct AggregateArgs {
+    #[arg(short = 'n', long)]
+    limit: Option<usize>,
+
+    #[arg(short = 't', long)]
+    team: Option<String>,
+
+    #[arg(short = 's', long, value_name = "YEAR")]
+    year_start: Option<i32>,
+
+    #[arg(short = 'e', long, value_name = "YEAR")]
+    year_end: Option<i32>,
+
+    /// Follow each pitcher's plate appearances instead of each batter's when searching events.
+    #[arg(long)]
+    pitcher: bool,
+
+    #[arg(long, value_enum, default_value_t)]
+    by: GroupBy,
+
+    /// Split the games of each player or team by the values of a program, like `park_id`. Repeat
+    /// to split by several.
+    #[arg(short = 'g', long = "group", value_name = "PROGRAM")]
+    groups: Vec<String>,
+
+    /// Make the group values the subject, gathering the games of everyone with the same values,
+    /// like the seasons of managers with `--group manager_id`.
+    #[arg(long, requires = "groups")]
+    group_subject: bool,
+
+    #[arg(long, value_name = "PROGRAM")]
+    filter: Option<String>,
+
+    /// Condition on the totals of a group to keep it, like `pa >= 200`.
+    #[arg(long, value_name = "PROGRAM")]
+    having: Option<String>,
+
+    /// Program on the totals to rank the groups by, like `h / ab`. Defaults to the first total.
+    #[arg(long, value_name = "PROGRAM")]
+    sort_key: Option<String>,
+
+    /// Totals to add up for each group, like `hr=hr` or `multi_hit=h >= 2`. True counts as one and
+    /// `games` is always the number of games.
+    #[arg(required = true, value_name = "NAME=PROGRAM")]
+    sums: Vec<String>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum)]
+enum GroupBy {
+    #[default]
+    Season,
+    Career,
+    Team,
+    Opponent,
+}
+
+
+/// Writes the results, with the names of players and teams and the dates of games in place of
+/// their IDs.
+struct Report {
+    output: OutputArgs,
+    raw_ids: bool,
+    players: HashMap<String, String>,
+    /// Names of each team by season, oldest first.
+    teams: HashMap<String, Vec<(u16, String)>>,
+}
+
+
+/// One game found by a search.
+#[derive(Default, Serialize)]
+struct GameRow {
+    subject: String,
+    game: String,
+    sort_key: Option<f64>,
+}
+
+
+/// The game where a player met a milestone.
+#[derive(Default, Serialize)]
+struct MilestoneRow {
+    subject: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    group: String,
+    game: String,
+    career_game: u16,
+}
+
 
 struct QueryArgs {
     career: bool,
@@ -132,30 +320,107 @@ struct QueryArgs {
     year_start: Option<i32>,
     year_end: Option<i32>,
     game_start: Option<u16>,
+    pitcher: bool,
+    /// Set `park_factor` on the loaded games.
+    park_factors: bool,
+    /// Set `age_days` and `age` on the loaded player game logs.
+    ages: bool,
+    /// Set `opponent`, `park_id` and `manager_id` on the loaded player game logs.
+    game_details: bool,
 }
 
 impl QueryArgs {
-    fn from_streak(streak_args: &StreakArgs) -> Self {
-        Self {
+    fn from_search(search_args: &SearchArgs, sort_key: Option<&String>) -> Result<Self, Box<dyn Error>> {
+        // Searching doesn't split games by season so load them the same way as career mode.
+        Ok(Self {
+            career: true,
+            team: search_args.team.clone(),
+            year_start: search_args.year_start,
+            year_end: search_args.year_end,
+            game_start: None,
+            pitcher: false,
+            park_factors: uses_variable(&[search_args.filter.as_ref(), sort_key], "park_factor")?,
+            ages: uses_age(&[search_args.filter.as_ref(), sort_key])?,
+            game_details: uses_game_details(&[search_args.filter.as_ref(), sort_key])?,
+        })
+    }
+
+    fn from_streak(streak_args: &StreakArgs) -> Result<Self, Box<dyn Error>> {
+        let mut programs: Vec<Option<&String>> = streak_args.groups.iter().map(Some).collect();
+        programs.extend([Some(&streak_args.condition), streak_args.count.as_ref()]);
+        Ok(Self {
             career: streak_args.career,
             team: streak_args.team.clone(),
             year_start: streak_args.year_start,
             year_end: streak_args.year_end,
             game_start: streak_args.game_start,
-        }
+            pitcher: streak_args.pitcher,
+            park_factors: uses_variable(&programs, "park_factor")?,
+            ages: uses_age(&programs)?,
+            game_details: uses_game_details(&programs)?,
+        })
     }
 
-    fn from_window(window_args: &WindowArgs) -> Self {
-        Self {
+    fn from_window(window_args: &WindowArgs) -> Result<Self, Box<dyn Error>> {
+        let mut programs: Vec<Option<&String>> = window_args.groups.iter().map(Some).collect();
+        programs.extend([Some(&window_args.count), window_args.rate.as_ref()]);
+        Ok(Self {
             career: window_args.career,
             team: window_args.team.clone(),
             year_start: window_args.year_start,
             year_end: window_args.year_end,
             game_start: window_args.game_start,
-        }
+            pitcher: window_args.pitcher,
+            park_factors: uses_variable(&programs, "park_factor")?,
+            ages: uses_age(&programs)?,
+            game_details: uses_game_details(&programs)?,
+        })
+    }
+
+    fn from_milestone(milestone_args: &MilestoneArgs) -> Result<Self, Box<dyn Error>> {
+        let mut programs: Vec<Option<&String>> = milestone_args.groups.iter().map(Some).collect();
+        programs.push(Some(&milestone_args.condition));
+        Ok(Self {
+            career: true,
+            team: milestone_args.team.clone(),
+            year_start: milestone_args.year_start,
+            year_end: milestone_args.year_end,
+            game_start: None,
+            pitcher: false,
+            park_factors: uses_variable(&programs, "park_factor")?,
+            ages: uses_age(&programs)?,
+            game_details: uses_game_details(&programs)?,
+        })
     }
 
-    fn build_game_log_query<T: Sql>(&self) -> (String, Vec<(&str, String)>) {
+    fn from_aggregate(aggregate_args: &AggregateArgs) -> Result<Self, Box<dyn Error>> {
+        let mut programs: Vec<Option<&String>> = aggregate_args.sums.iter().chain(&aggregate_args.groups).map(Some).collect();
+        programs.push(aggregate_args.filter.as_ref());
+        // Groups are made after loading so load everything the same way as career mode.
+        Ok(Self {
+            career: true,
+            team: aggregate_args.team.clone(),
+            year_start: aggregate_args.year_start,
+            year_end: aggregate_args.year_end,
+            game_start: None,
+            pitcher: aggregate_args.pitcher,
+            park_factors: uses_variable(&programs, "park_factor")?,
+            ages: uses_age(&programs)?,
+            game_details: aggregate_args.by == GroupBy::Opponent || uses_game_details(&programs)?,
+        })
+    }
+
+    /// Whether player game logs are joined to their games, which selects the game columns before
+    /// the game log's.
+    fn selects_game_columns(&self) -> bool {
+        let need_where = self.team.is_some() || self.year_start.is_some() || self.year_end.is_some();
+        !self.career || need_where || self.ages || self.game_details
+    }
+
+    /// Player game logs joined to their games start with the game columns: the date, the birth
+    /// date of the player when `birth_dates` is set, the opposing team, the park and the manager of
+    /// the player's team.
+    fn build_game_log_query<T: Sql>(&self, birth_dates: bool) -> (String, Vec<(&str, String)>) {
         let mut select_sql = String::with_capacity(300);
         let mut params = Vec::new();
         let table_name = T::table_name();
@@ -168,11 +433,26 @@ impl QueryArgs {
         // Need to do a join if not separating the games by year or if limiting the games to select
         // by a year.
         let need_where = self.team.is_some() || self.year_start.is_some() || self.year_end.is_some();
-        let join = !loading_teams && (!self.career || need_where);
-        // This column is only necessary when needing to split up the player game logs by season.
-        // It can be skipped in career mode.
-        if !self.career && !loading_teams {
+        let birth_dates = birth_dates && !loading_teams;
+        let join = !loading_teams && self.selects_game_columns();
+        // The game columns are only necessary when needing to split up the player game logs by
+        // season or to know more about the games. They can be skipped otherwise in career mode.
+        if join {
             select_sql.push_str("games.date, ");
+            // Birth dates missing any part are unknown.
+            if birth_dates {
+                select_sql.push_str(
+                    "CASE WHEN p.birth_year <> '' AND p.birth_month <> '' AND p.birth_day <> ''
+                        THEN printf('%04d-%02d-%02d', p.birth_year, p.birth_month, p.birth_day)
+                    END, "
+                );
+            }
+            else {
+                select_sql.push_str("NULL, ");
+            }
+            select_sql.push_str("CASE WHEN games.home_team = gl.team_id THEN games.visitor_team ELSE games.home_team END, ");
+            select_sql.push_str("games.park_id, ");
+            select_sql.push_str("CASE WHEN games.home_team = gl.team_id THEN games.home_manager_id ELSE games.visitor_manager_id END, ");
         }
         for (idx, name) in T::column_names().iter().enumerate() {
             if idx > 0 {
@@ -192,6 +472,9 @@ impl QueryArgs {
         if join {
             select_sql.push_str(" JOIN games ON gl.game_id = games.game_id");
         }
+        if birth_dates {
+            select_sql.push_str(" LEFT JOIN people p ON p.key_retro = gl.player_id");
+        }
 
         if need_where {
             select_sql.push_str(" WHERE ");
@@ -223,31 +506,208 @@ impl QueryArgs {
             team = self.team,
             year_start = self.year_start,
             year_end = self.year_end,
+            birth_dates = birth_dates,
             length = select_sql.len(),
             "SQL to select game logs"
         );
         (select_sql, params)
     }
+
+    /// Plate appearances are always joined to the games to order them by date.
+    fn build_event_query(&self) -> (String, Vec<(&str, String)>) {
+        let mut select_sql = String::with_capacity(1000);
+        let mut params = Vec::new();
+
+        select_sql.push_str("SELECT games.date");
+        for name in Event::column_names() {
+            select_sql.push_str(", e.");
+            select_sql.push_str(&name);
+        }
+        select_sql.push_str(" FROM ");
+        select_sql.push_str(Event::table_name());
+        select_sql.push_str(" e JOIN games ON e.game_id = games.game_id WHERE e.bat_event_fl = 1");
+
+        if let Some(team) = &self.team {
+            if self.pitcher {
+                select_sql.push_str(" AND e.fld_team_id = :team");
+            }
+            else {
+                select_sql.push_str(" AND e.bat_team_id = :team");
+            }
+            params.push((":team", team.to_string()));
+        }
+        if let Some(year) = self.year_start {
+            select_sql.push_str(" AND strftime('%Y', games.date) >= :start");
+            params.push((":start", year.to_string()));
+        }
+        if let Some(year) = self.year_end {
+            select_sql.push_str(" AND strftime('%Y', games.date) <= :end");
+            params.push((":end", year.to_string()));
+        }
+        select_sql.push_str(" ORDER BY games.date, e.game_id, e.event_id");
+        debug!(
+            sql = select_sql,
+            career = self.career,
+            pitcher = self.pitcher,
+            team = self.team,
+            year_start = self.year_start,
+            year_end = self.year_end,
+            "SQL to select events"
+        );
+        (select_sql, params)
+    }
+}
+
+
+impl Report {
+    /// Without the people or teams tables, or with `raw_ids`, the IDs are printed as they are.
+    fn load(conn: &Connection, raw_ids: bool, output: OutputArgs) -> Result<Self, Box<dyn Error>> {
+        let mut players = HashMap::new();
+        let mut teams: HashMap<String, Vec<(u16, String)>> = HashMap::new();
+        if !raw_ids && conn.table_exists(Some("main"), "people")? {
+            let mut statement = conn.prepare(
+                "SELECT key_retro, name_first, name_last FROM people WHERE key_retro IS NOT NULL AND key_retro <> ''"
+            )?;
+            let mut rows = statement.query(())?;
+            while let Some(row) = rows.next()? {
+                let first: Option<String> = row.get(1)?;
+                let last: Option<String> = row.get(2)?;
+                let name = [first, last].into_iter().flatten().filter(|n| !n.is_empty()).collect::<Vec<_>>().join(" ");
+                if !name.is_empty() {
+                    players.insert(row.get(0)?, name);
+                }
+            }
+        }
+        if !raw_ids && conn.table_exists(Some("main"), games::Team::table_name())? {
+            let mut statement = conn.prepare(&format!("SELECT * FROM {} ORDER BY season", games::Team::table_name()))?;
+            let mut rows = statement.query(())?;
+            while let Some(row) = rows.next()? {
+                let team = games::Team::read_row(row, 0)?;
+                teams.entry(team.team_id.clone()).or_default().push((team.season, team.name()));
+            }
+        }
+        Ok(Self { output, raw_ids, players, teams })
+    }
+
+    /// Write the results in the chosen format, after the total found in the text format.
+    fn write<T: Serialize + Default>(&self, label: &str, total: usize, rows: &[T]) -> Result<(), Box<dyn Error>> {
+        if self.output.is_text() {
+            println!("Total {}: {}", label, total);
+        }
+        self.output.write(rows)
+    }
+
+    /// Write rows with columns only known at run time, after the total found in the text format.
+    fn write_columns(&self, label: &str, total: usize, header: &[String], rows: &[Vec<serde_json::Value>]) -> Result<(), Box<dyn Error>> {
+        if self.output.is_text() {
+            println!("Total {}: {}", label, total);
+        }
+        self.output.write_columns(header, rows)
+    }
+
+    /// Name of a player or team. Teams are named as they were in the season of the game.
+    fn subject(&self, id: &str, game_id: &str) -> String {
+        if let Some(name) = self.players.get(id) {
+            return name.clone();
+        }
+        if let Some(seasons) = self.teams.get(id) {
+            let season = game_id.get(3..7).and_then(|year| year.parse::<u16>().ok());
+            let name = seasons.iter().rev()
+                .find(|(team_season, _)| Some(*team_season) == season)
+                .or(seasons.last());
+            if let Some((_, name)) = name {
+                return name.clone();
+            }
+        }
+        id.to_string()
+    }
+
+    /// Date of a game, like "2020-08-01 (2)" for the second game of a doubleheader. Plate
+    /// appearance IDs add the event number, like "2020-08-01 #45".
+    fn game(&self, game_id: &str) -> String {
+        if self.raw_ids {
+            return game_id.to_string();
+        }
+        let date = game_id.get(3..11).and_then(|date| chrono::NaiveDate::parse_from_str(date, "%Y%m%d").ok());
+        let (Some(date), Some(number), Some(rest)) = (date, game_id.get(11..12), game_id.get(12..))
+        else {
+            return game_id.to_string();
+        };
+        let mut text = date.to_string();
+        if number != "0" {
+            text.push_str(&format!(" ({})", number));
+        }
+        if let Some(event_id) = rest.strip_prefix('-') {
+            text.push_str(&format!(" #{}", event_id));
+        }
+        text
+    }
+}
+
+
+/// Park factors and ages are only loaded when one of the programs uses them.
+fn uses_variable(programs: &[Option<&String>], variable: &str) -> Result<bool, Box<dyn Error>> {
+    for program in programs.iter().flatten() {
+        if CelExec::references_variable(program, variable)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+
+fn uses_game_details(programs: &[Option<&String>]) -> Result<bool, Box<dyn Error>> {
+    Ok(uses_variable(programs, "opponent")? || uses_variable(programs, "park_id")? || uses_variable(programs, "manager_id")?)
+}
+
+
+fn uses_age(programs: &[Option<&String>]) -> Result<bool, Box<dyn Error>> {
+    Ok(uses_variable(programs, "age_days")? || uses_variable(programs, "age")?)
+}
+
+
+fn apply_park_factors<T: WithParkFactor>(conn: &Connection, args: &QueryArgs, games: &mut HashMap<Key, Vec<T>>) -> Result<(), Box<dyn Error>> {
+    if !args.park_factors {
+        return Ok(());
+    }
+    let park_factors = ParkFactors::load(conn, args.year_start, args.year_end)?;
+    for games in games.values_mut() {
+        park_factors.apply(games);
+    }
+    Ok(())
 }
 
 
-fn load_player_games<T: SearchKey + Sql>(conn: &Connection, args: &QueryArgs) -> Result<HashMap<Key, Vec<T>>, Box<dyn Error>> {
-    let (select_sql, params) = args.build_game_log_query::<T>();
+fn load_player_games<T>(conn: &Connection, args: &QueryArgs) -> Result<HashMap<Key, Vec<T>>, Box<dyn Error>>
+    where T: SearchKey + Sql + WithParkFactor + PlayerGamelog
+{
+    // Without the register loaded every age is unknown.
+    let birth_dates = args.ages && conn.table_exists(Some("main"), "people")?;
+    if args.ages && !birth_dates {
+        eprintln!("No people table, load the register to know the ages of players");
+    }
+    let (select_sql, params) = args.build_game_log_query::<T>(birth_dates);
     let load_start = Instant::now();
     let mut players = HashMap::new();
     let mut statement = conn.prepare(&select_sql)?;
     let mut found_game_logs = 0;
     let mut rows = statement.query(&params[0..])?;
+    let game_columns = args.selects_game_columns();
     while let Some(row) = rows.next()? {
-        let gl;
-        let key = if args.career {
-            gl = T::read_row(row, 0)?;
-            Key { id: gl.subject_id().to_string(), year: 0 }
+        let mut gl;
+        let key = if game_columns {
+            let date: chrono::NaiveDate = row.get(0)?;
+            gl = T::read_row(row, GAME_COLUMNS)?;
+            if args.ages {
+                gl.set_age(row.get(1)?, date);
+            }
+            gl.set_game_details(row.get(2)?, row.get(3)?, row.get(4)?);
+            let year = if args.career { 0 } else { date.year() };
+            Key { id: gl.subject_id().to_string(), year, group: String::new() }
         }
         else {
-            let date: chrono::NaiveDate = row.get(0)?;
-            gl = T::read_row(row, 1)?;
-            Key { id: gl.subject_id().to_string(), year: date.year() }
+            gl = T::read_row(row, 0)?;
+            Key { id: gl.subject_id().to_string(), year: 0, group: String::new() }
         };
 
         // If this game log comes before the starting game, ignore it.
@@ -261,12 +721,171 @@ fn load_player_games<T: SearchKey + Sql>(conn: &Connection, args: &QueryArgs) ->
     }
     let load_end = Instant::now();
     debug!(player_seasons = players.len(), games_found = found_game_logs, duration = format!("{:?}", load_end.duration_since(load_start)), "Loaded player games");
+    apply_park_factors(conn, args, &mut players)?;
     Ok(players)
 }
 
 
+/// A game of a team's schedule, with the details given to a player's missed game.
+struct ScheduleGame {
+    game_id: String,
+    opponent: String,
+    park_id: String,
+    manager_id: String,
+}
+
+
+/// Every team's games by team and season, then by team game number.
+type Schedules = HashMap<(String, i32), BTreeMap<u16, ScheduleGame>>;
+
+
+fn load_team_schedules(conn: &Connection, args: &QueryArgs) -> Result<Schedules, Box<dyn Error>> {
+    let mut select_sql = String::from(
+        "SELECT game_id, date, park_id,
+            home_team, home_team_game_number, home_manager_id,
+            visitor_team, visitor_team_game_number, visitor_manager_id
+        FROM games WHERE 1 = 1"
+    );
+    let mut params = Vec::new();
+    if let Some(year) = args.year_start {
+        select_sql.push_str(" AND strftime('%Y', date) >= :start");
+        params.push((":start", year.to_string()));
+    }
+    if let Some(year) = args.year_end {
+        select_sql.push_str(" AND strftime('%Y', date) <= :end");
+        params.push((":end", year.to_string()));
+    }
+    let mut schedules = Schedules::new();
+    let mut statement = conn.prepare(&select_sql)?;
+    let mut rows = statement.query(&params[0..])?;
+    while let Some(row) = rows.next()? {
+        let game_id: String = row.get(0)?;
+        let date: chrono::NaiveDate = row.get(1)?;
+        let park_id: Option<String> = row.get(2)?;
+        let home: (String, u16, Option<String>) = (row.get(3)?, row.get(4)?, row.get(5)?);
+        let visitor: (String, u16, Option<String>) = (row.get(6)?, row.get(7)?, row.get(8)?);
+        for ((team, game_number, manager_id), opponent) in [(home.clone(), visitor.0.clone()), (visitor, home.0)] {
+            let game = ScheduleGame {
+                game_id: game_id.clone(),
+                opponent,
+                park_id: park_id.clone().unwrap_or_default(),
+                manager_id: manager_id.unwrap_or_default(),
+            };
+            schedules.entry((team, date.year())).or_default().insert(game_number, game);
+        }
+    }
+    Ok(schedules)
+}
+
+
+/// Put each player's games in order by date and add the games of their team's schedule they
+/// missed. A player's games for one team in a season are filled in between the first and last, and
+/// also from the start of the season before the first team they played for and to the end of the
+/// season after the last.
+fn add_missed_games<T>(conn: &Connection, args: &QueryArgs, players: &mut HashMap<Key, Vec<T>>) -> Result<(), Box<dyn Error>>
+    where T: PlayerGamelog + SearchKey
+{
+    let load_start = Instant::now();
+    let schedules = load_team_schedules(conn, args)?;
+    let mut missed_games = 0;
+    // Game IDs after the home team are the date and game number, which sort in order.
+    let season = |game_id: &str| game_id.get(3..7).and_then(|year| year.parse::<i32>().ok());
+    for games in players.values_mut() {
+        games.sort_by(|a, b| a.game_id().get(3..).cmp(&b.game_id().get(3..)));
+        // Consecutive games for the same team in the same season.
+        let mut stints: Vec<Vec<T>> = Vec::new();
+        for game in games.drain(..) {
+            match stints.last_mut() {
+                Some(stint) if stint[0].team_id() == game.team_id() && season(stint[0].game_id()) == season(game.game_id()) => {
+                    stint.push(game);
+                }
+                _ => stints.push(vec![game]),
+            }
+        }
+
+        let seasons: Vec<Option<i32>> = stints.iter().map(|stint| season(stint[0].game_id())).collect();
+        let mut schedule_games: Vec<T> = Vec::with_capacity(stints.len());
+        for (idx, stint) in stints.into_iter().enumerate() {
+            let schedule = seasons[idx].and_then(|year| schedules.get(&(stint[0].team_id().to_string(), year)));
+            let Some(schedule) = schedule
+            else {
+                schedule_games.extend(stint);
+                continue;
+            };
+            let season_start = idx == 0 || seasons[idx - 1] != seasons[idx];
+            let season_end = idx + 1 == seasons.len() || seasons[idx + 1] != seasons[idx];
+            let first_game = stint[0].team_game();
+            let last_game = stint[stint.len() - 1].team_game();
+
+            let mut played = stint.into_iter().peekable();
+            let mut stint_games: Vec<T> = Vec::new();
+            for (&team_game, scheduled) in schedule {
+                if (!season_start && team_game < first_game) || (!season_end && team_game > last_game) {
+                    continue;
+                }
+                while let Some(game) = played.next_if(|game| game.team_game() <= team_game) {
+                    stint_games.push(game);
+                }
+                if stint_games.last().is_some_and(|game| game.team_game() == team_game) {
+                    continue;
+                }
+                let mut missed = match (stint_games.last(), played.peek()) {
+                    (Some(previous), _) => previous.missed_game(&scheduled.game_id, team_game),
+                    (None, Some(next)) => next.missed_game_before(&scheduled.game_id, team_game),
+                    (None, None) => break,
+                };
+                if args.game_start.is_some_and(|game_start| missed.order(args.career) < game_start) {
+                    continue;
+                }
+                missed.set_game_details(scheduled.opponent.clone(), scheduled.park_id.clone(), scheduled.manager_id.clone());
+                stint_games.push(missed);
+                missed_games += 1;
+            }
+            stint_games.extend(played);
+            schedule_games.extend(stint_games);
+        }
+        *games = schedule_games;
+    }
+    let load_end = Instant::now();
+    debug!(missed_games = missed_games, duration = format!("{:?}", load_end.duration_since(load_start)), "Added missed games");
+    Ok(())
+}
+
+
+fn load_plate_appearances(conn: &Connection, args: &QueryArgs) -> Result<HashMap<Key, Vec<PlateAppearance>>, Box<dyn Error>> {
+    let (select_sql, params) = args.build_event_query();
+    let load_start = Instant::now();
+    let mut subjects: HashMap<Key, Vec<PlateAppearance>> = HashMap::new();
+    let mut statement = conn.prepare(&select_sql)?;
+    let mut found_events = 0;
+    let mut rows = statement.query(&params[0..])?;
+    while let Some(row) = rows.next()? {
+        let date: chrono::NaiveDate = row.get(0)?;
+        let mut pa = PlateAppearance::new(Event::read_row(row, 1)?, args.pitcher);
+        let year = if args.career { 0 } else { date.year() };
+        let key = Key { id: pa.subject_id().to_string(), year, group: String::new() };
+        let entry = subjects.entry(key).or_default();
+        // Rows come back in order so the plate appearance number is the position in the season
+        // or career.
+        pa.order = u16::try_from(entry.len() + 1).unwrap_or(u16::MAX);
+        entry.push(pa);
+        found_events += 1;
+    }
+    // Skip any plate appearances before the starting one after numbering them all.
+    if let Some(game_start) = args.game_start {
+        for entries in subjects.values_mut() {
+            entries.retain(|pa| pa.order >= game_start);
+        }
+    }
+    let load_end = Instant::now();
+    debug!(subjects = subjects.len(), events_found = found_events, duration = format!("{:?}", load_end.duration_since(load_start)), "Loaded plate appearances");
+    apply_park_factors(conn, args, &mut subjects)?;
+    Ok(subjects)
+}
+
+
 fn load_team_games(conn: &Connection, args: &QueryArgs) -> Result<HashMap<Key, Vec<games::TeamGameLogSmall>>, Box<dyn Error>> {
-    let (select_sql, params) = args.build_game_log_query::<games::GameLogSmall>();
+    let (select_sql, params) = args.build_game_log_query::<games::GameLogSmall>(false);
     let load_start = Instant::now();
     let mut team_seasons = HashMap::new();
     let mut statement = conn.prepare(&select_sql)?;
@@ -277,12 +896,12 @@ fn load_team_games(conn: &Connection, args: &QueryArgs) -> Result<HashMap<Key, V
         let (home, visitor) = gl.each_team_game();
         match &args.team {
             Some(team) if *team == home.team => {
-                let home_key = Key { id: team.to_string(), year: home.date.year() };
+                let home_key = Key { id: team.to_string(), year: home.date.year(), group: String::new() };
                 let entry = team_seasons.entry(home_key).or_insert_with(Vec::new);
                 entry.push(home);
             }
             None => {
-                let home_key = Key { id: home.subject_id().to_string(), year: home.date.year() };
+                let home_key = Key { id: home.subject_id().to_string(), year: home.date.year(), group: String::new() };
                 let entry = team_seasons.entry(home_key).or_insert_with(Vec::new);
                 entry.push(home);
             }
@@ -290,12 +909,12 @@ fn load_team_games(conn: &Connection, args: &QueryArgs) -> Result<HashMap<Key, V
         }
         match &args.team {
             Some(team) if *team == visitor.team => {
-                let visitor_key = Key { id: team.to_string(), year: visitor.date.year() };
+                let visitor_key = Key { id: team.to_string(), year: visitor.date.year(), group: String::new() };
                 let entry = team_seasons.entry(visitor_key).or_insert_with(Vec::new);
                 entry.push(visitor);
             }
             None => {
-                let visitor_key = Key { id: visitor.subject_id().to_string(), year: visitor.date.year() };
+                let visitor_key = Key { id: visitor.subject_id().to_string(), year: visitor.date.year(), group: String::new() };
                 let entry = team_seasons.entry(visitor_key).or_insert_with(Vec::new);
                 entry.push(visitor);
             }
@@ -305,11 +924,58 @@ fn load_team_games(conn: &Connection, args: &QueryArgs) -> Result<HashMap<Key, V
     }
     let load_end = Instant::now();
     debug!(team_seasons = team_seasons.len(), games_found = found_game_logs, duration = format!("{:?}", load_end.duration_since(load_start)), "Loaded team games");
+    apply_park_factors(conn, args, &mut team_seasons)?;
     Ok(team_seasons)
 }
 
 
-fn find_game_streaks<T>(streak_args: &StreakArgs, mut games: HashMap<Key, Vec<T>>) -> Result<(), Box<dyn Error>>
+fn search_games<T>(report: &Report, search_args: &SearchArgs, sort_key: Option<&String>, games: HashMap<Key, Vec<T>>) -> Result<(), Box<dyn Error>>
+    where T: Send + Sync + SearchKey + CelEval
+{
+    let mut exec = CelExec::default();
+    if let Some(program) = &search_args.filter {
+        exec.set_filter(program)?;
+    }
+    if let Some(program) = sort_key {
+        exec.set_sort(program)?;
+    }
+    if let Some(limit) = search_args.limit {
+        exec.set_limit(limit);
+    }
+
+    let mut games: Vec<T> = games.into_values().flatten().collect();
+    let filter_start = Instant::now();
+    exec.filter(&mut games);
+    let filter_end = Instant::now();
+    debug!(games = games.len(), duration = format!("{:?}", filter_end.duration_since(filter_start)), "Filtered games");
+
+    let sort_start = Instant::now();
+    exec.sort(&mut games, &SortOrder::Desc);
+    let sort_end = Instant::now();
+    debug!(duration = format!("{:?}", sort_end.duration_since(sort_start)), "Sorted games");
+
+    games.truncate(exec.limit());
+    display_games(&exec, report, &games)?;
+
+    Ok(())
+}
+
+
+fn display_games<T>(exec: &CelExec, report: &Report, games: &[T]) -> Result<(), Box<dyn Error>>
+    where T: SearchKey + CelEval
+{
+    let rows: Vec<GameRow> = games.iter()
+        .map(|game| GameRow {
+            subject: report.subject(game.subject_id(), game.id()),
+            game: report.game(game.id()),
+            sort_key: exec.sort_value(game),
+        })
+        .collect();
+    report.write("games", rows.len(), &rows)
+}
+
+
+fn find_game_streaks<T>(report: &Report, streak_args: &StreakArgs, games: HashMap<Key, Vec<T>>) -> Result<(), Box<dyn Error>>
     where T: Send + Sync + SearchKey + CelEval
 {
     let mut exec = CelExec::default();
@@ -321,14 +987,30 @@ fn find_game_streaks<T>(streak_args: &StreakArgs, mut games: HashMap<Key, Vec<T>
     if let Some(game_start) = streak_args.game_start {
         exec.set_game_start(game_start);
     }
+    if let Some(misses) = streak_args.max_misses {
+        exec.set_max_misses(misses);
+    }
+    if let Some(misses) = streak_args.max_consecutive_misses {
+        exec.set_max_consecutive_misses(misses);
+    }
     if let Some(limit) = streak_args.limit {
         exec.set_limit(limit);
     }
 
-    let sort_start = Instant::now();
-    games.par_iter_mut().for_each(|(_k, games)| games.sort_unstable_by_key(|g| g.order(streak_args.career)));
-    let sort_end = Instant::now();
-    debug!(duration = format!("{:?}", sort_end.duration_since(sort_start)), "Sorted games");
+    for program in &streak_args.groups {
+        exec.add_group(program)?;
+    }
+    exec.set_group_subject(streak_args.group_subject);
+    let mut games = exec.regroup(games)?;
+
+    // Games on the team schedule are already in order and missed games share the player's game
+    // number with the game before.
+    if !streak_args.team_schedule {
+        let sort_start = Instant::now();
+        games.par_iter_mut().for_each(|(_k, games)| games.sort_unstable_by_key(|g| g.order(streak_args.career)));
+        let sort_end = Instant::now();
+        debug!(duration = format!("{:?}", sort_end.duration_since(sort_start)), "Sorted games");
+    }
 
     let eval_start = Instant::now();
     let streak_map = exec.streak_eval(&games);
@@ -340,25 +1022,32 @@ fn find_game_streaks<T>(streak_args: &StreakArgs, mut games: HashMap<Key, Vec<T>
     let check_end = Instant::now();
     debug!(duration = format!("{:?}", check_end.duration_since(check_start)), "Found streaks");
 
-    display_streaks(streaks);
+    display_streaks(report, streaks)?;
 
     Ok(())
 }
 
 
-fn display_streaks(mut streaks: Vec<StreakSpan>) {
+fn display_streaks(report: &Report, mut streaks: Vec<StreakSpan>) -> Result<(), Box<dyn Error>> {
     streaks.sort_unstable_by_key(|streak| Reverse(streak.count));
-    println!("Total streaks: {}", streaks.len());
-    if !streaks.is_empty() {
-        println!("subject ID | game start | game end | count | streak length");
-        for streak in streaks.iter().take(200) {
-            println!("{} | {} | {} | {} | {}", streak.id, streak.start, streak.end, streak.count, streak.length);
-        }
+    display_spans(report, "streaks", streaks)
+}
+
+
+/// Write spans in the order given.
+fn display_spans(report: &Report, label: &str, mut spans: Vec<StreakSpan>) -> Result<(), Box<dyn Error>> {
+    let total = spans.len();
+    spans.truncate(DISPLAY_LIMIT);
+    for span in spans.iter_mut() {
+        span.id = report.subject(&span.id, &span.start);
+        span.start = report.game(&span.start);
+        span.end = report.game(&span.end);
     }
+    report.write(label, total, &spans)
 }
 
 
-fn find_game_windows<T>(window_args: &WindowArgs, mut games: HashMap<Key, Vec<T>>) -> Result<(), Box<dyn Error>>
+fn find_game_windows<T>(report: &Report, window_args: &WindowArgs, games: HashMap<Key, Vec<T>>) -> Result<(), Box<dyn Error>>
     where T: Send + Sync + SearchKey + CelEval
 {
     let mut exec = CelExec::default();
@@ -368,11 +1057,28 @@ fn find_game_windows<T>(window_args: &WindowArgs, mut games: HashMap<Key, Vec<T>
         exec.set_limit(limit);
     }
 
+    for program in &window_args.groups {
+        exec.add_group(program)?;
+    }
+    exec.set_group_subject(window_args.group_subject);
+    let mut games = exec.regroup(games)?;
+
     let sort_start = Instant::now();
     games.par_iter_mut().for_each(|(_k, games)| games.sort_unstable_by_key(|g| g.order(window_args.career)));
     let sort_end = Instant::now();
     debug!(duration = format!("{:?}", sort_end.duration_since(sort_start)), "Sorted games");
 
+    if let Some(ratio) = window_args.density {
+        if ratio <= 0.0 || ratio > 1.0 {
+            return Err(format!("density {ratio} is not a share of games above 0 and up to 1").into());
+        }
+        let eval_start = Instant::now();
+        let spans = exec.density_eval(&games, ratio, window_args.size as usize);
+        let eval_end = Instant::now();
+        debug!(ratio = ratio, duration = format!("{:?}", eval_end.duration_since(eval_start)), "Found dense spans");
+        return display_spans(report, "spans", spans);
+    }
+
     let eval_start = Instant::now();
     let team_windows = exec.window_eval(&games, window_args.size as usize);
     let eval_end = Instant::now();
@@ -383,124 +1089,480 @@ fn find_game_windows<T>(window_args: &WindowArgs, mut games: HashMap<Key, Vec<T>
     let check_end = Instant::now();
     debug!(duration = format!("{:?}", check_end.duration_since(check_start)), "Sorted windows");
 
-    display_windows(windows);
+    display_windows(report, windows)?;
 
     Ok(())
 }
 
 
-fn display_windows(windows: Vec<&WindowEntry>) {
-    println!("Total windows: {}", windows.len());
-    if !windows.is_empty() {
-        println!("subject ID | game start | game end | count");
-        for window in windows.iter().take(200) {
-            println!("{} | {} | {} | {}", window.id, window.start, window.end, window.count);
+fn display_windows(report: &Report, windows: Vec<&WindowEntry>) -> Result<(), Box<dyn Error>> {
+    let total = windows.len();
+    let windows: Vec<WindowEntry> = windows.into_iter()
+        .take(DISPLAY_LIMIT)
+        .map(|window| WindowEntry {
+            id: report.subject(&window.id, &window.start),
+            group: window.group.clone(),
+            start: report.game(&window.start),
+            end: report.game(&window.end),
+            count: window.count,
+        })
+        .collect();
+    report.write("windows", total, &windows)
+}
+
+
+fn find_game_milestones<T>(report: &Report, milestone_args: &MilestoneArgs, games: HashMap<Key, Vec<T>>) -> Result<(), Box<dyn Error>>
+    where T: Send + Sync + SearchKey + CelEval
+{
+    let mut exec = CelExec::default();
+    exec.set_career_mode(true);
+    exec.set_condition(&milestone_args.condition)?;
+    if let Some(limit) = milestone_args.limit {
+        exec.set_limit(limit);
+    }
+
+    for program in &milestone_args.groups {
+        exec.add_group(program)?;
+    }
+    exec.set_group_subject(milestone_args.group_subject);
+    let mut games = exec.regroup(games)?;
+
+    let sort_start = Instant::now();
+    games.par_iter_mut().for_each(|(_k, games)| games.sort_unstable_by_key(|g| g.order(true)));
+    let sort_end = Instant::now();
+    debug!(duration = format!("{:?}", sort_end.duration_since(sort_start)), "Sorted games");
+
+    let eval_start = Instant::now();
+    let milestones = exec.milestone_eval(&games);
+    let eval_end = Instant::now();
+    debug!(duration = format!("{:?}", eval_end.duration_since(eval_start)), "Found milestones");
+
+    display_milestones(report, &milestones)?;
+
+    Ok(())
+}
+
+
+fn display_milestones<T: SearchKey>(report: &Report, milestones: &[(&Key, &T)]) -> Result<(), Box<dyn Error>> {
+    let rows: Vec<MilestoneRow> = milestones.iter()
+        .map(|(key, game)| MilestoneRow {
+            subject: report.subject(game.subject_id(), game.id()),
+            group: key.group.clone(),
+            game: report.game(game.id()),
+            career_game: game.order(true),
+        })
+        .collect();
+    report.write("milestones", rows.len(), &rows)
+}
+
+
+fn find_aggregates<T>(report: &Report, aggregate_args: &AggregateArgs, sort_key: Option<&String>, games: HashMap<Key, Vec<T>>) -> Result<(), Box<dyn Error>>
+    where T: Send + Sync + SearchKey + CelEval + Matchup
+{
+    let mut exec = CelExec::default();
+    if let Some(program) = &aggregate_args.filter {
+        exec.set_filter(program)?;
+    }
+    for sum in &aggregate_args.sums {
+        exec.add_sum(sum)?;
+    }
+    if let Some(program) = &aggregate_args.having {
+        exec.set_condition(program)?;
+    }
+    // Without a sort key rank by the first total.
+    let first_sum = aggregate_args.sums.first().and_then(|sum| sum.split_once('=')).map(|(name, _)| name.trim().to_string());
+    if let Some(program) = sort_key.or(first_sum.as_ref()) {
+        exec.set_sort(program)?;
+    }
+    if let Some(limit) = aggregate_args.limit {
+        exec.set_limit(limit);
+    }
+    for program in &aggregate_args.groups {
+        exec.add_group(program)?;
+    }
+    exec.set_group_subject(aggregate_args.group_subject);
+
+    let mut games = exec.regroup(games)?;
+    let filter_start = Instant::now();
+    for games in games.values_mut() {
+        exec.filter(games);
+    }
+    let filter_end = Instant::now();
+    debug!(duration = format!("{:?}", filter_end.duration_since(filter_start)), "Filtered games");
+
+    // Grouped by the subject, the chosen group and the values of any group programs.
+    let mut groups: HashMap<(String, String, String), Vec<T>> = HashMap::new();
+    for (key, games) in games {
+        for game in games {
+            let group = match aggregate_args.by {
+                // The season is part of the game ID.
+                GroupBy::Season => game.id().get(3..7).unwrap_or_default().to_string(),
+                GroupBy::Career => String::new(),
+                GroupBy::Team => game.team().to_string(),
+                GroupBy::Opponent => game.opponent().to_string(),
+            };
+            groups.entry((key.id.clone(), group, key.group.clone())).or_default().push(game);
         }
     }
+
+    let eval_start = Instant::now();
+    let mut aggregates = exec.aggregate_eval(&groups);
+    exec.retain_condition(&mut aggregates);
+    exec.sort(&mut aggregates, &SortOrder::Desc);
+    let eval_end = Instant::now();
+    debug!(groups = groups.len(), duration = format!("{:?}", eval_end.duration_since(eval_start)), "Aggregated groups");
+
+    let total = aggregates.len();
+    aggregates.truncate(exec.limit());
+    display_aggregates(report, aggregate_args, sort_key.is_some().then_some(&exec), &aggregates, total)
+}
+
+
+fn display_aggregates(report: &Report, aggregate_args: &AggregateArgs, sort_exec: Option<&CelExec>, aggregates: &[Aggregate<(String, String, String)>], total: usize) -> Result<(), Box<dyn Error>> {
+    let by = aggregate_args.by;
+    let mut header = vec!["subject".to_string()];
+    match by {
+        GroupBy::Season => header.push("season".to_string()),
+        GroupBy::Career => {}
+        GroupBy::Team => header.push("team".to_string()),
+        GroupBy::Opponent => header.push("opponent".to_string()),
+    }
+    let group_column = !aggregate_args.groups.is_empty() && !aggregate_args.group_subject;
+    if group_column {
+        header.push("group".to_string());
+    }
+    header.push("games".to_string());
+    if let Some(aggregate) = aggregates.first() {
+        header.extend(aggregate.sums.iter().map(|(name, _)| name.to_string()));
+    }
+    if sort_exec.is_some() {
+        header.push("sort_key".to_string());
+    }
+
+    let rows: Vec<Vec<serde_json::Value>> = aggregates.iter()
+        .map(|aggregate| {
+            let (subject, group, values) = aggregate.key;
+            let mut row = vec![report.subject(subject, "").into()];
+            match by {
+                GroupBy::Season => row.push(group.parse::<u16>().map_or_else(|_| group.as_str().into(), Into::into)),
+                GroupBy::Career => {}
+                GroupBy::Team | GroupBy::Opponent => row.push(report.subject(group, "").into()),
+            }
+            if group_column {
+                row.push(values.as_str().into());
+            }
+            row.push(aggregate.games.into());
+            row.extend(aggregate.sums.iter().map(|(_, total)| total_value(*total)));
+            if let Some(exec) = sort_exec {
+                row.push(exec.sort_value(aggregate).map_or(serde_json::Value::Null, total_value));
+            }
+            row
+        })
+        .collect();
+    report.write_columns("groups", total, &header, &rows)
+}
+
+
+/// Whole totals print as integers.
+fn total_value(total: f64) -> serde_json::Value {
+    if total.fract() == 0.0 && total.abs() < 1e15 {
+        (total as i64).into()
+    }
+    else {
+        total.into()
+    }
 }
 
 
-fn check_program<T: CelEval>(source: &str) -> Result<bool, Box<dyn Error>> {
+/// Check the program only uses variables of the type being searched.
+fn check_program<T: CelEval>(source: &str) -> Result<(), Box<dyn Error>> {
     if !CelExec::check_program_variables::<T>(source)? {
-        eprintln!("Program condition requires unknown variables.");
-        // Fix to be Err?
-        return Ok(false);
+        return Err(format!("program uses unknown variables: {}", source).into());
     }
-    Ok(true)
+    Ok(())
 }
 
 
-fn find_player_game_log_streaks<T>(connection: &Connection, streak_args: &StreakArgs) -> Result<(), Box<dyn Error>>
-    where T: Send + Sync + CelEval + SearchKey + Sql
+fn search_player_game_logs<T>(connection: &Connection, report: &Report, search_args: &SearchArgs, sort_key: Option<&String>) -> Result<(), Box<dyn Error>>
+    where T: Send + Sync + CelEval + SearchKey + Sql + WithParkFactor + PlayerGamelog
 {
-    if !check_program::<T>(&streak_args.condition)? {
-        return Ok(());
+    if let Some(filter_program) = &search_args.filter {
+        check_program::<T>(filter_program)?;
     }
-    if let Some(count_program) = &streak_args.count && !check_program::<T>(count_program)? {
-        return Ok(());
+    if let Some(sort_program) = sort_key {
+        check_program::<T>(sort_program)?;
     }
 
-    let query_args = QueryArgs::from_streak(streak_args);
+    let query_args = QueryArgs::from_search(search_args, sort_key)?;
     let players: HashMap<_, Vec<T>> = load_player_games(connection, &query_args)?;
-    find_game_streaks(streak_args, players)?;
+    search_games(report, search_args, sort_key, players)?;
     Ok(())
 }
 
 
-fn find_team_game_streaks(connection: &Connection, streak_args: &StreakArgs) -> Result<(), Box<dyn Error>>
+fn search_events(connection: &Connection, report: &Report, search_args: &SearchArgs, sort_key: Option<&String>) -> Result<(), Box<dyn Error>> {
+    if let Some(filter_program) = &search_args.filter {
+        check_program::<PlateAppearance>(filter_program)?;
+    }
+    if let Some(sort_program) = sort_key {
+        check_program::<PlateAppearance>(sort_program)?;
+    }
+
+    let query_args = QueryArgs::from_search(search_args, sort_key)?;
+    let batters = load_plate_appearances(connection, &query_args)?;
+    search_games(report, search_args, sort_key, batters)?;
+    Ok(())
+}
+
+
+fn find_player_game_log_streaks<T>(connection: &Connection, report: &Report, streak_args: &StreakArgs) -> Result<(), Box<dyn Error>>
+    where T: Send + Sync + CelEval + SearchKey + Sql + WithParkFactor + PlayerGamelog
 {
-    if !check_program::<games::TeamGameLogSmall>(&streak_args.condition)? {
-        return Ok(());
+    check_program::<T>(&streak_args.condition)?;
+    if let Some(count_program) = &streak_args.count {
+        check_program::<T>(count_program)?;
     }
-    if let Some(count_program) = &streak_args.count && !check_program::<games::TeamGameLogSmall>(count_program)? {
-        return Ok(());
+    check_programs::<T>(&streak_args.groups)?;
+
+    let query_args = QueryArgs::from_streak(streak_args)?;
+    let mut players: HashMap<_, Vec<T>> = load_player_games(connection, &query_args)?;
+    if streak_args.team_schedule {
+        add_missed_games(connection, &query_args, &mut players)?;
     }
+    find_game_streaks(report, streak_args, players)?;
+    Ok(())
+}
 
-    let query_args = QueryArgs::from_streak(streak_args);
+
+fn find_team_game_streaks(connection: &Connection, report: &Report, streak_args: &StreakArgs) -> Result<(), Box<dyn Error>>
+{
+    check_program::<games::TeamGameLogSmall>(&streak_args.condition)?;
+    if let Some(count_program) = &streak_args.count {
+        check_program::<games::TeamGameLogSmall>(count_program)?;
+    }
+    check_programs::<games::TeamGameLogSmall>(&streak_args.groups)?;
+
+    if streak_args.team_schedule {
+        eprintln!("Team games already follow the team schedule");
+    }
+
+    let query_args = QueryArgs::from_streak(streak_args)?;
     let team_seasons: HashMap<_, Vec<games::TeamGameLogSmall>> = load_team_games(connection, &query_args)?;
-    find_game_streaks(streak_args, team_seasons)?;
+    find_game_streaks(report, streak_args, team_seasons)?;
     Ok(())
 }
 
 
-fn find_player_game_log_windows<T>(connection: &Connection, window_args: &WindowArgs) -> Result<(), Box<dyn Error>>
-    where T: Send + Sync + CelEval + SearchKey + Sql
-{
-    if !check_program::<T>(&window_args.count)? {
-        return Ok(());
+fn find_event_streaks(connection: &Connection, report: &Report, streak_args: &StreakArgs) -> Result<(), Box<dyn Error>> {
+    check_program::<PlateAppearance>(&streak_args.condition)?;
+    if let Some(count_program) = &streak_args.count {
+        check_program::<PlateAppearance>(count_program)?;
+    }
+    check_programs::<PlateAppearance>(&streak_args.groups)?;
+
+    if streak_args.team_schedule {
+        eprintln!("The team schedule only applies to player game logs, ignoring it");
     }
 
-    let query_args = QueryArgs::from_window(window_args);
+    let query_args = QueryArgs::from_streak(streak_args)?;
+    let subjects = load_plate_appearances(connection, &query_args)?;
+    find_game_streaks(report, streak_args, subjects)?;
+    Ok(())
+}
+
+
+fn find_player_game_log_windows<T>(connection: &Connection, report: &Report, window_args: &WindowArgs) -> Result<(), Box<dyn Error>>
+    where T: Send + Sync + CelEval + SearchKey + Sql + WithParkFactor + PlayerGamelog
+{
+    check_program::<T>(&window_args.count)?;
+    check_programs::<T>(&window_args.groups)?;
+
+    let query_args = QueryArgs::from_window(window_args)?;
     let players: HashMap<_, Vec<T>> = load_player_games(connection, &query_args)?;
-    find_game_windows(window_args, players)?;
+    find_game_windows(report, window_args, players)?;
     Ok(())
 }
 
 
-fn find_team_game_windows(connection: &Connection, window_args: &WindowArgs) -> Result<(), Box<dyn Error>>
+fn find_team_game_windows(connection: &Connection, report: &Report, window_args: &WindowArgs) -> Result<(), Box<dyn Error>>
 {
-    if !check_program::<games::TeamGameLogSmall>(&window_args.count)? {
-        return Ok(());
-    }
+    check_program::<games::TeamGameLogSmall>(&window_args.count)?;
+    check_programs::<games::TeamGameLogSmall>(&window_args.groups)?;
 
-    let query_args = QueryArgs::from_window(window_args);
+    let query_args = QueryArgs::from_window(window_args)?;
     let team_seasons: HashMap<_, Vec<games::TeamGameLogSmall>> = load_team_games(connection, &query_args)?;
-    find_game_windows(window_args, team_seasons)?;
+    find_game_windows(report, window_args, team_seasons)?;
+    Ok(())
+}
+
+
+fn find_event_windows(connection: &Connection, report: &Report, window_args: &WindowArgs) -> Result<(), Box<dyn Error>> {
+    check_program::<PlateAppearance>(&window_args.count)?;
+    check_programs::<PlateAppearance>(&window_args.groups)?;
+
+    let query_args = QueryArgs::from_window(window_args)?;
+    let subjects = load_plate_appearances(connection, &query_args)?;
+    find_game_windows(report, window_args, subjects)?;
+    Ok(())
+}
+
+
+fn find_player_game_log_milestones<T>(connection: &Connection, report: &Report, milestone_args: &MilestoneArgs) -> Result<(), Box<dyn Error>>
+    where T: Send + Sync + CelEval + SearchKey + Sql + WithParkFactor + PlayerGamelog
+{
+    check_program::<T>(&milestone_args.condition)?;
+    check_programs::<T>(&milestone_args.groups)?;
+
+    let query_args = QueryArgs::from_milestone(milestone_args)?;
+    let players: HashMap<_, Vec<T>> = load_player_games(connection, &query_args)?;
+    find_game_milestones(report, milestone_args, players)?;
+    Ok(())
+}
+
+
+fn check_programs<T: CelEval>(programs: &[String]) -> Result<(), Box<dyn Error>> {
+    for program in programs {
+        check_program::<T>(program)?;
+    }
     Ok(())
 }
 
 
+fn check_aggregate_programs<T: CelEval>(aggregate_args: &AggregateArgs) -> Result<(), Box<dyn Error>> {
+    if let Some(filter_program) = &aggregate_args.filter {
+        check_program::<T>(filter_program)?;
+    }
+    for sum in &aggregate_args.sums {
+        if let Some((_, program)) = sum.split_once('=') {
+            check_program::<T>(program)?;
+        }
+    }
+    check_programs::<T>(&aggregate_args.groups)
+}
+
+
+fn aggregate_player_game_logs<T>(connection: &Connection, report: &Report, aggregate_args: &AggregateArgs, sort_key: Option<&String>) -> Result<(), Box<dyn Error>>
+    where T: Send + Sync + CelEval + SearchKey + Sql + WithParkFactor + PlayerGamelog + Matchup
+{
+    check_aggregate_programs::<T>(aggregate_args)?;
+
+    let query_args = QueryArgs::from_aggregate(aggregate_args)?;
+    let players: HashMap<_, Vec<T>> = load_player_games(connection, &query_args)?;
+    find_aggregates(report, aggregate_args, sort_key, players)
+}
+
+
+fn aggregate_team_games(connection: &Connection, report: &Report, aggregate_args: &AggregateArgs, sort_key: Option<&String>) -> Result<(), Box<dyn Error>> {
+    check_aggregate_programs::<games::TeamGameLogSmall>(aggregate_args)?;
+
+    let query_args = QueryArgs::from_aggregate(aggregate_args)?;
+    let team_seasons = load_team_games(connection, &query_args)?;
+    find_aggregates(report, aggregate_args, sort_key, team_seasons)
+}
+
+
+fn aggregate_events(connection: &Connection, report: &Report, aggregate_args: &AggregateArgs, sort_key: Option<&String>) -> Result<(), Box<dyn Error>> {
+    check_aggregate_programs::<PlateAppearance>(aggregate_args)?;
+
+    let query_args = QueryArgs::from_aggregate(aggregate_args)?;
+    let subjects = load_plate_appearances(connection, &query_args)?;
+    find_aggregates(report, aggregate_args, sort_key, subjects)
+}
+
+
 fn run() -> Result<(), Box<dyn Error>> {
     let args = PlayIndexCelArgs::parse();
 
     let connection = Connection::open_with_flags(&args.database, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    database::check_schema_version(&connection)?;
+    search(&connection, &args)
+}
+
+
+fn search(connection: &Connection, args: &PlayIndexCelArgs) -> Result<(), Box<dyn Error>> {
+    let report = Report::load(connection, args.raw_ids, args.output.clone())?;
 
     match (&args.table, &args.mode) {
+        (SearchTable::BattingGameLogs, SearchCommand::Search(search_args)) => {
+            let sort_key = search_args.sort_key.as_ref().or(args.sort_key.as_ref());
+            search_player_game_logs::<player::BattingGamelog>(connection, &report, search_args, sort_key)?;
+        }
+        (SearchTable::FieldingGameLogs, SearchCommand::Search(search_args)) => {
+            let sort_key = search_args.sort_key.as_ref().or(args.sort_key.as_ref());
+            search_player_game_logs::<player::FieldingGamelog>(connection, &report, search_args, sort_key)?;
+        }
+        (SearchTable::PitchingGameLogs, SearchCommand::Search(search_args)) => {
+            let sort_key = search_args.sort_key.as_ref().or(args.sort_key.as_ref());
+            search_player_game_logs::<player::PitchingGamelog>(connection, &report, search_args, sort_key)?;
+        }
+        (SearchTable::Events, SearchCommand::Search(search_args)) => {
+            let sort_key = search_args.sort_key.as_ref().or(args.sort_key.as_ref());
+            search_events(connection, &report, search_args, sort_key)?;
+        }
+        (SearchTable::Events, SearchCommand::Streak(streak_args)) => {
+            find_event_streaks(connection, &report, streak_args)?;
+        }
+        (SearchTable::Events, SearchCommand::Window(window_args)) => {
+            find_event_windows(connection, &report, window_args)?;
+        }
         (SearchTable::BattingGameLogs, SearchCommand::Streak(streak_args)) => {
-            find_player_game_log_streaks::<player::BattingGamelog>(&connection, streak_args)?;
+            find_player_game_log_streaks::<player::BattingGamelog>(connection, &report, streak_args)?;
         }
         (SearchTable::FieldingGameLogs, SearchCommand::Streak(streak_args)) => {
-            find_player_game_log_streaks::<player::FieldingGamelog>(&connection, streak_args)?;
+            find_player_game_log_streaks::<player::FieldingGamelog>(connection, &report, streak_args)?;
         }
         (SearchTable::PitchingGameLogs, SearchCommand::Streak(streak_args)) => {
-            find_player_game_log_streaks::<player::PitchingGamelog>(&connection, streak_args)?;
+            find_player_game_log_streaks::<player::PitchingGamelog>(connection, &report, streak_args)?;
         }
         (SearchTable::TeamGames, SearchCommand::Streak(streak_args)) => {
-            find_team_game_streaks(&connection, streak_args)?;
+            find_team_game_streaks(connection, &report, streak_args)?;
         }
         (SearchTable::BattingGameLogs, SearchCommand::Window(window_args)) => {
-            find_player_game_log_windows::<player::BattingGamelog>(&connection, window_args)?;
+            find_player_game_log_windows::<player::BattingGamelog>(connection, &report, window_args)?;
         }
         (SearchTable::FieldingGameLogs, SearchCommand::Window(window_args)) => {
-            find_player_game_log_windows::<player::FieldingGamelog>(&connection, window_args)?;
+            find_player_game_log_windows::<player::FieldingGamelog>(connection, &report, window_args)?;
         }
         (SearchTable::PitchingGameLogs, SearchCommand::Window(window_args)) => {
-            find_player_game_log_windows::<player::PitchingGamelog>(&connection, window_args)?;
+            find_player_game_log_windows::<player::PitchingGamelog>(connection, &report, window_args)?;
         }
         (SearchTable::TeamGames, SearchCommand::Window(window_args)) => {
-            find_team_game_windows(&connection, window_args)?;
+            find_team_game_windows(connection, &report, window_args)?;
+        }
+        (SearchTable::BattingGameLogs, SearchCommand::Milestone(milestone_args)) => {
+            find_player_game_log_milestones::<player::BattingGamelog>(connection, &report, milestone_args)?;
+        }
+        (SearchTable::FieldingGameLogs, SearchCommand::Milestone(milestone_args)) => {
+            find_player_game_log_milestones::<player::FieldingGamelog>(connection, &report, milestone_args)?;
+        }
+        (SearchTable::PitchingGameLogs, SearchCommand::Milestone(milestone_args)) => {
+            find_player_game_log_milestones::<player::PitchingGamelog>(connection, &report, milestone_args)?;
+        }
+        (table, SearchCommand::Aggregate(aggregate_args)) => {
+            let sort_key = aggregate_args.sort_key.as_ref().or(args.sort_key.as_ref());
+            match table {
+                SearchTable::BattingGameLogs => {
+                    aggregate_player_game_logs::<player::BattingGamelog>(connection, &report, aggregate_args, sort_key)?;
+                }
+                SearchTable::FieldingGameLogs => {
+                    aggregate_player_game_logs::<player::FieldingGamelog>(connection, &report, aggregate_args, sort_key)?;
+                }
+                SearchTable::PitchingGameLogs => {
+                    aggregate_player_game_logs::<player::PitchingGamelog>(connection, &report, aggregate_args, sort_key)?;
+                }
+                SearchTable::TeamGames => {
+                    aggregate_team_games(connection, &report, aggregate_args, sort_key)?;
+                }
+                SearchTable::Events => {
+                    aggregate_events(connection, &report, aggregate_args, sort_key)?;
+                }
+            }
         }
-        _ => {
+        (SearchTable::TeamGames, SearchCommand::Search(_)) => {
+            return Err("team games can't be searched, only their streaks, windows and aggregates".into());
+        }
+        (SearchTable::TeamGames | SearchTable::Events, SearchCommand::Milestone(_)) => {
+            return Err("milestones are only found in player game logs".into());
         }
     }
 
@@ -511,3 +1573,148 @@ fn main() -> Result<(), Box<dyn Error>> {
     tracing_subscriber::fmt::init();
     run()
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use baseball::retrosheet::event::load_season_events;
+
+    /// The game in testdata/events/2020 loaded into the games and events tables.
+    fn events_database() -> Connection {
+        let season_dir = path::Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/events/2020");
+        let events = load_season_events(&season_dir).unwrap();
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut tx = conn.transaction().unwrap();
+        games::GameLog::create_table(&mut tx).unwrap();
+        tx.execute(
+            "INSERT INTO games (game_id, date, visitor_team, visitor_league, home_team, home_league)
+            VALUES ('TST202004010', '2020-04-01', 'AWY', 'AL', 'TST', 'AL')",
+            (),
+        ).unwrap();
+        Event::create_table(&mut tx).unwrap();
+        let placeholders: Vec<String> = Event::column_names().iter().map(|name| format!(":{}", name)).collect();
+        let insert_sql = format!("INSERT INTO events VALUES ({})", placeholders.join(", "));
+        let mut insert = tx.prepare(&insert_sql).unwrap();
+        for event in &events {
+            Event::from(event).write_row(&mut insert).unwrap();
+        }
+        drop(insert);
+        tx.commit().unwrap();
+        conn
+    }
+
+    /// Six games of TST at home against AWY in 2020, the first game number of the team schedules.
+    fn schedule_database() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut tx = conn.transaction().unwrap();
+        games::GameLog::create_table(&mut tx).unwrap();
+        for game in 1..=6 {
+            tx.execute(
+                "INSERT INTO games (game_id, date, visitor_team, visitor_league, visitor_team_game_number, visitor_manager_id,
+                    home_team, home_league, home_team_game_number, home_manager_id, park_id)
+                VALUES (?1, ?2, 'AWY', 'AL', ?3, 'awymgr', 'TST', 'AL', ?3, 'tstmgr', 'TST01')",
+                rusqlite::params![format!("TST2020040{}0", game), format!("2020-04-0{}", game), game],
+            ).unwrap();
+        }
+        tx.commit().unwrap();
+        conn
+    }
+
+    fn batting_gamelog(player_id: &str, team_id: &str, team_game: u16, hr: u8, career_hr: u32) -> player::BattingGamelog {
+        let mut gamelog = player::BattingGamelog::from(baseball::chadwick::gamelogs::BattingGamelog::default());
+        gamelog.player_id = player_id.to_string();
+        gamelog.game_id = format!("TST2020040{}0", team_game);
+        gamelog.team_id = team_id.to_string();
+        gamelog.career_game = team_game + 100;
+        gamelog.team_game = team_game;
+        gamelog.hr = hr;
+        gamelog.career_hr = career_hr;
+        gamelog
+    }
+
+    #[test]
+    fn team_schedule_misses() {
+        let conn = schedule_database();
+        let key = |id: &str| Key { id: id.to_string(), year: 2020, group: String::new() };
+        let mut players = HashMap::from([
+            (key("p1"), vec![batting_gamelog("p1", "TST", 4, 0, 11), batting_gamelog("p1", "TST", 2, 1, 11)]),
+            // Traded from AWY to TST after the second game.
+            (key("p2"), vec![batting_gamelog("p2", "AWY", 1, 0, 0), batting_gamelog("p2", "AWY", 2, 0, 0), batting_gamelog("p2", "TST", 5, 1, 1)]),
+        ]);
+        let cli = PlayIndexCelArgs::try_parse_from(["play_index_cel", "test.db", "batting-game-logs", "streak", "--team-schedule", "hr > 0 || missed"]).unwrap();
+        let SearchCommand::Streak(streak_args) = &cli.mode
+        else {
+            panic!("not a streak search");
+        };
+        let query_args = QueryArgs::from_streak(streak_args).unwrap();
+        add_missed_games(&conn, &query_args, &mut players).unwrap();
+
+        let games = |player: &str| -> Vec<(String, u16, bool)> {
+            players[&key(player)].iter().map(|game| (game.team_id.clone(), game.team_game, game.missed)).collect()
+        };
+        let team_games = |team: &str, games: &[(u16, bool)]| -> Vec<(String, u16, bool)> {
+            games.iter().map(|(team_game, missed)| (team.to_string(), *team_game, *missed)).collect()
+        };
+        // Missed before, between and after the games played.
+        assert_eq!(games("p1"), team_games("TST", &[(1, true), (2, false), (3, true), (4, false), (5, true), (6, true)]));
+        // Only missed before the first team and after the last.
+        let mut p2 = team_games("AWY", &[(1, false), (2, false)]);
+        p2.extend(team_games("TST", &[(5, false), (6, true)]));
+        assert_eq!(games("p2"), p2);
+
+        let p1 = &players[&key("p1")];
+        assert_eq!((p1[0].career_game, p1[0].career_hr), (101, 10));
+        assert_eq!((p1[2].career_game, p1[2].career_hr), (102, 11));
+        assert_eq!((p1[2].opponent.as_str(), p1[2].park_id.as_str(), p1[2].manager_id.as_str()), ("AWY", "TST01", "tstmgr"));
+        let p2 = &players[&key("p2")];
+        assert_eq!((p2[3].opponent.as_str(), p2[3].manager_id.as_str()), ("AWY", "tstmgr"));
+
+        // The condition sees the missed games.
+        let mut exec = CelExec::default();
+        exec.set_filter(&streak_args.condition).unwrap();
+        let mut p1 = players.remove(&key("p1")).unwrap();
+        exec.filter(&mut p1);
+        assert_eq!(p1.iter().map(|game| game.team_game).collect::<Vec<_>>(), [1, 2, 3, 5, 6]);
+    }
+
+    /// Run the command line against the database, returning the CSV written.
+    fn search_csv(conn: &Connection, name: &str, command: &[&str]) -> Result<String, Box<dyn Error>> {
+        let output = std::env::temp_dir().join(format!("play-index-cel-{}-{}.csv", name, std::process::id()));
+        let mut cli = vec!["play_index_cel", "--raw-ids", "--format", "csv", "--output", output.to_str().unwrap(), "test.db"];
+        cli.extend(command);
+        let result = search(conn, &PlayIndexCelArgs::try_parse_from(cli)?);
+        let written = fs::read_to_string(&output).unwrap_or_default();
+        let _ = fs::remove_file(&output);
+        result.map(|_| written)
+    }
+
+    #[test]
+    fn plate_appearance_search() {
+        let conn = events_database();
+        let hits = search_csv(&conn, "hits", &["events", "search", "--filter", "hit", "--sort-key", "event_id"]).unwrap();
+        assert_eq!(hits, "subject,game,sort_key\nh01,TST202004010-6,6.0\na01,TST202004010-1,1.0\n");
+
+        let strikeouts = search_csv(&conn, "strikeouts", &["events", "search", "--filter", "event_cd == 3"]).unwrap();
+        assert_eq!(strikeouts, "subject,game,sort_key\nh02,TST202004010-7,\n");
+    }
+
+    #[test]
+    fn unsupported_searches() {
+        let conn = events_database();
+        let searches = [
+            (&["team-games", "search", "--filter", "w"][..], "team games can't be searched"),
+            (&["team-games", "milestone", "w"], "milestones are only found"),
+            (&["events", "milestone", "hit"], "milestones are only found"),
+        ];
+        for (command, message) in searches {
+            let error = search_csv(&conn, "unsupported", command).unwrap_err();
+            assert!(error.to_string().starts_with(message), "{:?}: {}", command, error);
+        }
+        let error = search_csv(&conn, "unknown", &["events", "search", "--filter", "career_hr > 0"]).unwrap_err();
+        assert!(error.to_string().contains("unknown variables"), "{}", error);
+    }
+}
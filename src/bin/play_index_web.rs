@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::path;
+use std::process;
 use std::str;
 use std::sync;
 
@@ -11,6 +12,7 @@ use axum::{
     Json,
     Router,
 };
+use baseball_tools::database;
 use clap::Parser;
 use rusqlite::{Connection, Result, OpenFlags};
 use rusqlite::types::ValueRef;
@@ -49,6 +51,10 @@ async fn main() {
     let args = PlayIndexArgs::parse();
 
     let connection = Connection::open_with_flags(args.database, OpenFlags::SQLITE_OPEN_READ_ONLY).unwrap();
+    if let Err(err) = database::check_schema_version(&connection) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
 
     let appstate = AppState {
         conn: connection,
@@ -0,0 +1,43 @@
+use std::error::Error;
+use std::path;
+
+use baseball::chadwick::re24::RunExpectancy;
+use baseball_tools::database;
+use baseball_tools::internals::load_run_expectancy;
+
+use clap::Parser;
+use rusqlite::{Connection, OpenFlags};
+
+
+/// Print the run expectancy matrix over a range of seasons, adding up the totals build_db stored
+/// for each season.
+#[derive(Parser)]
+struct RunExpectancyArgs {
+    database: path::PathBuf,
+
+    /// First season of the range.
+    first: u16,
+
+    /// Last season of the range, the first season when not given.
+    last: Option<u16>,
+}
+
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = RunExpectancyArgs::parse();
+    let last = args.last.unwrap_or(args.first);
+
+    let connection = Connection::open_with_flags(&args.database, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    database::check_schema_version(&connection)?;
+    let re = load_run_expectancy(&connection, args.first, last)?;
+    if re == RunExpectancy::new() {
+        return Err(format!("No run expectancy stored for {}-{}", args.first, last).into());
+    }
+
+    println!("Runs scored");
+    print!("{}", re);
+    println!("Probability 1+ runs score");
+    print!("{}", re.scoring_chances());
+
+    Ok(())
+}
@@ -3,10 +3,10 @@ use std::error::Error;
 use std::path;
 
 use baseball::chadwick::gamelogs::{BattingGamelog, FieldingGamelog, PitchingGamelog};
+use baseball_tools::output::OutputArgs;
 use baseball_tools::search::{CelExec, SortOrder};
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
-use csv::Writer;
 use rayon::prelude::*;
 use serde::de::DeserializeOwned;
 
@@ -25,8 +25,8 @@ struct GamelogArgs {
     #[arg(long, value_name = "ORDER")]
     sort_order: Option<CliSortOrder>,
 
-    #[arg(long = "csv")]
-    csv_file: Option<path::PathBuf>,
+    #[command(flatten)]
+    output: OutputArgs,
 
     #[command(subcommand)]
     gamelog: GamelogType,
@@ -91,25 +91,9 @@ fn load_gamelog_file<T: DeserializeOwned>(file: &path::Path) -> Result<Vec<T>, B
 }
 
 
-fn dump_csv<T: serde::Serialize>(gamelog: &[T], csv_file: &path::Path) -> Result<(), Box<dyn Error>> {
-    let mut writer = csv::WriterBuilder::new()
-        .has_headers(true)
-        .from_path(csv_file)?;
-    for row in gamelog {
-        writer.serialize(row)?;
-    }
-    Ok(())
-}
-
-
-fn results<T: serde::Serialize>(games: &[T], limit: Option<usize>) -> Result<String, Box<dyn Error>> {
-    let mut wtr = Writer::from_writer(vec![]);
-    let limit = limit.unwrap_or(games.len());
-    for game in games.iter().take(limit) {
-        wtr.serialize(game)?;
-    }
-
-    Ok(String::from_utf8(wtr.into_inner()?)?)
+fn results<T: serde::Serialize + Default>(games: &[T], limit: Option<usize>, output: &OutputArgs) -> Result<(), Box<dyn Error>> {
+    let limit = limit.unwrap_or(games.len()).min(games.len());
+    output.write(&games[..limit])
 }
 
 
@@ -125,7 +109,7 @@ fn run() -> Result<(), Box<dyn Error>> {
 
     let sort_order = args.sort_order.unwrap_or(CliSortOrder::Asc).into();
 
-    let results = match args.gamelog {
+    match args.gamelog {
         GamelogType::Batting(files) => {
             let mut gamelogs = Vec::new();
             for file in &files.gamelogs {
@@ -134,10 +118,7 @@ fn run() -> Result<(), Box<dyn Error>> {
                 gamelogs.par_extend(gamelog);
             }
             search.sort(&mut gamelogs, &sort_order);
-            if let Some(csv_file) = args.csv_file {
-                dump_csv(&gamelogs, &csv_file)?;
-            }
-            results(&gamelogs, args.limit)?
+            results(&gamelogs, args.limit, &args.output)?;
         }
         GamelogType::Fielding(files) => {
             let mut gamelogs = Vec::new();
@@ -147,10 +128,7 @@ fn run() -> Result<(), Box<dyn Error>> {
                 gamelogs.par_extend(gamelog);
             }
             search.sort(&mut gamelogs, &sort_order);
-            if let Some(csv_file) = args.csv_file {
-                dump_csv(&gamelogs, &csv_file)?;
-            }
-            results(&gamelogs, args.limit)?
+            results(&gamelogs, args.limit, &args.output)?;
         }
         GamelogType::Pitching(files) => {
             let mut gamelogs = Vec::new();
@@ -160,14 +138,9 @@ fn run() -> Result<(), Box<dyn Error>> {
                 gamelogs.par_extend(gamelog);
             }
             search.sort(&mut gamelogs, &sort_order);
-            if let Some(csv_file) = args.csv_file {
-                dump_csv(&gamelogs, &csv_file)?;
-            }
-            results(&gamelogs, args.limit)?
+            results(&gamelogs, args.limit, &args.output)?;
         }
-    };
-
-    println!("{}", results);
+    }
 
     Ok(())
 }
@@ -1,6 +1,113 @@
 use std::error::Error;
+use std::fmt;
 
-use rusqlite::{Row, Statement, Transaction};
+use rusqlite::{Connection, OptionalExtension, Row, Statement, ToSql, Transaction};
+use rusqlite::types::ValueRef;
+
+pub use baseball_tools_derive::{Sql, SqlColumns};
+
+
+/// Version of the schema the code reads and writes. Bump it with a new migration whenever a table
+/// changes.
+pub const SCHEMA_VERSION: u32 = 7;
+
+/// Version of databases built before the schema was versioned.
+const UNVERSIONED_SCHEMA: u32 = 1;
+
+const GAMELOG_RE24_COLUMNS: &[(&str, &str)] = &[("re24", "REAL")];
+
+const GAMELOG_WPA_COLUMNS: &[(&str, &str)] = &[
+    ("wpa", "REAL"),
+    ("season_wpa", "REAL"),
+    ("li", "REAL"),
+    ("low_pa", "INTEGER"),
+    ("low_ab", "INTEGER"),
+    ("low_h", "INTEGER"),
+    ("low_hr", "INTEGER"),
+    ("low_bb", "INTEGER"),
+    ("low_so", "INTEGER"),
+    ("medium_pa", "INTEGER"),
+    ("medium_ab", "INTEGER"),
+    ("medium_h", "INTEGER"),
+    ("medium_hr", "INTEGER"),
+    ("medium_bb", "INTEGER"),
+    ("medium_so", "INTEGER"),
+    ("high_pa", "INTEGER"),
+    ("high_ab", "INTEGER"),
+    ("high_h", "INTEGER"),
+    ("high_hr", "INTEGER"),
+    ("high_bb", "INTEGER"),
+    ("high_so", "INTEGER"),
+];
+
+const GUTS_LEAGUE_COLUMNS: &[(&str, &str)] = &[
+    ("league_woba", "REAL"),
+    ("runs_per_pa", "REAL"),
+    ("runs_per_win", "REAL"),
+];
+
+const GUTS_AVERAGE_COLUMNS: &[(&str, &str)] = &[
+    ("league_obp", "REAL"),
+    ("league_slg", "REAL"),
+    ("league_era", "REAL"),
+];
+
+const BATTING_PLUS_COLUMNS: &[(&str, &str)] = &[("ops_plus", "REAL"), ("wrc_plus", "REAL")];
+
+const PITCHING_PLUS_COLUMNS: &[(&str, &str)] = &[("era_plus", "REAL"), ("fip_minus", "REAL")];
+
+const BATTING_CAREER_COLUMNS: &[(&str, &str)] = &[
+    ("career_pa", "INTEGER"),
+    ("career_ab", "INTEGER"),
+    ("career_r", "INTEGER"),
+    ("career_h", "INTEGER"),
+    ("career_d", "INTEGER"),
+    ("career_t", "INTEGER"),
+    ("career_hr", "INTEGER"),
+    ("career_rbi", "INTEGER"),
+    ("career_bb", "INTEGER"),
+    ("career_so", "INTEGER"),
+    ("career_sb", "INTEGER"),
+];
+
+const PITCHING_CAREER_COLUMNS: &[(&str, &str)] = &[
+    ("career_gs", "INTEGER"),
+    ("career_w", "INTEGER"),
+    ("career_l", "INTEGER"),
+    ("career_sv", "INTEGER"),
+    ("career_ipouts", "INTEGER"),
+    ("career_h", "INTEGER"),
+    ("career_er", "INTEGER"),
+    ("career_hr", "INTEGER"),
+    ("career_bb", "INTEGER"),
+    ("career_so", "INTEGER"),
+];
+
+/// Every migration in order. Each one brings a database up to its version.
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 2, table: "batting_gamelogs", change: Change::AddColumns(GAMELOG_RE24_COLUMNS), reload: &["gamelogs"] },
+    Migration { version: 2, table: "pitching_gamelogs", change: Change::AddColumns(GAMELOG_RE24_COLUMNS), reload: &["gamelogs"] },
+    Migration { version: 3, table: "batting_gamelogs", change: Change::AddColumns(GAMELOG_WPA_COLUMNS), reload: &["gamelogs"] },
+    Migration { version: 3, table: "pitching_gamelogs", change: Change::AddColumns(GAMELOG_WPA_COLUMNS), reload: &["gamelogs"] },
+    Migration { version: 4, table: "guts", change: Change::AddColumns(GUTS_LEAGUE_COLUMNS), reload: &["games"] },
+    Migration { version: 5, table: "guts", change: Change::AddColumns(GUTS_AVERAGE_COLUMNS), reload: &["games"] },
+    Migration { version: 5, table: "batting_gamelogs", change: Change::AddColumns(BATTING_PLUS_COLUMNS), reload: &["gamelogs"] },
+    Migration { version: 5, table: "pitching_gamelogs", change: Change::AddColumns(PITCHING_PLUS_COLUMNS), reload: &["gamelogs"] },
+    Migration { version: 6, table: "batting_gamelogs", change: Change::AddColumns(BATTING_CAREER_COLUMNS), reload: &["gamelogs"] },
+    Migration { version: 6, table: "pitching_gamelogs", change: Change::AddColumns(PITCHING_CAREER_COLUMNS), reload: &["gamelogs"] },
+    Migration { version: 7, table: "guts", change: Change::CreateTable(include_str!("sql/create_guts.sql")), reload: &["games"] },
+    Migration { version: 7, table: "teams", change: Change::CreateTable(include_str!("sql/create_teams.sql")), reload: &["games"] },
+    Migration { version: 7, table: "park_factors", change: Change::CreateTable(include_str!("sql/create_park_factors.sql")), reload: &["games"] },
+    Migration { version: 7, table: "run_expectancy", change: Change::CreateTable(include_str!("sql/create_run_expectancy.sql")), reload: &["gamelogs"] },
+    Migration {
+        version: 7,
+        table: "events",
+        change: Change::CreateTable(concat!(include_str!("sql/create_events.sql"), ";\n", include_str!("sql/create_events_indexes.sql"))),
+        reload: &["events"],
+    },
+    Migration { version: 7, table: "season_loads", change: Change::CreateTable(include_str!("sql/create_season_loads.sql")), reload: &[] },
+    Migration { version: 7, table: "career_game_updates", change: Change::CreateTable(include_str!("sql/create_career_game_updates.sql")), reload: &[] },
+];
 
 pub trait Sql where Self: Sized {
     fn create_table(tx: &mut Transaction) -> Result<(), Box<dyn Error>>;
@@ -11,5 +118,317 @@ pub trait Sql where Self: Sized {
 
     fn write_row(&self, statement: &mut Statement) -> Result<usize, rusqlite::Error>;
 
-    fn column_names<'a>() -> Vec<&'a str>;
+    fn column_names() -> Vec<String>;
+}
+
+
+/// A group of columns stored inline in another type's table, with the names prefixed.
+pub trait SqlColumns where Self: Sized {
+    const COLUMN_COUNT: usize;
+
+    fn column_names(prefix: &str) -> Vec<String>;
+
+    fn read_columns(row: &Row, offset: usize) -> Result<Self, rusqlite::Error>;
+
+    fn column_values(&self) -> Vec<&dyn ToSql>;
+}
+
+
+/// A change to one table, bringing the database up to `version`.
+struct Migration {
+    version: u32,
+    table: &'static str,
+    change: Change,
+    /// Sources of `season_loads` computing the changed table, which need to be loaded again.
+    reload: &'static [&'static str],
+}
+
+
+enum Change {
+    /// Columns appended in order, matching the table's create statement, so inserts listing every
+    /// column keep working. A missing table is skipped since a later migration or its loader
+    /// creates it with the current schema.
+    AddColumns(&'static [(&'static str, &'static str)]),
+    /// Statements creating the table, run when it doesn't exist yet.
+    CreateTable(&'static str),
+}
+
+
+#[derive(Debug)]
+pub enum SchemaError {
+    /// The database was built before the schema was versioned.
+    Unversioned,
+    /// The database needs to be upgraded with build_db.
+    Outdated(u32),
+    /// The database was built by a newer version of the tools.
+    Unknown(u32),
+}
+
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Unversioned => {
+                write!(f, "database has no schema version, run build_db to upgrade it to version {}", SCHEMA_VERSION)
+            }
+            SchemaError::Outdated(version) => {
+                write!(f, "database schema version {} is older than version {}, run build_db to upgrade it", version, SCHEMA_VERSION)
+            }
+            SchemaError::Unknown(version) => {
+                write!(f, "database schema version {} is newer than version {}, the latest these tools understand", version, SCHEMA_VERSION)
+            }
+        }
+    }
+}
+
+
+impl Error for SchemaError {}
+
+
+/// The schema version stored in the database, or None when it doesn't have one.
+pub fn schema_version(conn: &Connection) -> Result<Option<u32>, rusqlite::Error> {
+    if !conn.table_exists(Some("main"), "schema_version")? {
+        return Ok(None);
+    }
+    conn.query_row("SELECT version FROM schema_version", (), |row| row.get(0)).optional()
+}
+
+
+/// Check the database is at the schema version the code understands, for the tools that only read
+/// it.
+pub fn check_schema_version(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    match schema_version(conn)? {
+        Some(SCHEMA_VERSION) => Ok(()),
+        Some(version) if version < SCHEMA_VERSION => Err(Box::new(SchemaError::Outdated(version))),
+        Some(version) => Err(Box::new(SchemaError::Unknown(version))),
+        None => Err(Box::new(SchemaError::Unversioned)),
+    }
+}
+
+
+/// Bring the database up to the current schema version, returning the version it started at. A new
+/// database runs every migration, creating its tables, and starts at the current version since
+/// nothing in it needs upgrading. Databases from newer versions of the tools are refused.
+pub fn migrate(conn: &mut Connection) -> Result<u32, Box<dyn Error>> {
+    let (version, applied) = match schema_version(conn)? {
+        Some(version) => (version, version),
+        None => {
+            let tables: u32 = conn.query_row("SELECT count(*) FROM sqlite_master WHERE type = 'table'", (), |row| row.get(0))?;
+            if tables > 0 { (UNVERSIONED_SCHEMA, UNVERSIONED_SCHEMA) } else { (SCHEMA_VERSION, 0) }
+        }
+    };
+    if version > SCHEMA_VERSION {
+        return Err(Box::new(SchemaError::Unknown(version)));
+    }
+
+    let tx = conn.transaction()?;
+    for migration in MIGRATIONS.iter().filter(|migration| migration.version > applied) {
+        let table_exists = tx.table_exists(Some("main"), migration.table)?;
+        match migration.change {
+            Change::AddColumns(columns) => {
+                if !table_exists {
+                    continue;
+                }
+                let mut statement = tx.prepare("SELECT name FROM pragma_table_info(?1)")?;
+                let existing: Vec<String> = statement.query_map([migration.table], |row| row.get(0))?
+                    .collect::<Result<_, _>>()?;
+                for (column, column_type) in columns {
+                    if !existing.iter().any(|name| name == column) {
+                        tx.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", migration.table, column, column_type), ())?;
+                    }
+                }
+            }
+            Change::CreateTable(create_sql) => {
+                if table_exists {
+                    continue;
+                }
+                tx.execute_batch(create_sql)?;
+            }
+        }
+        if tx.table_exists(Some("main"), "season_loads")? {
+            for source in migration.reload {
+                tx.execute("DELETE FROM season_loads WHERE source = ?1", [source])?;
+            }
+        }
+    }
+    if !tx.table_exists(Some("main"), "schema_version")? {
+        tx.execute(include_str!("sql/create_schema_version.sql"), ())?;
+    }
+    tx.execute("DELETE FROM schema_version", ())?;
+    tx.execute("INSERT INTO schema_version VALUES (?1)", [SCHEMA_VERSION])?;
+    tx.commit()?;
+
+    Ok(version)
+}
+
+
+// Use a different method to map sqlite values to f32 because rusqlite won't map SQL NULLs to f32
+// NAN.
+pub fn map_sql_real_to_f32(value: ValueRef) -> f32 {
+    match value {
+        ValueRef::Null => {
+            f32::NAN
+        }
+        ValueRef::Integer(int) => {
+            int as f32
+        }
+        ValueRef::Real(real) => {
+            real as f32
+        }
+        ValueRef::Text(_) => {
+            f32::NAN
+        }
+        ValueRef::Blob(_) => {
+            // This shouldn't happen based on the expected data.
+            f32::NAN
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::events::Event;
+    use crate::games::{GameLog, Team};
+    use crate::internals::Guts;
+    use crate::parks::ParkFactor;
+    use crate::player::{BattingGamelog, FieldingGamelog, PitchingGamelog};
+
+    use baseball::chadwick::gamelogs;
+
+    fn columns(tx: &mut Transaction, table: &str) -> Vec<String> {
+        let mut statement = tx.prepare("SELECT name FROM pragma_table_info(?1)").unwrap();
+        statement.query_map([table], |row| row.get(0)).unwrap().map(Result::unwrap).collect()
+    }
+
+    fn table_columns<T: Sql>() -> Vec<String> {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut tx = conn.transaction().unwrap();
+        T::create_table(&mut tx).unwrap();
+        columns(&mut tx, T::table_name())
+    }
+
+    #[test]
+    fn column_names_match_tables() {
+        assert_eq!(GameLog::column_names(), table_columns::<GameLog>());
+        assert_eq!(BattingGamelog::column_names(), table_columns::<BattingGamelog>());
+        assert_eq!(FieldingGamelog::column_names(), table_columns::<FieldingGamelog>());
+        assert_eq!(PitchingGamelog::column_names(), table_columns::<PitchingGamelog>());
+        assert_eq!(Event::column_names(), table_columns::<Event>());
+        assert_eq!(Guts::column_names(), table_columns::<Guts>());
+        assert_eq!(ParkFactor::column_names(), table_columns::<ParkFactor>());
+        assert_eq!(Team::column_names(), table_columns::<Team>());
+    }
+
+    #[test]
+    fn flattened_round_trip() {
+        let mut gamelog = BattingGamelog::from(gamelogs::BattingGamelog::default());
+        gamelog.player_id = "bat01".to_string();
+        gamelog.avg = 0.5;
+        gamelog.high_leverage.pa = 2;
+        gamelog.high_leverage.hr = 1;
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut tx = conn.transaction().unwrap();
+        BattingGamelog::create_table(&mut tx).unwrap();
+        let placeholders: Vec<String> = BattingGamelog::column_names().iter().map(|name| format!(":{}", name)).collect();
+        let insert_sql = format!("INSERT INTO batting_gamelogs VALUES ({})", placeholders.join(", "));
+        gamelog.write_row(&mut tx.prepare(&insert_sql).unwrap()).unwrap();
+
+        let (bavg, high_hr): (f32, u8) = tx.query_one("SELECT bavg, high_hr FROM batting_gamelogs", (), |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        assert_eq!((bavg, high_hr), (0.5, 1));
+        let read = tx.query_one("SELECT * FROM batting_gamelogs", (), |row| BattingGamelog::read_row(row, 0)).unwrap();
+        assert_eq!(read.player_id, "bat01");
+        assert_eq!(read.avg, 0.5);
+        assert!(read.wpa.is_nan());
+        assert_eq!((read.high_leverage.pa, read.high_leverage.hr, read.medium_leverage.pa), (2, 1, 0));
+    }
+
+    #[test]
+    fn migrate_unversioned() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut tx = conn.transaction().unwrap();
+        BattingGamelog::create_table(&mut tx).unwrap();
+        PitchingGamelog::create_table(&mut tx).unwrap();
+        // Drop the newer columns to get back to the unversioned schema.
+        let tables = [
+            ("batting_gamelogs", BATTING_PLUS_COLUMNS, BATTING_CAREER_COLUMNS),
+            ("pitching_gamelogs", PITCHING_PLUS_COLUMNS, PITCHING_CAREER_COLUMNS),
+        ];
+        for (table, plus_columns, career_columns) in tables {
+            for (column, _) in GAMELOG_RE24_COLUMNS.iter().chain(GAMELOG_WPA_COLUMNS).chain(plus_columns).chain(career_columns) {
+                tx.execute(&format!("ALTER TABLE {} DROP COLUMN {}", table, column), ()).unwrap();
+            }
+        }
+        tx.execute(include_str!("sql/create_season_loads.sql"), ()).unwrap();
+        for source in ["games", "gamelogs"] {
+            tx.execute("INSERT INTO season_loads VALUES (2020, ?1, 'hash', '', 'now')", [source]).unwrap();
+        }
+        tx.commit().unwrap();
+        assert!(matches!(check_schema_version(&conn).unwrap_err().downcast_ref(), Some(SchemaError::Unversioned)));
+
+        assert_eq!(migrate(&mut conn).unwrap(), UNVERSIONED_SCHEMA);
+        assert_eq!(schema_version(&conn).unwrap(), Some(SCHEMA_VERSION));
+        check_schema_version(&conn).unwrap();
+        // The missing guts, teams and park_factors tables are created and their games reloaded too.
+        assert_eq!(conn.query_one("SELECT count(*) FROM season_loads", (), |row| row.get::<_, u32>(0)).unwrap(), 0);
+        let mut tx = conn.transaction().unwrap();
+        assert_eq!(BattingGamelog::column_names(), columns(&mut tx, "batting_gamelogs"));
+        assert_eq!(PitchingGamelog::column_names(), columns(&mut tx, "pitching_gamelogs"));
+        assert_eq!(Guts::column_names(), columns(&mut tx, "guts"));
+    }
+
+    #[test]
+    fn migrate_creates_tables() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(migrate(&mut conn).unwrap(), SCHEMA_VERSION);
+        let mut tx = conn.transaction().unwrap();
+        assert_eq!(Event::column_names(), columns(&mut tx, "events"));
+        assert_eq!(Guts::column_names(), columns(&mut tx, "guts"));
+        assert_eq!(ParkFactor::column_names(), columns(&mut tx, "park_factors"));
+        assert_eq!(Team::column_names(), columns(&mut tx, "teams"));
+        for table in ["run_expectancy", "season_loads", "career_game_updates"] {
+            assert!(tx.table_exists(Some("main"), table).unwrap(), "{} missing", table);
+        }
+        let indexes: u32 = tx.query_one("SELECT count(*) FROM sqlite_master WHERE type = 'index' AND tbl_name = 'events' AND sql IS NOT NULL", (), |row| row.get(0)).unwrap();
+        assert_eq!(indexes, 3);
+    }
+
+    #[test]
+    fn migrate_reloads_seasons() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut tx = conn.transaction().unwrap();
+        Guts::create_table(&mut tx).unwrap();
+        Event::create_table(&mut tx).unwrap();
+        tx.execute(include_str!("sql/create_season_loads.sql"), ()).unwrap();
+        tx.execute(include_str!("sql/create_schema_version.sql"), ()).unwrap();
+        tx.execute("INSERT INTO schema_version VALUES (4)", ()).unwrap();
+        for source in ["games", "events"] {
+            tx.execute("INSERT INTO season_loads VALUES (2020, ?1, 'hash', '', 'now')", [source]).unwrap();
+        }
+        for (column, _) in GUTS_AVERAGE_COLUMNS {
+            tx.execute(&format!("ALTER TABLE guts DROP COLUMN {}", column), ()).unwrap();
+        }
+        tx.commit().unwrap();
+
+        assert_eq!(migrate(&mut conn).unwrap(), 4);
+        let sources: Vec<String> = conn.prepare("SELECT source FROM season_loads").unwrap()
+            .query_map((), |row| row.get(0)).unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(sources, ["events"]);
+    }
+
+    #[test]
+    fn migrate_versions() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(migrate(&mut conn).unwrap(), SCHEMA_VERSION);
+        assert_eq!(migrate(&mut conn).unwrap(), SCHEMA_VERSION);
+
+        conn.execute("UPDATE schema_version SET version = ?1", [SCHEMA_VERSION + 1]).unwrap();
+        assert!(migrate(&mut conn).is_err());
+        assert!(matches!(check_schema_version(&conn).unwrap_err().downcast_ref(), Some(SchemaError::Unknown(_))));
+    }
 }
@@ -0,0 +1,311 @@
+use std::error::Error;
+
+use baseball::chadwick::events::{ExtendedEvent, FullEvent};
+
+use crate::database::Sql;
+use crate::parks::WithParkFactor;
+use crate::search::{CelEval, Matchup, SearchKey};
+
+use cel::Context;
+
+
+const EVENT_WALK: u8 = 14;
+const EVENT_INTENTIONAL_WALK: u8 = 15;
+const EVENT_HIT_BY_PITCH: u8 = 16;
+const DEST_OUT: u8 = 0;
+
+
+/// One play-by-play event with the columns of `FullEvent` and `ExtendedEvent` most useful for
+/// searching. Runner IDs are NULL when the base is empty.
+#[derive(Clone, Debug, Default, PartialEq, Sql, CelEval)]
+#[sql(table = "events", create = "sql/create_events.sql")]
+#[cel(method(hit = is_hit, on_base = is_on_base, retired = is_retired))]
+pub struct Event {
+    #[cel(skip)]
+    pub game_id: String,
+    pub event_id: u16,
+    pub inn_ct: u8,
+    pub bat_home_id: u8,
+    pub outs_ct: u8,
+    pub balls_ct: u8,
+    pub strikes_ct: u8,
+    pub pitch_seq_tx: String,
+    pub away_score_ct: u8,
+    pub home_score_ct: u8,
+    pub bat_id: String,
+    pub bat_hand_cd: String,
+    pub resp_bat_id: String,
+    pub pit_id: String,
+    pub pit_hand_cd: String,
+    pub resp_pit_id: String,
+    pub bat_team_id: String,
+    pub fld_team_id: String,
+    pub base1_run_id: Option<String>,
+    pub base2_run_id: Option<String>,
+    pub base3_run_id: Option<String>,
+    pub start_bases_cd: u8,
+    pub end_bases_cd: u8,
+    pub event_tx: String,
+    pub leadoff_fl: bool,
+    pub ph_fl: bool,
+    pub bat_fld_cd: u8,
+    pub bat_lineup_id: u8,
+    pub event_cd: u8,
+    pub bat_event_fl: bool,
+    pub ab_fl: bool,
+    pub h_cd: u8,
+    pub sh_fl: bool,
+    pub sf_fl: bool,
+    pub event_outs_ct: u8,
+    pub dp_fl: bool,
+    pub tp_fl: bool,
+    pub rbi_ct: u8,
+    pub wp_fl: bool,
+    pub pb_fl: bool,
+    pub fld_cd: u8,
+    pub battedball_cd: String,
+    pub bunt_fl: bool,
+    pub foul_fl: bool,
+    pub battedball_loc_tx: String,
+    pub err_ct: u8,
+    pub bat_dest_id: u8,
+    pub run1_dest_id: u8,
+    pub run2_dest_id: u8,
+    pub run3_dest_id: u8,
+    pub run1_sb_fl: bool,
+    pub run2_sb_fl: bool,
+    pub run3_sb_fl: bool,
+    pub run1_cs_fl: bool,
+    pub run2_cs_fl: bool,
+    pub run3_cs_fl: bool,
+    pub event_runs_ct: u8,
+    pub pa_new_fl: bool,
+}
+
+
+/// A plate appearance searched as part of either the batter's or the pitcher's sequence of plate
+/// appearances.
+pub struct PlateAppearance {
+    pub event: Event,
+    /// Searching by the pitcher instead of the batter.
+    pub pitcher: bool,
+    /// Game and event ID, like "NYA202404010-37".
+    pub id: String,
+    /// Plate appearance number for the batter or pitcher, in the season or career.
+    pub order: u16,
+    /// Run factor of the park the game was played in.
+    pub park_factor: f32,
+}
+
+
+fn runner(runner_id: &str) -> Option<String> {
+    if runner_id.is_empty() {
+        None
+    }
+    else {
+        Some(runner_id.to_string())
+    }
+}
+
+
+impl Event {
+    pub fn is_hit(&self) -> bool {
+        self.h_cd > 0
+    }
+
+    /// Reached base by a hit, walk or hit by pitch, as counted for on-base percentage.
+    pub fn is_on_base(&self) -> bool {
+        self.is_hit() || matches!(self.event_cd, EVENT_WALK | EVENT_INTENTIONAL_WALK | EVENT_HIT_BY_PITCH)
+    }
+
+    /// The batter was put out.
+    pub fn is_retired(&self) -> bool {
+        self.bat_event_fl && self.bat_dest_id == DEST_OUT
+    }
+}
+
+
+impl PlateAppearance {
+    pub fn new(event: Event, pitcher: bool) -> Self {
+        let id = format!("{}-{}", event.game_id, event.event_id);
+        Self {
+            event,
+            pitcher,
+            id,
+            order: 0,
+            park_factor: f32::NAN,
+        }
+    }
+}
+
+
+impl CelEval for PlateAppearance {
+    fn add_cel_variables(&self, context: &mut Context, variables: &[&str]) -> Result<(), Box<dyn Error>> {
+        if variables.contains(&"park_factor") {
+            context.add_variable("park_factor", self.park_factor)?;
+        }
+        self.event.add_cel_variables(context, variables)
+    }
+
+    fn check_cel_variables(variables: &[&str]) -> bool {
+        let event_variables: Vec<&str> = variables.iter().copied().filter(|name| *name != "park_factor").collect();
+        Event::check_cel_variables(&event_variables)
+    }
+}
+
+
+impl WithParkFactor for PlateAppearance {
+    fn park_game_id(&self) -> &str { &self.event.game_id }
+
+    fn set_park_factor(&mut self, factor: f32) { self.park_factor = factor; }
+}
+
+
+impl Matchup for PlateAppearance {
+    fn team(&self) -> &str {
+        if self.pitcher {
+            &self.event.fld_team_id
+        }
+        else {
+            &self.event.bat_team_id
+        }
+    }
+
+    fn opponent(&self) -> &str {
+        if self.pitcher {
+            &self.event.bat_team_id
+        }
+        else {
+            &self.event.fld_team_id
+        }
+    }
+}
+
+
+impl SearchKey for PlateAppearance {
+    fn id(&self) -> &str { &self.id }
+
+    fn subject_id(&self) -> &str {
+        if self.pitcher {
+            &self.event.resp_pit_id
+        }
+        else {
+            &self.event.resp_bat_id
+        }
+    }
+
+    /// Plate appearances are loaded separately for seasons and careers so the order is the same.
+    fn order(&self, _career: bool) -> u16 { self.order }
+}
+
+
+impl From<&(FullEvent, ExtendedEvent)> for Event {
+    fn from(event: &(FullEvent, ExtendedEvent)) -> Self {
+        let (full, extended) = event;
+        Self {
+            game_id: full.game_id.clone(),
+            event_id: full.event_id.parse().unwrap_or_default(),
+            inn_ct: full.inn_ct,
+            bat_home_id: full.bat_home_id.parse().unwrap_or_default(),
+            outs_ct: full.outs_ct,
+            balls_ct: full.balls_ct,
+            strikes_ct: full.strikes_ct,
+            pitch_seq_tx: full.pitch_seq_tx.clone(),
+            away_score_ct: full.away_score_ct,
+            home_score_ct: full.home_score_ct,
+            bat_id: full.bat_id.clone(),
+            bat_hand_cd: full.bat_hand_cd.code().to_string(),
+            resp_bat_id: full.resp_bat_id.clone(),
+            pit_id: full.pit_id.clone(),
+            pit_hand_cd: full.pit_hand_cd.code().to_string(),
+            resp_pit_id: full.resp_pit_id.clone(),
+            bat_team_id: extended.bat_team_id.clone(),
+            fld_team_id: extended.fld_team_id.clone(),
+            base1_run_id: runner(&full.base1_run_id),
+            base2_run_id: runner(&full.base2_run_id),
+            base3_run_id: runner(&full.base3_run_id),
+            start_bases_cd: extended.start_bases_cd.parse().unwrap_or_default(),
+            end_bases_cd: extended.end_bases_cd.parse().unwrap_or_default(),
+            event_tx: full.event_tx.clone(),
+            leadoff_fl: full.leadoff_fl,
+            ph_fl: full.ph_fl,
+            bat_fld_cd: full.bat_fld_cd,
+            bat_lineup_id: full.bat_lineup_id,
+            event_cd: full.event_cd,
+            bat_event_fl: full.bat_event_fl,
+            ab_fl: full.ab_fl,
+            h_cd: full.h_cd,
+            sh_fl: full.sh_fl,
+            sf_fl: full.sf_fl,
+            event_outs_ct: full.event_outs_ct,
+            dp_fl: full.dp_fl,
+            tp_fl: full.tp_fl,
+            rbi_ct: full.rbi_ct,
+            wp_fl: full.wp_fl,
+            pb_fl: full.pb_fl,
+            fld_cd: full.fld_cd,
+            battedball_cd: full.battedball_cd.clone(),
+            bunt_fl: full.bunt_fl,
+            foul_fl: full.foul_fl,
+            battedball_loc_tx: full.battedball_loc_tx.clone(),
+            err_ct: full.err_ct,
+            bat_dest_id: full.bat_dest_id.parse().unwrap_or_default(),
+            run1_dest_id: full.run1_dest_id.parse().unwrap_or_default(),
+            run2_dest_id: full.run2_dest_id.parse().unwrap_or_default(),
+            run3_dest_id: full.run3_dest_id.parse().unwrap_or_default(),
+            run1_sb_fl: full.run1_sb_fl,
+            run2_sb_fl: full.run2_sb_fl,
+            run3_sb_fl: full.run3_sb_fl,
+            run1_cs_fl: full.run1_cs_fl,
+            run2_cs_fl: full.run2_cs_fl,
+            run3_cs_fl: full.run3_cs_fl,
+            event_runs_ct: extended.event_runs_ct,
+            pa_new_fl: extended.pa_new_fl,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rusqlite::Connection;
+
+    #[test]
+    fn round_trip() {
+        let full = FullEvent {
+            game_id: "TST202404010".to_string(),
+            event_id: "7".to_string(),
+            bat_home_id: "1".to_string(),
+            bat_id: "bat01".to_string(),
+            pit_id: "pit01".to_string(),
+            base2_run_id: "run01".to_string(),
+            bat_dest_id: "1".to_string(),
+            run2_dest_id: "4".to_string(),
+            event_cd: 20,
+            h_cd: 1,
+            ..Default::default()
+        };
+        let extended = ExtendedEvent {
+            start_bases_cd: "2".to_string(),
+            end_bases_cd: "1".to_string(),
+            event_runs_ct: 1,
+            ..Default::default()
+        };
+        let event = Event::from(&(full, extended));
+        assert_eq!(event.event_id, 7);
+        assert_eq!(event.base1_run_id, None);
+        assert_eq!(event.base2_run_id.as_deref(), Some("run01"));
+        assert_eq!(event.bat_hand_cd, "?");
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        let mut tx = conn.transaction().unwrap();
+        Event::create_table(&mut tx).unwrap();
+        let placeholders: Vec<String> = Event::column_names().iter().map(|name| format!(":{}", name)).collect();
+        let insert_sql = format!("INSERT INTO events VALUES ({})", placeholders.join(", "));
+        event.write_row(&mut tx.prepare(&insert_sql).unwrap()).unwrap();
+        let read = tx.query_one("SELECT * FROM events", (), |row| Event::read_row(row, 0)).unwrap();
+        assert_eq!(read, event);
+    }
+}
@@ -1,31 +1,431 @@
 use std::default::Default;
+use std::error::Error;
 
+use baseball::chadwick::events::FullEvent;
+use baseball::chadwick::re24::{RunExpectancy, StateTotals};
+use baseball::chadwick::state;
+use rusqlite::Connection;
 
+use crate::database::{Sql, SqlColumns};
+use crate::games::{GameLog, RetrosheetOption};
+
+
+/// Season constants for the league, computed from a season of games and events.
+#[derive(Sql)]
+#[sql(table = "guts", create = "sql/create_guts.sql")]
 pub struct Guts {
     pub season: u16,
+    #[sql(flatten = "woba_")]
     pub woba: WobaWeights,
     pub fip_constant: f32,
+    pub league_woba: f32,
+    pub runs_per_pa: f32,
+    pub runs_per_win: f32,
+    pub league_obp: f32,
+    pub league_slg: f32,
+    pub league_era: f32,
 }
 
 
-#[derive(Default)]
+#[derive(Default, SqlColumns)]
 pub struct WobaWeights {
     pub scale: f32,
     pub bb_weight: f32,
     pub hbp_weight: f32,
+    #[sql(column = "1b_weight")]
     pub s_weight: f32,
+    #[sql(column = "2b_weight")]
     pub d_weight: f32,
+    #[sql(column = "3b_weight")]
     pub t_weight: f32,
     pub hr_weight: f32,
 }
 
 
+/// League totals of the games in a season. Games missing any of the basic batting or pitching
+/// totals are skipped while stats not tracked in older seasons count as zero.
+#[derive(Default)]
+pub struct LeagueTotals {
+    pub games: u32,
+    pub r: u32,
+    pub ipouts: u32,
+    pub er: u32,
+    pub ab: u32,
+    pub h: u32,
+    pub d: u32,
+    pub t: u32,
+    pub hr: u32,
+    pub bb: u32,
+    pub ibb: u32,
+    pub hbp: u32,
+    pub so: u32,
+    pub sh: u32,
+    pub sf: u32,
+}
+
+
+/// Average run value of each kind of plate appearance over a season.
+#[derive(Default)]
+struct RunValues {
+    bb: RunValue,
+    hbp: RunValue,
+    s: RunValue,
+    d: RunValue,
+    t: RunValue,
+    hr: RunValue,
+    out: RunValue,
+}
+
+
+#[derive(Default)]
+struct RunValue {
+    total: f64,
+    times: u32,
+}
+
+
 impl Guts {
     pub fn new(season: u16) -> Self {
         Self {
             season,
             woba: WobaWeights::default(),
             fip_constant: 0.0,
+            league_woba: f32::NAN,
+            runs_per_pa: f32::NAN,
+            runs_per_win: f32::NAN,
+            league_obp: f32::NAN,
+            league_slg: f32::NAN,
+            league_era: f32::NAN,
+        }
+    }
+
+    /// Compute the constants from the season's totals and its events, which must be in game order.
+    /// Without events the wOBA weights are NaN.
+    pub fn from_season(season: u16, totals: &LeagueTotals, events: &[FullEvent]) -> Self {
+        let runs = totals.r as f32;
+        let innings = totals.ipouts as f32 / 3.0;
+        let (unscaled_fip, era) = if innings > 0.0 {
+            let fip = (13.0 * totals.hr as f32 + 3.0 * (totals.bb + totals.hbp) as f32 - 2.0 * totals.so as f32) / innings;
+            (fip, totals.er as f32 * 9.0 / innings)
+        }
+        else {
+            (f32::NAN, f32::NAN)
+        };
+        let runs_per_win = 9.0 * (runs / innings) * 1.5 + 3.0;
+
+        let woba = RunValues::from_events(events).woba_weights(totals);
+        Self {
+            season,
+            fip_constant: era - unscaled_fip,
+            league_woba: totals.obp(),
+            runs_per_pa: runs / totals.pa() as f32,
+            runs_per_win,
+            league_obp: totals.obp(),
+            league_slg: totals.slg(),
+            league_era: era,
+            woba,
+        }
+    }
+
+    // Stats relative to the league, where 100 is average. Each takes the run factor of the parks
+    // the stats came from.
+
+    pub fn ops_plus(&self, obp: f32, slg: f32, park_factor: f32) -> f32 {
+        100.0 * (obp / self.league_obp + slg / self.league_slg - 1.0) / park_factor
+    }
+
+    pub fn wrc_plus(&self, woba: f32, park_factor: f32) -> f32 {
+        let runs_per_pa = self.runs_per_pa;
+        let wraa_per_pa = (woba - self.league_woba) / self.woba.scale;
+        100.0 * (wraa_per_pa + runs_per_pa + (runs_per_pa - park_factor * runs_per_pa)) / runs_per_pa
+    }
+
+    pub fn era_plus(&self, era: f32, park_factor: f32) -> f32 {
+        100.0 * self.league_era * park_factor / era
+    }
+
+    /// League FIP is the league ERA since the FIP constant scales one to the other. Lower is better.
+    pub fn fip_minus(&self, fip: f32, park_factor: f32) -> f32 {
+        100.0 * (fip + (fip - fip * park_factor)) / self.league_era
+    }
+}
+
+
+/// Add up the run expectancy totals stored for each season from `first` through `last`, giving the
+/// matrix for the whole range. Seasons without stored totals add nothing.
+pub fn load_run_expectancy(conn: &Connection, first: u16, last: u16) -> Result<RunExpectancy, Box<dyn Error>> {
+    let mut re = RunExpectancy::new();
+    if !conn.table_exists(Some("main"), "run_expectancy")? {
+        return Ok(re);
+    }
+
+    let mut statement = conn.prepare(
+        "SELECT bases_cd, outs, SUM(runs), SUM(times), SUM(scoreless)
+        FROM run_expectancy
+        WHERE season >= :first AND season <= :last
+        GROUP BY bases_cd, outs"
+    )?;
+    let mut rows = statement.query(rusqlite::named_params! {":first": first, ":last": last})?;
+    while let Some(row) = rows.next()? {
+        let totals = StateTotals {
+            runs: row.get(2)?,
+            times: row.get(3)?,
+            scoreless: row.get(4)?,
+        };
+        re.add_totals(row.get(0)?, row.get(1)?, &totals);
+    }
+    Ok(re)
+}
+
+
+impl WobaWeights {
+    /// wOBA of a line of batting stats, where `bb` includes intentional walks.
+    #[allow(clippy::too_many_arguments)]
+    pub fn woba(&self, ab: u32, h: u32, d: u32, t: u32, hr: u32, bb: u32, ibb: u32, hbp: u32, sf: u32) -> f32 {
+        let ubb = bb.saturating_sub(ibb);
+        let singles = h.saturating_sub(d + t + hr);
+        let denominator = (ab + ubb + sf + hbp) as f32;
+        if denominator > 0.0 {
+            (self.bb_weight * ubb as f32
+                + self.hbp_weight * hbp as f32
+                + self.s_weight * singles as f32
+                + self.d_weight * d as f32
+                + self.t_weight * t as f32
+                + self.hr_weight * hr as f32) / denominator
+        }
+        else {
+            f32::NAN
+        }
+    }
+}
+
+
+impl LeagueTotals {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_game(&mut self, game: &GameLog) {
+        fn known(stat: &RetrosheetOption) -> Option<u32> {
+            match stat {
+                RetrosheetOption::Some(v) => Some((*v).into()),
+                _ => None,
+            }
+        }
+        fn or_zero(stat: &RetrosheetOption) -> u32 {
+            known(stat).unwrap_or_default()
+        }
+
+        let (Some(ipouts), Some(v_ab), Some(h_ab), Some(v_h), Some(h_h)) = (game.number_of_outs, game.visitor_ab, game.home_ab, game.visitor_hits, game.home_hits)
+        else {
+            return;
+        };
+        let (Some(v_d), Some(h_d), Some(v_t), Some(h_t), Some(v_hr), Some(h_hr)) = (
+            known(&game.visitor_doubles), known(&game.home_doubles),
+            known(&game.visitor_triples), known(&game.home_triples),
+            known(&game.visitor_homeruns), known(&game.home_homeruns),
+        )
+        else {
+            return;
+        };
+        let (Some(v_bb), Some(h_bb), Some(v_so), Some(h_so), Some(v_er), Some(h_er)) = (
+            known(&game.visitor_walks), known(&game.home_walks),
+            known(&game.visitor_strikeouts), known(&game.home_strikeouts),
+            known(&game.visitor_individual_earned_runs), known(&game.home_individual_earned_runs),
+        )
+        else {
+            return;
+        };
+
+        self.games += 1;
+        self.r += u32::from(game.visitor_score) + u32::from(game.home_score);
+        self.ipouts += u32::from(ipouts);
+        self.er += v_er + h_er;
+        self.ab += u32::from(v_ab) + u32::from(h_ab);
+        self.h += u32::from(v_h) + u32::from(h_h);
+        self.d += v_d + h_d;
+        self.t += v_t + h_t;
+        self.hr += v_hr + h_hr;
+        self.bb += v_bb + h_bb;
+        self.so += v_so + h_so;
+        self.ibb += or_zero(&game.visitor_intentional_walks) + or_zero(&game.home_intentional_walks);
+        self.hbp += or_zero(&game.visitor_hbp) + or_zero(&game.home_hbp);
+        self.sf += or_zero(&game.visitor_sac_flies) + or_zero(&game.home_sac_flies);
+        self.sh += u32::from(game.visitor_sac_hits.unwrap_or_default()) + u32::from(game.home_sac_hits.unwrap_or_default());
+    }
+
+    pub fn pa(&self) -> u32 {
+        self.ab + self.bb + self.hbp + self.sf + self.sh
+    }
+
+    pub fn obp(&self) -> f32 {
+        let denominator = (self.ab + self.bb + self.hbp + self.sf) as f32;
+        (self.h + self.bb + self.hbp) as f32 / denominator
+    }
+
+    pub fn slg(&self) -> f32 {
+        let tb = self.h + self.d + 2 * self.t + 3 * self.hr;
+        tb as f32 / self.ab as f32
+    }
+}
+
+
+impl RunValues {
+    /// Average the RE24 of the plate appearances in events, using the season's own run expectancy.
+    fn from_events(events: &[FullEvent]) -> Self {
+        let re = RunExpectancy::from_events(events);
+        let mut values = Self::default();
+        for (change, event) in state::replay(events).iter().zip(events.iter()) {
+            if !event.bat_event_fl {
+                continue;
+            }
+            let value = match event.event_cd {
+                // Generic out, strikeout and fielder's choice.
+                2 | 3 | 19 => &mut values.out,
+                // Intentional walks (15) are left out of wOBA.
+                14 => &mut values.bb,
+                16 => &mut values.hbp,
+                20 => &mut values.s,
+                21 => &mut values.d,
+                22 => &mut values.t,
+                23 => &mut values.hr,
+                _ => continue,
+            };
+            value.total += re.event_value(change);
+            value.times += 1;
+        }
+        values
+    }
+
+    /// wOBA weights are the run values above an out, scaled so league wOBA matches league OBP.
+    fn woba_weights(&self, totals: &LeagueTotals) -> WobaWeights {
+        let out = self.out.average();
+        let bb = self.bb.average() - out;
+        let hbp = self.hbp.average() - out;
+        let s = self.s.average() - out;
+        let d = self.d.average() - out;
+        let t = self.t.average() - out;
+        let hr = self.hr.average() - out;
+
+        let singles = totals.h.saturating_sub(totals.d + totals.t + totals.hr);
+        let unscaled = (bb * (totals.bb - totals.ibb) as f64
+            + hbp * totals.hbp as f64
+            + s * singles as f64
+            + d * totals.d as f64
+            + t * totals.t as f64
+            + hr * totals.hr as f64) / (totals.ab + totals.bb - totals.ibb + totals.sf + totals.hbp) as f64;
+        let scale = totals.obp() as f64 / unscaled;
+
+        WobaWeights {
+            scale: scale as f32,
+            bb_weight: (bb * scale) as f32,
+            hbp_weight: (hbp * scale) as f32,
+            s_weight: (s * scale) as f32,
+            d_weight: (d * scale) as f32,
+            t_weight: (t * scale) as f32,
+            hr_weight: (hr * scale) as f32,
+        }
+    }
+}
+
+
+impl RunValue {
+    fn average(&self) -> f64 {
+        if self.times > 0 {
+            self.total / self.times as f64
+        }
+        else {
+            f64::NAN
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals() -> LeagueTotals {
+        LeagueTotals {
+            games: 2,
+            r: 9,
+            ipouts: 108,
+            er: 8,
+            ab: 66,
+            h: 17,
+            d: 3,
+            t: 1,
+            hr: 2,
+            bb: 7,
+            ibb: 1,
+            hbp: 1,
+            so: 16,
+            sh: 1,
+            sf: 1,
+        }
+    }
+
+    #[test]
+    fn season_constants() {
+        let guts = Guts::from_season(2020, &totals(), &[]);
+        // ERA of 2.0 minus the unscaled FIP of (26 + 24 - 32) / 36.
+        assert!((guts.fip_constant - 1.5).abs() < 1e-5);
+        assert!((guts.runs_per_pa - 9.0 / 76.0).abs() < 1e-6);
+        assert!((guts.runs_per_win - 6.375).abs() < 1e-5);
+        assert!((guts.league_woba - 25.0 / 75.0).abs() < 1e-6);
+        assert!((guts.league_slg - 28.0 / 66.0).abs() < 1e-6);
+        assert!((guts.league_era - 2.0).abs() < 1e-6);
+        assert!(guts.woba.scale.is_nan());
+    }
+
+    #[test]
+    fn relative_stats() {
+        let mut guts = Guts::from_season(2020, &totals(), &[]);
+        guts.league_woba = 0.320;
+        guts.woba.scale = 1.2;
+        let (obp, slg) = (guts.league_obp, guts.league_slg);
+        assert!((guts.ops_plus(obp, slg, 1.0) - 100.0).abs() < 1e-3);
+        assert!((guts.ops_plus(obp * 1.1, slg * 1.1, 1.0) - 120.0).abs() < 1e-3);
+        assert!((guts.wrc_plus(0.320, 1.0) - 100.0).abs() < 1e-3);
+        // A hitter's park takes away from the same line.
+        assert!(guts.wrc_plus(0.320, 1.1) < 100.0);
+        assert!((guts.era_plus(1.0, 1.0) - 200.0).abs() < 1e-3);
+        assert!((guts.era_plus(2.0, 1.1) - 110.0).abs() < 1e-3);
+        assert!((guts.fip_minus(2.0, 1.0) - 100.0).abs() < 1e-3);
+        assert!((guts.fip_minus(2.0, 1.1) - 90.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn scaled_woba_matches_obp() {
+        let mut values = RunValues::default();
+        for (value, total) in [
+            (&mut values.out, -0.27), (&mut values.bb, 0.31), (&mut values.hbp, 0.33),
+            (&mut values.s, 0.47), (&mut values.d, 0.77), (&mut values.t, 1.04), (&mut values.hr, 1.4),
+        ] {
+            value.total = total;
+            value.times = 1;
+        }
+        let totals = totals();
+        let weights = values.woba_weights(&totals);
+        assert!((weights.s_weight / weights.bb_weight - 0.74 / 0.58).abs() < 1e-5);
+        let woba = weights.woba(totals.ab, totals.h, totals.d, totals.t, totals.hr, totals.bb, totals.ibb, totals.hbp, totals.sf);
+        assert!((woba - totals.obp()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn run_expectancy_range() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(load_run_expectancy(&conn, 2019, 2020).unwrap(), RunExpectancy::new());
+        conn.execute(include_str!("sql/create_run_expectancy.sql"), ()).unwrap();
+        for (season, runs, times, scoreless) in [(2018, 5, 10, 8), (2019, 6, 10, 7), (2020, 2, 4, 3)] {
+            conn.execute(
+                "INSERT INTO run_expectancy VALUES (?1, 0, 0, ?2, ?3, ?4, ?2 * 1.0 / ?3)",
+                (season, runs, times, scoreless),
+            ).unwrap();
         }
+        let re = load_run_expectancy(&conn, 2019, 2020).unwrap();
+        assert_eq!(re.totals(0, 0), Some(&StateTotals { runs: 8, times: 14, scoreless: 10 }));
+        assert_eq!(re.totals(7, 2), Some(&StateTotals::default()));
+        assert!((re.expected_runs(0, 0) - 8.0 / 14.0).abs() < 1e-9);
     }
 }
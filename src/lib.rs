@@ -1,7 +1,10 @@
 
 pub mod database;
+pub mod events;
 pub mod gamelogs;
 pub mod games;
 pub mod internals;
+pub mod output;
+pub mod parks;
 pub mod player;
 pub mod search;
@@ -0,0 +1,294 @@
+//! Writing search results as JSON, NDJSON, CSV, Markdown tables or aligned text.
+//!
+//! Records are any serializable type with flat fields. The tables for Markdown and text are made
+//! by writing the records as CSV first, which keeps the fields in the order of the type. The
+//! default record gives the header when there are no records.
+
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path;
+
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use serde::ser::SerializeMap;
+
+
+/// The header and rows of cells of a table.
+type Table = (Vec<String>, Vec<Vec<String>>);
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Columns aligned for reading in a terminal.
+    #[default]
+    Text,
+    /// One JSON array of every record.
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+    Csv,
+    /// A Markdown table.
+    Markdown,
+}
+
+
+/// The options every tool takes to choose how and where results are written.
+#[derive(Args, Clone, Debug)]
+pub struct OutputArgs {
+    #[arg(long, value_enum, default_value_t)]
+    pub format: Format,
+
+    /// Write the results to the file instead of standard output.
+    #[arg(short = 'o', long, value_name = "FILE")]
+    pub output: Option<path::PathBuf>,
+}
+
+
+impl OutputArgs {
+    /// Write the records in the chosen format to the chosen file or standard output.
+    pub fn write<T: Serialize + Default>(&self, records: &[T]) -> Result<(), Box<dyn Error>> {
+        match &self.output {
+            Some(output) => write_records(io::BufWriter::new(fs::File::create(output)?), self.format, records),
+            None => write_records(io::stdout().lock(), self.format, records),
+        }
+    }
+
+    /// Write records with columns only known at run time.
+    pub fn write_columns(&self, header: &[String], rows: &[Vec<serde_json::Value>]) -> Result<(), Box<dyn Error>> {
+        match &self.output {
+            Some(output) => write_columns(io::BufWriter::new(fs::File::create(output)?), self.format, header, rows),
+            None => write_columns(io::stdout().lock(), self.format, header, rows),
+        }
+    }
+
+    /// Summary lines, like the number of results, only go with the text format.
+    pub fn is_text(&self) -> bool {
+        self.format == Format::Text
+    }
+}
+
+
+pub fn write_records<T: Serialize + Default, W: Write>(mut writer: W, format: Format, records: &[T]) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut writer, records)?;
+            writeln!(writer)?;
+        }
+        Format::Ndjson => {
+            for record in records {
+                serde_json::to_writer(&mut writer, record)?;
+                writeln!(writer)?;
+            }
+        }
+        Format::Csv | Format::Markdown | Format::Text => {
+            let (header, rows) = table(records)?;
+            write_table(&mut writer, format, &header, &rows)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+
+/// Write records with columns only known at run time, like the totals of an aggregate search.
+pub fn write_columns<W: Write>(mut writer: W, format: Format, header: &[String], rows: &[Vec<serde_json::Value>]) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Json | Format::Ndjson => {
+            let records: Vec<Columns> = rows.iter().map(|row| Columns { header, row }).collect();
+            write_records(writer, format, &records)
+        }
+        Format::Csv | Format::Markdown | Format::Text => {
+            let rows: Vec<Vec<String>> = rows.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|value| match value {
+                            serde_json::Value::Null => String::new(),
+                            serde_json::Value::String(value) => value.clone(),
+                            value => value.to_string(),
+                        })
+                        .collect()
+                })
+                .collect();
+            write_table(&mut writer, format, header, &rows)?;
+            writer.flush()?;
+            Ok(())
+        }
+    }
+}
+
+
+/// One row of `write_columns`, written as an object with the columns in order.
+#[derive(Default)]
+struct Columns<'a> {
+    header: &'a [String],
+    row: &'a [serde_json::Value],
+}
+
+
+impl Serialize for Columns<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.header.len()))?;
+        for (name, value) in self.header.iter().zip(self.row) {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+
+/// Write the table as CSV, Markdown or aligned text. Without rows only the header is written.
+fn write_table<W: Write>(writer: &mut W, format: Format, header: &[String], rows: &[Vec<String>]) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(writer);
+            csv_writer.write_record(header)?;
+            for row in rows {
+                csv_writer.write_record(row)?;
+            }
+            csv_writer.flush()?;
+        }
+        Format::Markdown => {
+            let numeric = numeric_columns(header.len(), rows);
+            writeln!(writer, "| {} |", header.join(" | "))?;
+            let rule: Vec<&str> = numeric.iter().map(|n| if *n { "---:" } else { "---" }).collect();
+            writeln!(writer, "| {} |", rule.join(" | "))?;
+            for row in rows {
+                let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                writeln!(writer, "| {} |", cells.join(" | "))?;
+            }
+        }
+        // Text is the only other format written as a table.
+        _ => {
+            let numeric = numeric_columns(header.len(), rows);
+            let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+            for row in rows {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+            for row in std::iter::once(header).chain(rows.iter().map(Vec::as_slice)) {
+                let cells: Vec<String> = row.iter().enumerate()
+                    .map(|(idx, cell)| {
+                        if numeric[idx] {
+                            format!("{:>width$}", cell, width = widths[idx])
+                        }
+                        else {
+                            format!("{:<width$}", cell, width = widths[idx])
+                        }
+                    })
+                    .collect();
+                writeln!(writer, "{}", cells.join("  ").trim_end())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+
+/// The header and rows of the records, through CSV.
+fn table<T: Serialize + Default>(records: &[T]) -> Result<Table, Box<dyn Error>> {
+    let mut csv_writer = csv::Writer::from_writer(Vec::new());
+    if records.is_empty() {
+        csv_writer.serialize(T::default())?;
+    }
+    for record in records {
+        csv_writer.serialize(record)?;
+    }
+    let csv_data = csv_writer.into_inner()?;
+    let mut reader = csv::Reader::from_reader(csv_data.as_slice());
+    let header = reader.headers()?.iter().map(String::from).collect();
+    if records.is_empty() {
+        return Ok((header, Vec::new()));
+    }
+    let mut rows = Vec::with_capacity(records.len());
+    for row in reader.records() {
+        rows.push(row?.iter().map(String::from).collect());
+    }
+    Ok((header, rows))
+}
+
+
+/// Columns where every value is a number, or empty, get aligned to the right. Without rows nothing
+/// is known to be a number.
+fn numeric_columns(columns: usize, rows: &[Vec<String>]) -> Vec<bool> {
+    (0..columns)
+        .map(|idx| {
+            !rows.is_empty() && rows.iter().all(|row| row.get(idx).is_none_or(|cell| cell.is_empty() || cell.parse::<f64>().is_ok()))
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default, Serialize)]
+    struct Row {
+        subject: String,
+        count: u32,
+    }
+
+    fn rows() -> Vec<Row> {
+        vec![
+            Row { subject: "Babe Ruth".to_string(), count: 60 },
+            Row { subject: "Roger Maris".to_string(), count: 61 },
+        ]
+    }
+
+    fn written(format: Format, records: &[Row]) -> String {
+        let mut output = Vec::new();
+        write_records(&mut output, format, records).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn formats() {
+        let rows = rows();
+        assert_eq!(written(Format::Csv, &rows), "subject,count\nBabe Ruth,60\nRoger Maris,61\n");
+        assert_eq!(
+            written(Format::Ndjson, &rows),
+            "{\"subject\":\"Babe Ruth\",\"count\":60}\n{\"subject\":\"Roger Maris\",\"count\":61}\n"
+        );
+        assert_eq!(
+            written(Format::Markdown, &rows),
+            "| subject | count |\n| --- | ---: |\n| Babe Ruth | 60 |\n| Roger Maris | 61 |\n"
+        );
+        assert_eq!(written(Format::Text, &rows), "subject      count\nBabe Ruth       60\nRoger Maris     61\n");
+        let json: serde_json::Value = serde_json::from_str(&written(Format::Json, &rows)).unwrap();
+        assert_eq!(json[1]["count"], 61);
+    }
+
+    #[test]
+    fn columns() {
+        let header = vec!["subject".to_string(), "hr".to_string()];
+        let rows = vec![vec![serde_json::json!("Babe Ruth"), serde_json::json!(714)]];
+        let mut output = Vec::new();
+        write_columns(&mut output, Format::Ndjson, &header, &rows).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "{\"subject\":\"Babe Ruth\",\"hr\":714}\n");
+        let mut output = Vec::new();
+        write_columns(&mut output, Format::Csv, &header, &rows).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "subject,hr\nBabe Ruth,714\n");
+    }
+
+    #[test]
+    fn no_records() {
+        assert_eq!(written(Format::Csv, &[]), "subject,count\n");
+        assert_eq!(written(Format::Text, &[]), "subject  count\n");
+        assert_eq!(written(Format::Markdown, &[]), "| subject | count |\n| --- | --- |\n");
+        assert_eq!(written(Format::Json, &[]), "[]\n");
+        assert_eq!(written(Format::Ndjson, &[]), "");
+    }
+
+    #[test]
+    fn no_columns_rows() {
+        let header = vec!["subject".to_string(), "hr".to_string()];
+        let mut output = Vec::new();
+        write_columns(&mut output, Format::Csv, &header, &[]).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "subject,hr\n");
+        let mut output = Vec::new();
+        write_columns(&mut output, Format::Markdown, &header, &[]).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "| subject | hr |\n| --- | --- |\n");
+    }
+}
@@ -0,0 +1,257 @@
+//! Park factors from the runs, hits and home runs scored in each park against the road games of
+//! the teams that played there.
+//!
+//! A factor compares the rate per game in a park, counting both teams, to the rate in the road
+//! games of its home teams over the same seasons. Each season uses itself and the previous seasons
+//! in the park, and the factor is regressed toward neutral based on the number of games.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use rusqlite::Connection;
+
+use crate::database::Sql;
+
+
+/// Number of seasons, ending with the season itself, used for a park factor.
+pub const PARK_FACTOR_SEASONS: u16 = 3;
+
+/// Games of regression toward a neutral park, about a season of home games.
+pub const REGRESSION_GAMES: f32 = 81.0;
+
+
+/// Park factors for a park in a season. 1.0 is neutral and NaN is unknown.
+#[derive(Debug, Sql)]
+#[sql(table = "park_factors", create = "sql/create_park_factors.sql")]
+pub struct ParkFactor {
+    pub season: u16,
+    pub park_id: String,
+    /// Games in the park over the seasons used.
+    pub games: u32,
+    pub runs: f32,
+    pub hits: f32,
+    pub hr: f32,
+}
+
+
+/// Totals of both teams in one game, the input to park factors.
+pub struct ParkGame {
+    pub season: u16,
+    pub park_id: String,
+    pub home_team: String,
+    pub visitor_team: String,
+    pub runs: u32,
+    /// Hits and home runs aren't known for every game.
+    pub hits: Option<u32>,
+    pub hr: Option<u32>,
+}
+
+
+/// Searched games that can be given the run factor of the park they were played in.
+pub trait WithParkFactor {
+    fn park_game_id(&self) -> &str;
+
+    fn set_park_factor(&mut self, factor: f32);
+}
+
+
+/// The run factor of every game, for setting `park_factor` on searched games.
+pub struct ParkFactors {
+    games: HashMap<String, f32>,
+}
+
+
+#[derive(Clone, Copy, Default)]
+struct Stat {
+    games: u32,
+    total: u32,
+}
+
+
+#[derive(Clone, Copy, Default)]
+struct GameTotals {
+    runs: Stat,
+    hits: Stat,
+    hr: Stat,
+}
+
+
+impl ParkFactor {
+    /// Compute the factors of every park in every season found in the games.
+    pub fn from_games(games: &[ParkGame]) -> Vec<ParkFactor> {
+        let mut home: HashMap<(&str, u16, &str), GameTotals> = HashMap::new();
+        let mut road: HashMap<(u16, &str), GameTotals> = HashMap::new();
+        for game in games {
+            home.entry((&game.park_id, game.season, &game.home_team)).or_default().add(game);
+            road.entry((game.season, &game.visitor_team)).or_default().add(game);
+        }
+
+        let mut park_seasons: Vec<(&str, u16)> = home.keys().map(|(park, season, _)| (*park, *season)).collect();
+        park_seasons.sort_unstable();
+        park_seasons.dedup();
+
+        park_seasons.into_iter()
+            .map(|(park_id, season)| {
+                let first_season = season.saturating_sub(PARK_FACTOR_SEASONS - 1);
+                // Pair the park's games for each home team and season with that team's road games.
+                let window: Vec<(&GameTotals, &GameTotals)> = home.iter()
+                    .filter(|((park, home_season, _), _)| *park == park_id && (first_season..=season).contains(home_season))
+                    .filter_map(|((_, home_season, team), totals)| {
+                        road.get(&(*home_season, *team)).map(|road_totals| (totals, road_totals))
+                    })
+                    .collect();
+                ParkFactor {
+                    season,
+                    park_id: park_id.to_string(),
+                    games: window.iter().map(|(home, _)| home.runs.games).sum(),
+                    runs: factor(&window, |totals| totals.runs),
+                    hits: factor(&window, |totals| totals.hits),
+                    hr: factor(&window, |totals| totals.hr),
+                }
+            })
+            .collect()
+    }
+}
+
+
+impl ParkFactors {
+    /// Load the run factors of the games in the seasons. Without any park factors in the
+    /// database every game's factor is unknown.
+    pub fn load(conn: &Connection, year_start: Option<i32>, year_end: Option<i32>) -> Result<Self, Box<dyn Error>> {
+        let mut games = HashMap::new();
+        if !conn.table_exists(Some("main"), ParkFactor::table_name())? {
+            return Ok(Self { games });
+        }
+
+        let mut statement = conn.prepare(
+            "SELECT games.game_id, pf.runs
+            FROM games JOIN park_factors pf
+                ON pf.park_id = games.park_id AND pf.season = CAST(strftime('%Y', games.date) AS INTEGER)
+            WHERE (:start IS NULL OR pf.season >= :start) AND (:end IS NULL OR pf.season <= :end)"
+        )?;
+        let mut rows = statement.query(rusqlite::named_params! {":start": year_start, ":end": year_end})?;
+        while let Some(row) = rows.next()? {
+            games.insert(row.get(0)?, crate::database::map_sql_real_to_f32(row.get_ref(1)?));
+        }
+        Ok(Self { games })
+    }
+
+    pub fn get(&self, game_id: &str) -> f32 {
+        self.games.get(game_id).copied().unwrap_or(f32::NAN)
+    }
+
+    pub fn apply<T: WithParkFactor>(&self, games: &mut [T]) {
+        for game in games {
+            let factor = self.get(game.park_game_id());
+            game.set_park_factor(factor);
+        }
+    }
+}
+
+
+impl Stat {
+    fn add(&mut self, value: Option<u32>) {
+        if let Some(value) = value {
+            self.games += 1;
+            self.total += value;
+        }
+    }
+
+    fn rate(&self) -> Option<f32> {
+        if self.games > 0 {
+            Some(self.total as f32 / self.games as f32)
+        }
+        else {
+            None
+        }
+    }
+}
+
+
+impl GameTotals {
+    fn add(&mut self, game: &ParkGame) {
+        self.runs.add(Some(game.runs));
+        self.hits.add(game.hits);
+        self.hr.add(game.hr);
+    }
+}
+
+
+/// The park's total over the total expected from the road rates of its home teams, regressed
+/// toward 1.0 by the number of games.
+fn factor<F>(window: &[(&GameTotals, &GameTotals)], stat: F) -> f32
+    where F: Fn(&GameTotals) -> Stat
+{
+    let mut games = 0;
+    let mut observed = 0.0;
+    let mut expected = 0.0;
+    for (home, road) in window {
+        let home = stat(home);
+        if let Some(road_rate) = stat(road).rate() {
+            games += home.games;
+            observed += home.total as f32;
+            expected += home.games as f32 * road_rate;
+        }
+    }
+    if expected <= 0.0 {
+        return f32::NAN;
+    }
+
+    let raw = observed / expected;
+    let games = games as f32;
+    1.0 + (raw - 1.0) * games / (games + REGRESSION_GAMES)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(season: u16, park_id: &str, home_team: &str, visitor_team: &str, runs: u32, hr: Option<u32>) -> ParkGame {
+        ParkGame {
+            season,
+            park_id: park_id.to_string(),
+            home_team: home_team.to_string(),
+            visitor_team: visitor_team.to_string(),
+            runs,
+            hits: None,
+            hr,
+        }
+    }
+
+    #[test]
+    fn regressed_factors() {
+        let mut games = Vec::new();
+        for _ in 0..81 {
+            // Ten runs a game at home and eight on the road.
+            games.push(game(2020, "PRK01", "AAA", "BBB", 10, Some(2)));
+            games.push(game(2020, "PRK02", "BBB", "AAA", 8, None));
+        }
+        let factors = ParkFactor::from_games(&games);
+        assert_eq!(factors.len(), 2);
+
+        let park = &factors[0];
+        assert_eq!((park.park_id.as_str(), park.season, park.games), ("PRK01", 2020, 81));
+        // A raw factor of 1.25 regressed halfway after 81 games.
+        assert!((park.runs - 1.125).abs() < 1e-6);
+        assert!(park.hits.is_nan());
+        // No road home runs are known to compare against.
+        assert!(park.hr.is_nan());
+        assert!((factors[1].runs - 0.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn previous_seasons() {
+        let games = vec![
+            game(2018, "PRK01", "AAA", "BBB", 12, None),
+            game(2018, "PRK02", "BBB", "AAA", 6, None),
+            game(2021, "PRK01", "AAA", "BBB", 6, None),
+            game(2021, "PRK02", "BBB", "AAA", 6, None),
+        ];
+        let factors = ParkFactor::from_games(&games);
+        let park_2021 = factors.iter().find(|f| f.park_id == "PRK01" && f.season == 2021).unwrap();
+        // 2018 is outside the three seasons ending in 2021.
+        assert_eq!(park_2021.games, 1);
+        assert!((park_2021.runs - 1.0).abs() < 1e-6);
+    }
+}
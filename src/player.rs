@@ -1,13 +1,18 @@
-use std::error::Error;
-
+use baseball::chadwick::events::FullEvent;
 use baseball::chadwick::gamelogs;
 
-use crate::database::Sql;
-use crate::search::{CelEval, SearchKey};
+use crate::database::{Sql, SqlColumns};
+use crate::parks::WithParkFactor;
+use crate::search::{CelEval, Matchup, SearchKey};
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
 
-use cel::Context;
-use rusqlite::{Row, Statement, Transaction, named_params};
-use rusqlite::types::ValueRef;
+const EVENT_STRIKEOUT: u8 = 3;
+const EVENT_WALK: u8 = 14;
+const EVENT_INTENTIONAL_WALK: u8 = 15;
+const EVENT_HOME_RUN: u8 = 23;
 
 
 pub trait PlayerGamelog {
@@ -19,13 +24,46 @@ pub trait PlayerGamelog {
 
     fn career_game(&self) -> u16;
 
+    fn team_game(&self) -> u16;
+
     fn set_team_game(&mut self, game: u16);
+
+    /// Set the player's age on the date of the game.
+    fn set_age(&mut self, birth_date: Option<NaiveDate>, date: NaiveDate);
+
+    /// Set the opposing team, park and manager of the player's team from the game.
+    fn set_game_details(&mut self, opponent: String, park_id: String, manager_id: String);
+
+    /// A game of the team's schedule after this one that the player missed. Counting stats are
+    /// zero while the season and career totals carry over from this game.
+    fn missed_game(&self, game_id: &str, team_game: u16) -> Self where Self: Sized;
+
+    /// A game of the team's schedule before this one, and before any other of the player's games,
+    /// that the player missed. The career totals are the ones from before this game while the
+    /// season rates aren't known.
+    fn missed_game_before(&self, game_id: &str, team_game: u16) -> Self where Self: Sized;
+}
+
+
+/// Plate appearances in one leverage level of a game. For pitchers these are the batters faced.
+#[derive(Clone, Copy, Debug, Default, Serialize, SqlColumns)]
+pub struct LeverageSplit {
+    pub pa: u8,
+    pub ab: u8,
+    pub h: u8,
+    pub hr: u8,
+    pub bb: u8,
+    pub so: u8,
 }
 
 
+#[derive(Sql, CelEval)]
+#[sql(table = "batting_gamelogs", create = "sql/create_batting_gamelogs.sql")]
 #[allow(non_snake_case)]
 pub struct BattingGamelog {
+    #[cel(skip)]
     pub player_id: String,
+    #[cel(skip)]
     pub game_id: String,
     pub team_id: String,
     pub career_game: u16,
@@ -51,6 +89,7 @@ pub struct BattingGamelog {
     pub cs: u8,
 
     // These are cumulative totals from beginning of season through this game.
+    #[sql(column = "bavg")]
     pub avg: f32,
     pub obp: f32,
     pub slg: f32,
@@ -59,12 +98,67 @@ pub struct BattingGamelog {
     pub babip: f32,
 
     pub pos: String,
+    /// Run expectancy added over the game's plate appearances. NaN when it wasn't computed.
+    pub re24: f32,
+    /// Win probability added over the game's events. NaN when it wasn't computed.
+    pub wpa: f32,
+    /// Win probability added from the beginning of the season through this game.
+    pub season_wpa: f32,
+    /// Average leverage index of the game's plate appearances. NaN when it wasn't computed.
+    pub li: f32,
+    #[sql(flatten = "low_")]
+    pub low_leverage: LeverageSplit,
+    #[sql(flatten = "medium_")]
+    pub medium_leverage: LeverageSplit,
+    #[sql(flatten = "high_")]
+    pub high_leverage: LeverageSplit,
+    // These are relative to the league and adjusted for the parks played in, from the beginning of
+    // the season through this game.
+    pub ops_plus: f32,
+    pub wrc_plus: f32,
+    // Career totals through this game, set when ordering the career games.
+    pub career_pa: u32,
+    pub career_ab: u32,
+    pub career_r: u32,
+    pub career_h: u32,
+    pub career_d: u32,
+    pub career_t: u32,
+    pub career_hr: u32,
+    pub career_rbi: u32,
+    pub career_bb: u32,
+    pub career_so: u32,
+    pub career_sb: u32,
+    /// Run factor of the park the game was played in, set when searching.
+    #[sql(skip)]
+    pub park_factor: f32,
+    /// Age in days on the date of the game, set when searching. NaN when the birth date isn't known.
+    #[sql(skip)]
+    pub age_days: f32,
+    /// Baseball age, the age on June 30 of the season, set when searching.
+    #[sql(skip)]
+    pub age: f32,
+    /// Team the player's team played against, set when searching.
+    #[sql(skip)]
+    pub opponent: String,
+    /// Park the game was played in, set when searching.
+    #[sql(skip)]
+    pub park_id: String,
+    /// Manager of the player's team in the game, set when searching.
+    #[sql(skip)]
+    pub manager_id: String,
+    /// A game of the team's schedule the player didn't play in, only in team schedule searches.
+    #[sql(skip)]
+    pub missed: bool,
 }
 
 
+#[derive(Sql, CelEval)]
+#[sql(table = "fielding_gamelogs", create = "sql/create_fielding_gamelogs.sql")]
 #[allow(non_snake_case)]
 pub struct FieldingGamelog {
+    #[cel(skip)]
     pub player_id: String,
+    #[cel(skip)]
     pub game_id: String,
     pub team_id: String,
     pub career_game: u16,
@@ -79,12 +173,37 @@ pub struct FieldingGamelog {
     pub tp: u8,
     pub bip: u8,
     pub bf: u8,
+    /// Run factor of the park the game was played in, set when searching.
+    #[sql(skip)]
+    pub park_factor: f32,
+    /// Age in days on the date of the game, set when searching. NaN when the birth date isn't known.
+    #[sql(skip)]
+    pub age_days: f32,
+    /// Baseball age, the age on June 30 of the season, set when searching.
+    #[sql(skip)]
+    pub age: f32,
+    /// Team the player's team played against, set when searching.
+    #[sql(skip)]
+    pub opponent: String,
+    /// Park the game was played in, set when searching.
+    #[sql(skip)]
+    pub park_id: String,
+    /// Manager of the player's team in the game, set when searching.
+    #[sql(skip)]
+    pub manager_id: String,
+    /// A game of the team's schedule the player didn't play in, only in team schedule searches.
+    #[sql(skip)]
+    pub missed: bool,
 }
 
 
+#[derive(Sql, CelEval)]
+#[sql(table = "pitching_gamelogs", create = "sql/create_pitching_gamelogs.sql")]
 #[allow(non_snake_case)]
 pub struct PitchingGamelog {
+    #[cel(skip)]
     pub player_id: String,
+    #[cel(skip)]
     pub game_id: String,
     pub team_id: String,
     pub career_game: u16,
@@ -114,28 +233,74 @@ pub struct PitchingGamelog {
     pub decision: String,
     pub era: f32,
     pub fip: f32,
+    /// Run expectancy saved over the game's plate appearances. NaN when it wasn't computed.
+    pub re24: f32,
+    /// Win probability added over the game's events. NaN when it wasn't computed.
+    pub wpa: f32,
+    /// Win probability added from the beginning of the season through this game.
+    pub season_wpa: f32,
+    /// Average leverage index of the game's plate appearances. NaN when it wasn't computed.
+    pub li: f32,
+    #[sql(flatten = "low_")]
+    pub low_leverage: LeverageSplit,
+    #[sql(flatten = "medium_")]
+    pub medium_leverage: LeverageSplit,
+    #[sql(flatten = "high_")]
+    pub high_leverage: LeverageSplit,
+    // These are relative to the league and adjusted for the parks played in, from the beginning of
+    // the season through this game.
+    pub era_plus: f32,
+    pub fip_minus: f32,
+    // Career totals through this game, set when ordering the career games.
+    pub career_gs: u32,
+    pub career_w: u32,
+    pub career_l: u32,
+    pub career_sv: u32,
+    pub career_ipouts: u32,
+    pub career_h: u32,
+    pub career_er: u32,
+    pub career_hr: u32,
+    pub career_bb: u32,
+    pub career_so: u32,
+    /// Run factor of the park the game was played in, set when searching.
+    #[sql(skip)]
+    pub park_factor: f32,
+    /// Age in days on the date of the game, set when searching. NaN when the birth date isn't known.
+    #[sql(skip)]
+    pub age_days: f32,
+    /// Baseball age, the age on June 30 of the season, set when searching.
+    #[sql(skip)]
+    pub age: f32,
+    /// Team the player's team played against, set when searching.
+    #[sql(skip)]
+    pub opponent: String,
+    /// Park the game was played in, set when searching.
+    #[sql(skip)]
+    pub park_id: String,
+    /// Manager of the player's team in the game, set when searching.
+    #[sql(skip)]
+    pub manager_id: String,
+    /// A game of the team's schedule the player didn't play in, only in team schedule searches.
+    #[sql(skip)]
+    pub missed: bool,
 }
 
 
-// Use a different method to map sqlite values to f32 because rusqlite won't map SQL NULLs to f32
-// NAN.
-fn map_sql_real_to_f32(value: ValueRef) -> f32 {
-    match value {
-        ValueRef::Null => {
-            f32::NAN
+impl LeverageSplit {
+    /// Count the event ending a plate appearance.
+    pub fn add_event(&mut self, event: &FullEvent) {
+        self.pa += 1;
+        if event.ab_fl {
+            self.ab += 1;
         }
-        ValueRef::Integer(int) => {
-            int as f32
+        if event.h_cd > 0 {
+            self.h += 1;
         }
-        ValueRef::Real(real) => {
-            real as f32
-        }
-        ValueRef::Text(_) => {
-            f32::NAN
-        }
-        ValueRef::Blob(_) => {
-            // This shouldn't happen based on the expected data.
-            f32::NAN
+        match event.event_cd {
+            EVENT_HOME_RUN => self.hr += 1,
+            EVENT_WALK | EVENT_INTENTIONAL_WALK => self.bb += 1,
+            EVENT_STRIKEOUT => self.so += 1,
+            _ => {}
         }
     }
 }
@@ -150,83 +315,105 @@ impl PlayerGamelog for BattingGamelog {
 
     fn career_game(&self) -> u16 { self.career_game }
 
-    fn set_team_game(&mut self, game: u16) { self.team_game = game; }
-}
+    fn team_game(&self) -> u16 { self.team_game }
 
+    fn set_team_game(&mut self, game: u16) { self.team_game = game; }
 
-impl CelEval for BattingGamelog {
-    fn add_cel_variables(&self, context: &mut Context, variables: &[&str]) -> Result<(), Box<dyn Error>> {
-        for name in variables {
-            match *name {
-                "career_game" => context.add_variable("career_game", self.career_game)?,
-                "season_game" => context.add_variable("season_game", self.season_game)?,
-                "team_game" => context.add_variable("team_game", self.team_game)?,
-                "pa" => context.add_variable("pa", self.pa)?,
-                "ab" => context.add_variable("ab", self.ab)?,
-                "r" => context.add_variable("r", self.r)?,
-                "h" => context.add_variable("h", self.h)?,
-                "d" => context.add_variable("d", self.d)?,
-                "t" => context.add_variable("t", self.t)?,
-                "hr" => context.add_variable("hr", self.hr)?,
-                "rbi" => context.add_variable("rbi", self.rbi)?,
-                "rbi2out" => context.add_variable("rbi2out", self.rbi2out)?,
-                "bb" => context.add_variable("bb", self.bb)?,
-                "ibb" => context.add_variable("ibb", self.ibb)?,
-                "so" => context.add_variable("so", self.so)?,
-                "gidp" => context.add_variable("gidp", self.gidp)?,
-                "hbp" => context.add_variable("hbp", self.hbp)?,
-                "sh" => context.add_variable("sh", self.sh)?,
-                "sf" => context.add_variable("sf", self.sf)?,
-                "sb" => context.add_variable("sb", self.sb)?,
-                "cs" => context.add_variable("cs", self.cs)?,
-                "avg" => context.add_variable("avg", self.avg)?,
-                "obp" => context.add_variable("obp", self.obp)?,
-                "slg" => context.add_variable("slg", self.slg)?,
-                "woba" => context.add_variable("woba", self.woba)?,
-                "babip" => context.add_variable("babip", self.babip)?,
-                "pos" => context.add_variable("pos", self.pos.clone())?,
-                _ => {},
-            }
-        }
+    fn set_age(&mut self, birth_date: Option<NaiveDate>, date: NaiveDate) {
+        (self.age_days, self.age) = age_on(birth_date, date);
+    }
 
-        Ok(())
+    fn set_game_details(&mut self, opponent: String, park_id: String, manager_id: String) {
+        self.opponent = opponent;
+        self.park_id = park_id;
+        self.manager_id = manager_id;
     }
 
-    fn check_cel_variables(variables: &[&str]) -> bool {
-        for name in variables {
-            match *name {
-                "career_game" => {},
-                "season_game" => {},
-                "team_game" => {},
-                "pa" => {},
-                "ab" => {},
-                "r" => {},
-                "h" => {},
-                "d" => {},
-                "t" => {},
-                "hr" => {},
-                "rbi" => {},
-                "rbi2out" => {},
-                "bb" => {},
-                "ibb" => {},
-                "so" => {},
-                "gidp" => {},
-                "hbp" => {},
-                "sh" => {},
-                "sf" => {},
-                "sb" => {},
-                "cs" => {},
-                "avg" => {},
-                "obp" => {},
-                "slg" => {},
-                "woba" => {},
-                "babip" => {},
-                "pos" => {},
-                _ => return false,
-            }
+    fn missed_game(&self, game_id: &str, team_game: u16) -> Self {
+        Self {
+            player_id: self.player_id.clone(),
+            game_id: game_id.to_string(),
+            team_id: self.team_id.clone(),
+            career_game: self.career_game,
+            season_game: self.season_game,
+            team_game,
+            pa: 0,
+            ab: 0,
+            r: 0,
+            h: 0,
+            d: 0,
+            t: 0,
+            hr: 0,
+            rbi: 0,
+            rbi2out: 0,
+            bb: 0,
+            ibb: 0,
+            so: 0,
+            gidp: 0,
+            hbp: 0,
+            sh: 0,
+            sf: 0,
+            sb: 0,
+            cs: 0,
+            avg: self.avg,
+            obp: self.obp,
+            slg: self.slg,
+            woba: self.woba,
+            babip: f32::NAN,
+            pos: String::new(),
+            re24: f32::NAN,
+            wpa: f32::NAN,
+            season_wpa: self.season_wpa,
+            li: f32::NAN,
+            low_leverage: LeverageSplit::default(),
+            medium_leverage: LeverageSplit::default(),
+            high_leverage: LeverageSplit::default(),
+            ops_plus: self.ops_plus,
+            wrc_plus: self.wrc_plus,
+            career_pa: self.career_pa,
+            career_ab: self.career_ab,
+            career_r: self.career_r,
+            career_h: self.career_h,
+            career_d: self.career_d,
+            career_t: self.career_t,
+            career_hr: self.career_hr,
+            career_rbi: self.career_rbi,
+            career_bb: self.career_bb,
+            career_so: self.career_so,
+            career_sb: self.career_sb,
+            park_factor: f32::NAN,
+            age_days: self.age_days,
+            age: self.age,
+            opponent: String::new(),
+            park_id: String::new(),
+            manager_id: String::new(),
+            missed: true,
         }
+    }
 
-        true
+    fn missed_game_before(&self, game_id: &str, team_game: u16) -> Self {
+        let mut game = self.missed_game(game_id, team_game);
+        game.career_game = self.career_game.saturating_sub(1);
+        game.season_game = self.season_game.saturating_sub(1);
+        game.avg = f32::NAN;
+        game.obp = f32::NAN;
+        game.slg = f32::NAN;
+        game.woba = f32::NAN;
+        game.season_wpa = f32::NAN;
+        game.ops_plus = f32::NAN;
+        game.wrc_plus = f32::NAN;
+        game.career_pa = self.career_pa.saturating_sub(self.pa.into());
+        game.career_ab = self.career_ab.saturating_sub(self.ab.into());
+        game.career_r = self.career_r.saturating_sub(self.r.into());
+        game.career_h = self.career_h.saturating_sub(self.h.into());
+        game.career_d = self.career_d.saturating_sub(self.d.into());
+        game.career_t = self.career_t.saturating_sub(self.t.into());
+        game.career_hr = self.career_hr.saturating_sub(self.hr.into());
+        game.career_rbi = self.career_rbi.saturating_sub(self.rbi.into());
+        game.career_bb = self.career_bb.saturating_sub(self.bb.into());
+        game.career_so = self.career_so.saturating_sub(self.so.into());
+        game.career_sb = self.career_sb.saturating_sub(self.sb.into());
+        game
     }
 }
 
@@ -247,125 +434,17 @@ impl SearchKey for BattingGamelog {
 }
 
 
-impl Sql for BattingGamelog {
-    fn create_table(tx: &mut Transaction) -> Result<(), Box<dyn Error>> {
-        tx.execute("DROP TABLE IF EXISTS batting_gamelogs", ())?;
-        tx.execute(include_str!("sql/create_batting_gamelogs.sql"), ())?;
-        Ok(())
-    }
+impl WithParkFactor for BattingGamelog {
+    fn park_game_id(&self) -> &str { &self.game_id }
 
-    fn table_name<'a>() -> &'a str { "batting_gamelogs" }
-
-    /// Read one row from the database to create the full struct.
-    fn read_row(row: &Row, offset: usize) -> Result<Self, rusqlite::Error> {
-        Ok(Self {
-            player_id: row.get(offset)?,
-            game_id: row.get(offset + 1)?,
-            team_id: row.get(offset + 2)?,
-            career_game: row.get(offset + 3)?,
-            season_game: row.get(offset + 4)?,
-            team_game: row.get(offset + 5)?,
-            pa: row.get(offset + 6)?,
-            ab: row.get(offset + 7)?,
-            r: row.get(offset + 8)?,
-            h: row.get(offset + 9)?,
-            d: row.get(offset + 10)?,
-            t: row.get(offset + 11)?,
-            hr: row.get(offset + 12)?,
-            rbi: row.get(offset + 13)?,
-            rbi2out: row.get(offset + 14)?,
-            bb: row.get(offset + 15)?,
-            ibb: row.get(offset + 16)?,
-            so: row.get(offset + 17)?,
-            gidp: row.get(offset + 18)?,
-            hbp: row.get(offset + 19)?,
-            sh: row.get(offset + 20)?,
-            sf: row.get(offset + 21)?,
-            sb: row.get(offset + 22)?,
-            cs: row.get(offset + 23)?,
-            avg: map_sql_real_to_f32(row.get_ref(offset + 24)?),
-            obp: map_sql_real_to_f32(row.get_ref(offset + 25)?),
-            slg: map_sql_real_to_f32(row.get_ref(offset + 26)?),
-            woba: map_sql_real_to_f32(row.get_ref(offset + 27)?),
-            babip: map_sql_real_to_f32(row.get_ref(offset + 28)?),
-            pos: row.get(offset + 29)?,
-        })
-    }
+    fn set_park_factor(&mut self, factor: f32) { self.park_factor = factor; }
+}
 
-    /// Write the struct to the database using named parameters.
-    fn write_row(&self, statement: &mut Statement) -> Result<usize, rusqlite::Error> {
-        statement.execute(
-            named_params! {
-                ":player_id": &self.player_id,
-                ":game_id": &self.game_id,
-                ":team_id": &self.team_id,
-                ":career_game": &self.career_game,
-                ":season_game": &self.season_game,
-                ":team_game": &self.team_game,
-                ":pa": &self.pa,
-                ":ab": &self.ab,
-                ":r": &self.r,
-                ":h": &self.h,
-                ":d": &self.d,
-                ":t": &self.t,
-                ":hr": &self.hr,
-                ":rbi": &self.rbi,
-                ":rbi2out": &self.rbi2out,
-                ":bb": &self.bb,
-                ":ibb": &self.ibb,
-                ":so": &self.so,
-                ":gidp": &self.gidp,
-                ":hbp": &self.hbp,
-                ":sh": &self.sh,
-                ":sf": &self.sf,
-                ":sb": &self.sb,
-                ":cs": &self.cs,
-                // "avg" is an invalid column name so it's named "bavg" in the SQL.
-                ":bavg": &self.avg,
-                ":obp": &self.obp,
-                ":slg": &self.slg,
-                ":woba": &self.woba,
-                ":babip": &self.babip,
-                ":pos": &self.pos,
-            }
-        )
-    }
 
-    // This could maybe come from a #[derive].
-    fn column_names<'a>() -> Vec<&'a str> {
-        vec![
-            "player_id",
-            "game_id",
-            "team_id",
-            "career_game",
-            "season_game",
-            "team_game",
-            "pa",
-            "ab",
-            "r",
-            "h",
-            "d",
-            "t",
-            "hr",
-            "rbi",
-            "rbi2out",
-            "bb",
-            "ibb",
-            "so",
-            "gidp",
-            "hbp",
-            "sh",
-            "sf",
-            "sb",
-            "cs",
-            "bavg",
-            "obp",
-            "slg",
-            "woba",
-            "babip",
-            "pos",
-        ]
-    }
+impl Matchup for BattingGamelog {
+    fn team(&self) -> &str { &self.team_id }
+
+    fn opponent(&self) -> &str { &self.opponent }
 }
 
 
@@ -411,6 +490,33 @@ impl From<gamelogs::BattingGamelog> for BattingGamelog {
             woba: 0.0,
             babip,
             pos: gamelog.pos,
+            re24: f32::NAN,
+            wpa: f32::NAN,
+            season_wpa: f32::NAN,
+            li: f32::NAN,
+            low_leverage: LeverageSplit::default(),
+            medium_leverage: LeverageSplit::default(),
+            high_leverage: LeverageSplit::default(),
+            ops_plus: f32::NAN,
+            wrc_plus: f32::NAN,
+            career_pa: 0,
+            career_ab: 0,
+            career_r: 0,
+            career_h: 0,
+            career_d: 0,
+            career_t: 0,
+            career_hr: 0,
+            career_rbi: 0,
+            career_bb: 0,
+            career_so: 0,
+            career_sb: 0,
+            park_factor: f32::NAN,
+            age_days: f32::NAN,
+            age: f32::NAN,
+            opponent: String::new(),
+            park_id: String::new(),
+            manager_id: String::new(),
+            missed: false,
         }
     }
 }
@@ -425,53 +531,52 @@ impl PlayerGamelog for FieldingGamelog {
 
     fn career_game(&self) -> u16 { self.career_game }
 
-    fn set_team_game(&mut self, game: u16) { self.team_game = game; }
-}
+    fn team_game(&self) -> u16 { self.team_game }
 
+    fn set_team_game(&mut self, game: u16) { self.team_game = game; }
 
-impl CelEval for FieldingGamelog {
-    fn add_cel_variables(&self, context: &mut Context, variables: &[&str]) -> Result<(), Box<dyn Error>> {
-        for name in variables {
-            match *name {
-                "career_game" => context.add_variable("career_game", self.career_game)?,
-                "season_game" => context.add_variable("season_game", self.season_game)?,
-                "team_game" => context.add_variable("team_game", self.team_game)?,
-                "pos" => context.add_variable("pos", self.pos)?,
-                "o" => context.add_variable("o", self.o)?,
-                "po" => context.add_variable("po", self.po)?,
-                "a" => context.add_variable("a", self.a)?,
-                "e" => context.add_variable("e", self.e)?,
-                "dp" => context.add_variable("dp", self.dp)?,
-                "tp" => context.add_variable("tp", self.tp)?,
-                "bip" => context.add_variable("bip", self.bip)?,
-                "bf" => context.add_variable("bf", self.bf)?,
-                _ => {},
-            }
-        }
+    fn set_age(&mut self, birth_date: Option<NaiveDate>, date: NaiveDate) {
+        (self.age_days, self.age) = age_on(birth_date, date);
+    }
 
-        Ok(())
+    fn set_game_details(&mut self, opponent: String, park_id: String, manager_id: String) {
+        self.opponent = opponent;
+        self.park_id = park_id;
+        self.manager_id = manager_id;
     }
 
-    fn check_cel_variables(variables: &[&str]) -> bool {
-        for name in variables {
-            match *name {
-                "career_game" => {},
-                "season_game" => {},
-                "team_game" => {},
-                "pos" => {},
-                "o" => {},
-                "po" => {},
-                "a" => {},
-                "e" => {},
-                "dp" => {},
-                "tp" => {},
-                "bip" => {},
-                "bf" => {},
-                _ => return false,
-            }
+    fn missed_game(&self, game_id: &str, team_game: u16) -> Self {
+        Self {
+            player_id: self.player_id.clone(),
+            game_id: game_id.to_string(),
+            team_id: self.team_id.clone(),
+            career_game: self.career_game,
+            season_game: self.season_game,
+            team_game,
+            pos: 0,
+            o: 0,
+            po: 0,
+            a: 0,
+            e: 0,
+            dp: 0,
+            tp: 0,
+            bip: 0,
+            bf: 0,
+            park_factor: f32::NAN,
+            age_days: self.age_days,
+            age: self.age,
+            opponent: String::new(),
+            park_id: String::new(),
+            manager_id: String::new(),
+            missed: true,
         }
+    }
 
-        true
+    fn missed_game_before(&self, game_id: &str, team_game: u16) -> Self {
+        let mut game = self.missed_game(game_id, team_game);
+        game.career_game = self.career_game.saturating_sub(1);
+        game.season_game = self.season_game.saturating_sub(1);
+        game
     }
 }
 
@@ -492,77 +597,17 @@ impl SearchKey for FieldingGamelog {
 }
 
 
-impl Sql for FieldingGamelog {
-    fn create_table(tx: &mut Transaction) -> Result<(), Box<dyn Error>> {
-        tx.execute("DROP TABLE IF EXISTS fielding_gamelogs", ())?;
-        tx.execute(include_str!("sql/create_fielding_gamelogs.sql"), ())?;
-        Ok(())
-    }
+impl WithParkFactor for FieldingGamelog {
+    fn park_game_id(&self) -> &str { &self.game_id }
 
-    fn table_name<'a>() -> &'a str { "fielding_gamelogs" }
-
-    fn read_row(row: &Row, offset: usize) -> Result<Self, rusqlite::Error> {
-        Ok(Self {
-            player_id: row.get(offset)?,
-            game_id: row.get(offset + 1)?,
-            team_id: row.get(offset + 2)?,
-            career_game: row.get(offset + 3)?,
-            season_game: row.get(offset + 4)?,
-            team_game: row.get(offset + 5)?,
-            pos: row.get(offset + 6)?,
-            o: row.get(offset + 7)?,
-            po: row.get(offset + 8)?,
-            a: row.get(offset + 9)?,
-            e: row.get(offset + 10)?,
-            dp: row.get(offset + 11)?,
-            tp: row.get(offset + 12)?,
-            bip: row.get(offset + 13)?,
-            bf: row.get(offset + 14)?,
-        })
-    }
+    fn set_park_factor(&mut self, factor: f32) { self.park_factor = factor; }
+}
 
-    /// Write the struct to the database using named parameters.
-    fn write_row(&self, statement: &mut Statement) -> Result<usize, rusqlite::Error> {
-        statement.execute(
-            named_params! {
-                ":player_id": &self.player_id,
-                ":game_id": &self.game_id,
-                ":team_id": &self.team_id,
-                ":career_game": &self.career_game,
-                ":season_game": &self.season_game,
-                ":team_game": &self.team_game,
-                ":pos": &self.pos,
-                ":o": &self.o,
-                ":po": &self.po,
-                ":a": &self.a,
-                ":e": &self.e,
-                ":dp": &self.dp,
-                ":tp": &self.tp,
-                ":bip": &self.bip,
-                ":bf": &self.bf,
-            }
-        )
-    }
 
-    fn column_names<'a>() -> Vec<&'a str> {
-        vec![
-            "player_id",
-            "game_id",
-            "team_id",
-            "career_game",
-            "season_game",
-            "team_game",
-            "pos",
-            "o",
-            "po",
-            "a",
-            "e",
-            "dp",
-            "tp",
-            "bip",
-            "bf",
-        ]
-    }
+impl Matchup for FieldingGamelog {
+    fn team(&self) -> &str { &self.team_id }
+
+    fn opponent(&self) -> &str { &self.opponent }
 }
 
 
@@ -584,6 +629,13 @@ impl From<gamelogs::FieldingGamelog> for FieldingGamelog {
             tp: gamelog.tp,
             bip: gamelog.bip,
             bf: gamelog.bf,
+            park_factor: f32::NAN,
+            age_days: f32::NAN,
+            age: f32::NAN,
+            opponent: String::new(),
+            park_id: String::new(),
+            manager_id: String::new(),
+            missed: false,
         }
     }
 }
@@ -598,83 +650,101 @@ impl PlayerGamelog for PitchingGamelog {
 
     fn career_game(&self) -> u16 { self.career_game }
 
-    fn set_team_game(&mut self, game: u16) { self.team_game = game; }
-}
+    fn team_game(&self) -> u16 { self.team_game }
 
+    fn set_team_game(&mut self, game: u16) { self.team_game = game; }
 
-impl CelEval for PitchingGamelog {
-    fn add_cel_variables(&self, context: &mut Context, variables: &[&str]) -> Result<(), Box<dyn Error>> {
-        for name in variables {
-            match *name {
-                "career_game" => context.add_variable("career_game", self.career_game)?,
-                "season_game" => context.add_variable("season_game", self.season_game)?,
-                "team_game" => context.add_variable("team_game", self.team_game)?,
-                "gs" => context.add_variable("gs", self.gs)?,
-                "cg" => context.add_variable("cg", self.cg)?,
-                "sho" => context.add_variable("sho", self.sho)?,
-                "gf" => context.add_variable("gf", self.gf)?,
-                "ipouts" => context.add_variable("ipouts", self.ipouts)?,
-                "ab" => context.add_variable("ab", self.ab)?,
-                "bf" => context.add_variable("bf", self.bf)?,
-                "h" => context.add_variable("h", self.h)?,
-                "r" => context.add_variable("r", self.r)?,
-                "er" => context.add_variable("er", self.er)?,
-                "hr" => context.add_variable("hr", self.hr)?,
-                "bb" => context.add_variable("bb", self.bb)?,
-                "ibb" => context.add_variable("ibb", self.ibb)?,
-                "so" => context.add_variable("so", self.so)?,
-                "wp" => context.add_variable("wp", self.wp)?,
-                "bk" => context.add_variable("bk", self.bk)?,
-                "hbp" => context.add_variable("hbp", self.hbp)?,
-                "gb" => context.add_variable("gb", self.gb)?,
-                "fb" => context.add_variable("fb", self.fb)?,
-                "p" => context.add_variable("p", self.p)?,
-                "s" => context.add_variable("s", self.s)?,
-                "decision" => context.add_variable("decision", self.decision.clone())?,
-                "era" => context.add_variable("era", self.era)?,
-                "fip" => context.add_variable("fip", self.fip)?,
-                _ => {},
-            }
-        }
+    fn set_age(&mut self, birth_date: Option<NaiveDate>, date: NaiveDate) {
+        (self.age_days, self.age) = age_on(birth_date, date);
+    }
 
-        Ok(())
+    fn set_game_details(&mut self, opponent: String, park_id: String, manager_id: String) {
+        self.opponent = opponent;
+        self.park_id = park_id;
+        self.manager_id = manager_id;
     }
 
-    fn check_cel_variables(variables: &[&str]) -> bool {
-        for name in variables {
-            match *name {
-                "career_game" => {},
-                "season_game" => {},
-                "team_game" => {},
-                "gs" => {},
-                "cg" => {},
-                "sho" => {},
-                "gf" => {},
-                "ipouts" => {},
-                "ab" => {},
-                "bf" => {},
-                "h" => {},
-                "r" => {},
-                "er" => {},
-                "hr" => {},
-                "bb" => {},
-                "ibb" => {},
-                "so" => {},
-                "wp" => {},
-                "bk" => {},
-                "hbp" => {},
-                "gb" => {},
-                "fb" => {},
-                "p" => {},
-                "s" => {},
-                "decision" => {},
-                "era" => {},
-                "fip" => {},
-                _ => return false,
-            }
+    fn missed_game(&self, game_id: &str, team_game: u16) -> Self {
+        Self {
+            player_id: self.player_id.clone(),
+            game_id: game_id.to_string(),
+            team_id: self.team_id.clone(),
+            career_game: self.career_game,
+            season_game: self.season_game,
+            team_game,
+            gs: false,
+            cg: false,
+            sho: false,
+            gf: false,
+            ipouts: 0,
+            ab: 0,
+            bf: 0,
+            h: 0,
+            r: 0,
+            er: 0,
+            hr: 0,
+            bb: 0,
+            ibb: 0,
+            so: 0,
+            wp: 0,
+            bk: 0,
+            hbp: 0,
+            gb: 0,
+            fb: 0,
+            p: 0,
+            s: 0,
+            decision: String::new(),
+            era: self.era,
+            fip: self.fip,
+            re24: f32::NAN,
+            wpa: f32::NAN,
+            season_wpa: self.season_wpa,
+            li: f32::NAN,
+            low_leverage: LeverageSplit::default(),
+            medium_leverage: LeverageSplit::default(),
+            high_leverage: LeverageSplit::default(),
+            era_plus: self.era_plus,
+            fip_minus: self.fip_minus,
+            career_gs: self.career_gs,
+            career_w: self.career_w,
+            career_l: self.career_l,
+            career_sv: self.career_sv,
+            career_ipouts: self.career_ipouts,
+            career_h: self.career_h,
+            career_er: self.career_er,
+            career_hr: self.career_hr,
+            career_bb: self.career_bb,
+            career_so: self.career_so,
+            park_factor: f32::NAN,
+            age_days: self.age_days,
+            age: self.age,
+            opponent: String::new(),
+            park_id: String::new(),
+            manager_id: String::new(),
+            missed: true,
         }
+    }
 
-        true
+    fn missed_game_before(&self, game_id: &str, team_game: u16) -> Self {
+        let mut game = self.missed_game(game_id, team_game);
+        game.career_game = self.career_game.saturating_sub(1);
+        game.season_game = self.season_game.saturating_sub(1);
+        game.era = f32::NAN;
+        game.fip = f32::NAN;
+        game.season_wpa = f32::NAN;
+        game.era_plus = f32::NAN;
+        game.fip_minus = f32::NAN;
+        game.career_gs = self.career_gs.saturating_sub(self.gs.into());
+        game.career_w = self.career_w.saturating_sub((self.decision == "W").into());
+        game.career_l = self.career_l.saturating_sub((self.decision == "L").into());
+        game.career_sv = self.career_sv.saturating_sub((self.decision == "S").into());
+        game.career_ipouts = self.career_ipouts.saturating_sub(self.ipouts.into());
+        game.career_h = self.career_h.saturating_sub(self.h.into());
+        game.career_er = self.career_er.saturating_sub(self.er.into());
+        game.career_hr = self.career_hr.saturating_sub(self.hr.into());
+        game.career_bb = self.career_bb.saturating_sub(self.bb.into());
+        game.career_so = self.career_so.saturating_sub(self.so.into());
+        game
     }
 }
 
@@ -695,122 +765,17 @@ impl SearchKey for PitchingGamelog {
 }
 
 
-impl Sql for PitchingGamelog {
-    fn create_table(tx: &mut Transaction) -> Result<(), Box<dyn Error>> {
-        tx.execute("DROP TABLE IF EXISTS pitching_gamelogs", ())?;
-        tx.execute(include_str!("sql/create_pitching_gamelogs.sql"), ())?;
-        Ok(())
-    }
+impl WithParkFactor for PitchingGamelog {
+    fn park_game_id(&self) -> &str { &self.game_id }
 
-    fn table_name<'a>() -> &'a str { "pitching_gamelogs" }
-
-    fn read_row(row: &Row, offset: usize) -> Result<Self, rusqlite::Error> {
-        Ok(Self {
-            player_id: row.get(offset)?,
-            game_id: row.get(offset + 1)?,
-            team_id: row.get(offset + 2)?,
-            career_game: row.get(offset + 3)?,
-            season_game: row.get(offset + 4)?,
-            team_game: row.get(offset + 5)?,
-            gs: row.get(offset + 6)?,
-            cg: row.get(offset + 7)?,
-            sho: row.get(offset + 8)?,
-            gf: row.get(offset + 9)?,
-            ipouts: row.get(offset + 10)?,
-            ab: row.get(offset + 11)?,
-            bf: row.get(offset + 12)?,
-            h: row.get(offset + 13)?,
-            r: row.get(offset + 14)?,
-            er: row.get(offset + 15)?,
-            hr: row.get(offset + 16)?,
-            bb: row.get(offset + 17)?,
-            ibb: row.get(offset + 18)?,
-            so: row.get(offset + 19)?,
-            wp: row.get(offset + 20)?,
-            bk: row.get(offset + 21)?,
-            hbp: row.get(offset + 22)?,
-            gb: row.get(offset + 23)?,
-            fb: row.get(offset + 24)?,
-            p: row.get(offset + 25)?,
-            s: row.get(offset + 26)?,
-            decision: row.get(offset + 27)?,
-            era: map_sql_real_to_f32(row.get_ref(offset + 28)?),
-            fip: map_sql_real_to_f32(row.get_ref(offset + 29)?),
-        })
-    }
+    fn set_park_factor(&mut self, factor: f32) { self.park_factor = factor; }
+}
 
-    /// Write the struct to the database using named parameters.
-    fn write_row(&self, statement: &mut Statement) -> Result<usize, rusqlite::Error> {
-        statement.execute(
-            named_params! {
-                ":player_id": &self.player_id,
-                ":game_id": &self.game_id,
-                ":team_id": &self.team_id,
-                ":career_game": &self.career_game,
-                ":season_game": &self.season_game,
-                ":team_game": &self.team_game,
-                ":gs": &self.gs,
-                ":cg": &self.cg,
-                ":sho": &self.sho,
-                ":gf": &self.gf,
-                ":ipouts": &self.ipouts,
-                ":ab": &self.ab,
-                ":bf": &self.bf,
-                ":h": &self.h,
-                ":r": &self.r,
-                ":er": &self.er,
-                ":hr": &self.hr,
-                ":bb": &self.bb,
-                ":ibb": &self.ibb,
-                ":so": &self.so,
-                ":wp": &self.wp,
-                ":bk": &self.bk,
-                ":hbp": &self.hbp,
-                ":gb": &self.gb,
-                ":fb": &self.fb,
-                ":p": &self.p,
-                ":s": &self.s,
-                ":decision": &self.decision,
-                ":era": &self.era,
-                ":fip": &self.fip,
-            }
-        )
-    }
 
-    fn column_names<'a>() -> Vec<&'a str> {
-        vec![
-            "player_id",
-            "game_id",
-            "team_id",
-            "career_game",
-            "season_game",
-            "team_game",
-            "gs",
-            "cg",
-            "sho",
-            "gf",
-            "ipouts",
-            "ab",
-            "bf",
-            "h",
-            "r",
-            "er",
-            "hr",
-            "bb",
-            "ibb",
-            "so",
-            "wp",
-            "bk",
-            "hbp",
-            "gb",
-            "fb",
-            "p",
-            "s",
-            "decision",
-            "era",
-            "fip",
-        ]
-    }
+impl Matchup for PitchingGamelog {
+    fn team(&self) -> &str { &self.team_id }
+
+    fn opponent(&self) -> &str { &self.opponent }
 }
 
 
@@ -847,6 +812,66 @@ impl From<gamelogs::PitchingGamelog> for PitchingGamelog {
             decision: gamelog.decision,
             era: 0.0,
             fip: 0.0,
+            re24: f32::NAN,
+            wpa: f32::NAN,
+            season_wpa: f32::NAN,
+            li: f32::NAN,
+            low_leverage: LeverageSplit::default(),
+            medium_leverage: LeverageSplit::default(),
+            high_leverage: LeverageSplit::default(),
+            era_plus: f32::NAN,
+            fip_minus: f32::NAN,
+            career_gs: 0,
+            career_w: 0,
+            career_l: 0,
+            career_sv: 0,
+            career_ipouts: 0,
+            career_h: 0,
+            career_er: 0,
+            career_hr: 0,
+            career_bb: 0,
+            career_so: 0,
+            park_factor: f32::NAN,
+            age_days: f32::NAN,
+            age: f32::NAN,
+            opponent: String::new(),
+            park_id: String::new(),
+            manager_id: String::new(),
+            missed: false,
         }
     }
 }
+
+
+/// Age in days and baseball age of someone born on the birth date, on the date. Both are NaN
+/// without a birth date.
+pub fn age_on(birth_date: Option<NaiveDate>, date: NaiveDate) -> (f32, f32) {
+    let Some(birth_date) = birth_date
+    else {
+        return (f32::NAN, f32::NAN);
+    };
+    let age_days = (date - birth_date).num_days() as f32;
+    let mut age = date.year() - birth_date.year();
+    if (birth_date.month(), birth_date.day()) > (6, 30) {
+        age -= 1;
+    }
+    (age_days, age as f32)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages() {
+        let date = NaiveDate::from_ymd_opt(2020, 4, 1).unwrap();
+        let (days, age) = age_on(NaiveDate::from_ymd_opt(2000, 6, 30), date);
+        assert_eq!((days, age), (7215.0, 20.0));
+        // Born after June 30 so a year younger for the season, though older on the day.
+        let (days, age) = age_on(NaiveDate::from_ymd_opt(1999, 7, 1), date);
+        assert_eq!((days, age), (7580.0, 20.0));
+        let (days, age) = age_on(None, date);
+        assert!(days.is_nan() && age.is_nan());
+    }
+}
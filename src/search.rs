@@ -1,13 +1,16 @@
 use std::cmp::Reverse;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::default::Default;
 use std::error::Error;
 use std::hash::Hash;
 
 use cel::{Context, Program, Value};
 use rayon::prelude::*;
+use serde::Serialize;
 use tracing::trace;
 
+pub use baseball_tools_derive::CelEval;
+
 
 const DEFAULT_RESULT_LIMIT: usize = 10;
 
@@ -28,15 +31,31 @@ pub trait SearchKey {
     fn order(&self, career: bool) -> u16;
 }
 
+/// The teams on each side of a searched item, for grouping by team or opponent.
+pub trait Matchup {
+    fn team(&self) -> &str;
+
+    fn opponent(&self) -> &str;
+}
+
 pub struct CelExec<'a> {
     context: Context<'a>,
     career_mode: bool,
     result_limit: usize,
     game_start: Option<u16>,
+    /// Games a streak may have that don't meet the condition, in total and in a row.
+    max_misses: Option<u32>,
+    max_consecutive_misses: Option<u32>,
     filter_program: Option<Program>,
     sort_program: Option<Program>,
     condition_program: Option<Program>,
     count_program: Option<Program>,
+    /// Named programs added up over groups of items.
+    sum_programs: Vec<(String, Program)>,
+    /// Programs whose values split each subject's items into groups.
+    group_programs: Vec<Program>,
+    /// Make the group values the subject, gathering the items of every subject with the same values.
+    group_subject: bool,
 }
 
 
@@ -46,16 +65,24 @@ pub enum SortOrder {
 }
 
 
+/// The items of a subject, like a player or team, by season (zero for a career) and by the values
+/// of the group programs (empty without any).
 #[derive(Clone, Eq, Hash, PartialEq)]
 pub struct Key {
     pub id: String,
     pub year: i32,
+    pub group: String,
 }
 
 
+#[derive(Default, Serialize)]
 pub struct StreakSpan {
     /// The player or team ID for this streak.
+    #[serde(rename = "subject")]
     pub id: String,
+    /// The values of the group programs, if any.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub group: String,
     /// The first game in the streak.
     pub start: String,
     /// The final game in the streak.
@@ -67,6 +94,8 @@ pub struct StreakSpan {
     /// The length of the streak in either games or another countable statistic (e.g. plate
     /// appearances).
     pub count: u32,
+    /// Games inside the streak that didn't meet the condition.
+    pub misses: u32,
 }
 
 
@@ -78,8 +107,21 @@ pub struct StreakEntry {
 }
 
 
+/// Totals of the sum programs over a group of items. The totals and the number of items, `games`,
+/// are the CEL variables for the having condition and sort key.
+pub struct Aggregate<'a, K> {
+    pub key: &'a K,
+    pub games: u32,
+    pub sums: Vec<(&'a str, f64)>,
+}
+
+
+#[derive(Default, Serialize)]
 pub struct WindowEntry {
+    #[serde(rename = "subject")]
     pub id: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub group: String,
     pub start: String,
     pub end: String,
     pub count: u32,
@@ -95,10 +137,15 @@ impl<'a> CelExec<'a> {
             career_mode,
             result_limit: limit,
             game_start: None,
+            max_misses: None,
+            max_consecutive_misses: None,
             filter_program: None,
             sort_program: None,
             condition_program: None,
             count_program: None,
+            sum_programs: Vec::new(),
+            group_programs: Vec::new(),
+            group_subject: false,
         }
     }
 
@@ -110,10 +157,22 @@ impl<'a> CelExec<'a> {
         self.result_limit = limit;
     }
 
+    pub fn limit(&self) -> usize {
+        self.result_limit
+    }
+
     pub fn set_game_start(&mut self, start: u16) {
         self.game_start = Some(start);
     }
 
+    pub fn set_max_misses(&mut self, misses: u32) {
+        self.max_misses = Some(misses);
+    }
+
+    pub fn set_max_consecutive_misses(&mut self, misses: u32) {
+        self.max_consecutive_misses = Some(misses);
+    }
+
     pub fn set_filter(&mut self, source: &str) -> Result<(), Box<dyn Error>> {
         self.filter_program = Some(Program::compile(source)?);
         Ok(())
@@ -134,6 +193,65 @@ impl<'a> CelExec<'a> {
         Ok(())
     }
 
+    /// Add a program to sum over groups of items, from a definition like `hr=hr`.
+    pub fn add_sum(&mut self, definition: &str) -> Result<(), Box<dyn Error>> {
+        let Some((name, source)) = definition.split_once('=')
+        else {
+            return Err(format!("sum {definition:?} is not in the form NAME=PROGRAM").into());
+        };
+        self.sum_programs.push((name.trim().to_string(), Program::compile(source)?));
+        Ok(())
+    }
+
+    /// Add a program to split the items of each subject by, like `park_id` or `opponent`.
+    pub fn add_group(&mut self, source: &str) -> Result<(), Box<dyn Error>> {
+        self.group_programs.push(Program::compile(source)?);
+        Ok(())
+    }
+
+    pub fn set_group_subject(&mut self, group_subject: bool) {
+        self.group_subject = group_subject;
+    }
+
+    /// Split the items of every key by the values of the group programs, keeping their order.
+    /// When the group values are the subject, the items of every subject with the same values and
+    /// season are gathered under one key instead. Without any group programs the keys are
+    /// unchanged.
+    pub fn regroup<U>(&self, map: HashMap<Key, Vec<U>>) -> Result<HashMap<Key, Vec<U>>, Box<dyn Error>>
+        where U: CelEval,
+    {
+        if self.group_programs.is_empty() {
+            return Ok(map);
+        }
+        let mut groups: HashMap<Key, Vec<U>> = HashMap::with_capacity(map.len());
+        for (key, items) in map {
+            for item in items {
+                let mut values = Vec::with_capacity(self.group_programs.len());
+                for program in &self.group_programs {
+                    let references = program.references();
+                    let variables = references.variables();
+                    let mut ctx = self.context.new_inner_scope();
+                    item.add_cel_variables(&mut ctx, &variables)?;
+                    values.push(group_value(&program.execute(&ctx)?));
+                }
+                let group_key = if self.group_subject {
+                    Key { id: values.join(", "), year: key.year, group: String::new() }
+                }
+                else {
+                    Key { id: key.id.clone(), year: key.year, group: values.join(", ") }
+                };
+                groups.entry(group_key).or_default().push(item);
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Check if the program uses a variable.
+    pub fn references_variable(source: &str, variable: &str) -> Result<bool, Box<dyn Error>> {
+        let program = Program::compile(source)?;
+        Ok(program.references().has_variable(variable))
+    }
+
     pub fn check_program_variables<T: CelEval>(source: &str) -> Result<bool, Box<dyn Error>> {
         let program = Program::compile(source)?;
         let references = program.references();
@@ -200,6 +318,13 @@ impl<'a> CelExec<'a> {
         let mut streaks = Vec::with_capacity(150);
         let mut streak_minimum = 2;
         for (key, entries) in streak_map.iter() {
+            if self.max_misses.is_some() || self.max_consecutive_misses.is_some() {
+                let spans = self.tolerant_streaks(key, entries);
+                streaks.extend(spans.into_iter().filter(|span| span.count >= streak_minimum));
+                self.prune_streaks(&mut streaks, &mut streak_minimum);
+                continue;
+            }
+
             let mut streak_start = None;
             let mut streak_end = None;
             let mut game_start = None;
@@ -246,11 +371,13 @@ impl<'a> CelExec<'a> {
                     if let (Some(start), Some(end), Some(game_start)) = (streak_start, streak_end, game_start) && count >= streak_minimum {
                         let span = StreakSpan {
                             id: key.id.to_owned(),
+                            group: key.group.clone(),
                             start: start.clone(),
                             end: end.clone(),
                             game_start,
                             length,
                             count,
+                            misses: 0,
                         };
                         streaks.push(span);
                     }
@@ -267,31 +394,179 @@ impl<'a> CelExec<'a> {
             if let (Some(start), Some(end), Some(game_start)) = (streak_start, streak_end, game_start) && count >= streak_minimum {
                 let span = StreakSpan {
                     id: key.id.to_owned(),
+                    group: key.group.clone(),
                     start: start.clone(),
                     end: end.clone(),
                     game_start,
                     length,
                     count,
+                    misses: 0,
                 };
                 streaks.push(span);
             }
 
-            // Sort the spans and check the 100th entry to see if the streak minimum length should
-            // increase. If so, prune the list to only spans meeting the new minimum.
-            streaks.sort_unstable_by_key(|a| Reverse(a.count));
-            if let Some(span) = streaks.get(self.result_limit) && span.count > streak_minimum {
-                streak_minimum = span.count;
-                trace!(streak_minimum = streak_minimum, streaks = streaks.len(), "Increasing streak minimum");
-                streaks.retain(|span| span.count >= streak_minimum);
-            }
+            self.prune_streaks(&mut streaks, &mut streak_minimum);
         }
 
         streaks
     }
 
-    pub fn window_eval<'data, T, U>(&self, map: &'data HashMap<T, Vec<U>>, size: usize) -> HashMap<&'data T, Vec<WindowEntry>>
+    /// Sort the spans and check the entry at the result limit to see if the streak minimum length
+    /// should increase. If so, prune the list to only spans meeting the new minimum.
+    fn prune_streaks(&self, streaks: &mut Vec<StreakSpan>, streak_minimum: &mut u32) {
+        streaks.sort_unstable_by_key(|a| Reverse(a.count));
+        if let Some(span) = streaks.get(self.result_limit) && span.count > *streak_minimum {
+            *streak_minimum = span.count;
+            trace!(streak_minimum = streak_minimum, streaks = streaks.len(), "Increasing streak minimum");
+            streaks.retain(|span| span.count >= *streak_minimum);
+        }
+    }
+
+    /// Streaks allowing games that don't meet the condition, up to the most misses in total and in
+    /// a row. Streaks start and end with a game meeting the condition. After too many misses the
+    /// next streak starts after the first miss, so streaks can overlap.
+    fn tolerant_streaks(&self, key: &Key, entries: &[StreakEntry]) -> Vec<StreakSpan> {
+        let max_misses = self.max_misses.map_or(usize::MAX, |misses| misses as usize);
+        let max_consecutive = self.max_consecutive_misses.map_or(usize::MAX, |misses| misses as usize);
+        // Entries before the anchor game are skipped and only a streak starting at it counts.
+        let entries = match self.game_start {
+            Some(anchor) => &entries[entries.iter().position(|entry| entry.order >= anchor).unwrap_or(entries.len())..],
+            None => entries,
+        };
+
+        let mut spans: Vec<StreakSpan> = Vec::new();
+        let mut push_span = |streak: &[StreakEntry]| {
+            // A later start ending on the same game is part of the streak before.
+            if spans.last().is_some_and(|span| streak.last().is_some_and(|entry| span.end == entry.game_id)) {
+                return;
+            }
+            if let Some(span) = streak_span(key, streak) {
+                spans.push(span);
+            }
+        };
+        let mut start: Option<usize> = None;
+        let mut end = 0;
+        // Misses after the start of the current streak, including those after its last game.
+        let mut misses = VecDeque::new();
+        for (idx, entry) in entries.iter().enumerate() {
+            let Some(first) = start
+            else {
+                if entry.result {
+                    start = Some(idx);
+                    end = idx;
+                }
+                continue;
+            };
+            if entry.result {
+                end = idx;
+                continue;
+            }
+            if idx - end > max_consecutive {
+                push_span(&entries[first..=end]);
+                start = None;
+                misses.clear();
+                continue;
+            }
+            misses.push_back(idx);
+            if misses.len() > max_misses {
+                push_span(&entries[first..=end]);
+                // Start again at the first game meeting the condition after the first miss.
+                let dropped = misses.pop_front().unwrap_or(idx);
+                start = (dropped + 1..=end).find(|&next| entries[next].result);
+                match start {
+                    Some(next) => {
+                        while misses.front().is_some_and(|&miss| miss < next) {
+                            misses.pop_front();
+                        }
+                    }
+                    None => misses.clear(),
+                }
+            }
+        }
+        if let Some(first) = start {
+            push_span(&entries[first..=end]);
+        }
+
+        if let Some(anchor) = self.game_start {
+            spans.truncate(1);
+            spans.retain(|span| span.game_start == anchor);
+        }
+        spans
+    }
+
+    /// The longest span of each subject, of at least `minimum` items, where the share of items
+    /// with a count meets the ratio. Spans start and end with an item with a count and come back
+    /// longest first.
+    pub fn density_eval<U>(&self, map: &HashMap<Key, Vec<U>>, ratio: f64, minimum: usize) -> Vec<StreakSpan>
+        where U: Sync + CelEval + SearchKey,
+    {
+        let Some(ref program) = self.count_program
+        else {
+            return Vec::new();
+        };
+        let mut spans: Vec<StreakSpan> = map.par_iter()
+            .filter_map(|(key, items)| {
+                let references = program.references();
+                let variables = references.variables();
+                let values = Self::eval_slice(items, &self.context, program, &variables);
+                let hits: Vec<bool> = values.iter()
+                    .map(|(_item, value)| match value {
+                        Value::Int(i) => *i > 0,
+                        Value::UInt(u) => *u > 0,
+                        Value::Bool(b) => *b,
+                        _ => false,
+                    })
+                    .collect();
+                let (first, last) = longest_dense_span(&hits, ratio)?;
+                let length = last - first + 1;
+                if length < minimum {
+                    return None;
+                }
+                let count = hits[first..=last].iter().filter(|hit| **hit).count() as u32;
+                Some(StreakSpan {
+                    id: key.id.to_owned(),
+                    group: key.group.clone(),
+                    start: values[first].0.id().to_owned(),
+                    end: values[last].0.id().to_owned(),
+                    game_start: values[first].0.order(self.career_mode),
+                    length: length as u32,
+                    count,
+                    misses: length as u32 - count,
+                })
+            })
+            .collect();
+        spans.sort_unstable_by_key(|span| (Reverse(span.length), Reverse(span.count)));
+        spans.truncate(self.result_limit);
+        spans
+    }
+
+    /// The first item of each subject, in the order of their items, where the condition is true.
+    /// The items come back with their keys in order of when the condition was met, fastest first.
+    pub fn milestone_eval<'data, T, U>(&self, map: &'data HashMap<T, Vec<U>>) -> Vec<(&'data T, &'data U)>
         where T: Eq + Hash + Sync,
               U: Sync + CelEval + SearchKey,
+    {
+        let Some(ref program) = self.condition_program
+        else {
+            return Vec::new();
+        };
+        let references = program.references();
+        let variables = references.variables();
+        let mut milestones: Vec<(&T, &U)> = map.par_iter()
+            .filter_map(|(key, items)| {
+                items.iter().find(|item| self.filter_option(*item, program, &variables)).map(|item| (key, item))
+            })
+            .collect();
+        milestones.sort_unstable_by(|(_, a), (_, b)| {
+            a.order(self.career_mode).cmp(&b.order(self.career_mode))
+                .then_with(|| a.subject_id().cmp(b.subject_id()))
+        });
+        milestones.truncate(self.result_limit);
+        milestones
+    }
+
+    pub fn window_eval<'data, U>(&self, map: &'data HashMap<Key, Vec<U>>, size: usize) -> HashMap<&'data Key, Vec<WindowEntry>>
+        where U: Sync + CelEval + SearchKey,
     {
         if let Some(ref program) = self.count_program {
             let player_windows: HashMap<_, _> = map.par_iter().map(|kv| {
@@ -304,10 +579,10 @@ impl<'a> CelExec<'a> {
                 // chosen size. I don't want to skip those windows so check the size and manually
                 // run the inner method on the whole thing if too short.
                 let entries: Vec<_> = if processed_values.len() >= size {
-                    processed_values.windows(size).map(|w| self.window_eval_each(w)).collect()
+                    processed_values.windows(size).map(|w| self.window_eval_each(&key.group, w)).collect()
                 }
                 else {
-                    vec![self.window_eval_each(&processed_values)]
+                    vec![self.window_eval_each(&key.group, &processed_values)]
                 };
                 (key, entries)
             }).collect();
@@ -318,8 +593,45 @@ impl<'a> CelExec<'a> {
         }
     }
 
+    /// Add up each sum program over the items of every group. True counts as one.
+    pub fn aggregate_eval<'data, K, U>(&'data self, groups: &'data HashMap<K, Vec<U>>) -> Vec<Aggregate<'data, K>>
+        where K: Eq + Hash + Sync,
+              U: Sync + CelEval,
+    {
+        groups.par_iter().map(|(key, items)| {
+            let sums = self.sum_programs.iter().map(|(name, program)| {
+                let references = program.references();
+                let variables = references.variables();
+                let total = Self::eval_slice(items, &self.context, program, &variables).iter()
+                    .map(|(_item, value)| match value {
+                        Value::Int(i) => *i as f64,
+                        Value::UInt(u) => *u as f64,
+                        Value::Float(f) => *f,
+                        Value::Bool(true) => 1.0,
+                        _ => 0.0,
+                    })
+                    .sum();
+                (name.as_str(), total)
+            }).collect();
+            Aggregate {
+                key,
+                games: items.len() as u32,
+                sums,
+            }
+        }).collect()
+    }
+
+    /// Keep the items meeting the condition program, if there is one.
+    pub fn retain_condition<T: CelEval>(&self, input: &mut Vec<T>) {
+        if let Some(program) = self.condition_program.as_ref() {
+            let references = program.references();
+            let variables = references.variables();
+            input.retain(|element| self.filter_option(element, program, &variables));
+        }
+    }
+
     /// Evaluate every item within the window to produce a count from the program.
-    fn window_eval_each<T>(&self, window: &[(&T, Value)]) -> WindowEntry
+    fn window_eval_each<T>(&self, group: &str, window: &[(&T, Value)]) -> WindowEntry
         where T: SearchKey,
     {
         let mut count = 0;
@@ -337,6 +649,7 @@ impl<'a> CelExec<'a> {
 
         WindowEntry {
             id: start.map_or("id", |e| e.0.subject_id()).to_owned(),
+            group: group.to_owned(),
             start: start.map_or("unknown", |e| e.0.id()).to_owned(),
             end: end.map_or("unknown", |e| e.0.id()).to_owned(),
             count,
@@ -422,6 +735,15 @@ impl<'a> CelExec<'a> {
         }
     }
 
+    /// Value of the sort program for one item, if there is a sort program.
+    pub fn sort_value<T: CelEval>(&self, item: &T) -> Option<f64> {
+        self.sort_program.as_ref().map(|program| {
+            let references = program.references();
+            let variables = references.variables();
+            self.sort_key(item, program, &variables)
+        })
+    }
+
     fn sort_key<T: CelEval>(&self, career: &T, program: &Program, variables: &[&str]) -> f64 {
         let mut player_ctx = self.context.new_inner_scope();
         if career.add_cel_variables(&mut player_ctx, variables).is_err() {
@@ -447,10 +769,263 @@ impl<'a> Default for CelExec<'a> {
             career_mode: false,
             result_limit: DEFAULT_RESULT_LIMIT,
             game_start: None,
+            max_misses: None,
+            max_consecutive_misses: None,
             filter_program: None,
             sort_program: None,
             condition_program: None,
             count_program: None,
+            sum_programs: Vec::new(),
+            group_programs: Vec::new(),
+            group_subject: false,
+        }
+    }
+}
+
+
+/// A streak from the entries between its first and last games meeting the condition.
+fn streak_span(key: &Key, entries: &[StreakEntry]) -> Option<StreakSpan> {
+    let (first, last) = (entries.first()?, entries.last()?);
+    let misses = entries.iter().filter(|entry| !entry.result).count() as u32;
+    Some(StreakSpan {
+        id: key.id.to_owned(),
+        group: key.group.clone(),
+        start: first.game_id.clone(),
+        end: last.game_id.clone(),
+        game_start: first.order,
+        length: entries.len() as u32,
+        count: entries.iter().filter(|entry| entry.result).map(|entry| entry.count as u32).sum(),
+        misses,
+    })
+}
+
+
+/// The longest run of hits and misses starting and ending with a hit where the share of hits is
+/// at least the ratio, as the indexes of its first and last hits.
+fn longest_dense_span(hits: &[bool], ratio: f64) -> Option<(usize, usize)> {
+    // Allow for rounding in the sums, like 0.8 * 5 being a bit over 4.
+    const EPSILON: f64 = 1e-9;
+    // Each hit adds one less the ratio to the sums, and a miss takes off the ratio. A span meets
+    // the ratio when the sum after its last item is no less than the sum before its first.
+    let mut sums = Vec::with_capacity(hits.len() + 1);
+    let mut sum = 0.0;
+    sums.push(sum);
+    for hit in hits {
+        sum += if *hit { 1.0 } else { 0.0 } - ratio;
+        sums.push(sum);
+    }
+    // The longest span starts at a hit with a lower sum than every earlier hit, since any earlier
+    // start would make a longer span.
+    let mut starts: Vec<usize> = Vec::new();
+    for (idx, hit) in hits.iter().enumerate() {
+        if *hit && starts.last().is_none_or(|&start| sums[idx] < sums[start]) {
+            starts.push(idx);
+        }
+    }
+    // Match each start with the last end meeting the ratio, working back from the final hit.
+    let mut longest: Option<(usize, usize)> = None;
+    for end in (0..hits.len()).rev().filter(|&end| hits[end]) {
+        while let Some(&start) = starts.last() {
+            if start > end {
+                starts.pop();
+            }
+            else if sums[end + 1] + EPSILON >= sums[start] {
+                // Ties go to the earlier span.
+                if longest.is_none_or(|(first, last)| end - start >= last - first) {
+                    longest = Some((start, end));
+                }
+                starts.pop();
+            }
+            else {
+                break;
+            }
+        }
+    }
+    longest
+}
+
+
+/// The text of a group program's value. Values that can't be text, like lists, are empty.
+fn group_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::UInt(u) => u.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::Bool(b) => b.to_string(),
+        _ => String::new(),
+    }
+}
+
+
+impl<'a, K> CelEval for Aggregate<'a, K> {
+    fn add_cel_variables(&self, context: &mut Context, variables: &[&str]) -> Result<(), Box<dyn Error>> {
+        for variable in variables {
+            if *variable == "games" {
+                context.add_variable("games", self.games)?;
+            }
+            else if let Some((name, total)) = self.sums.iter().find(|(name, _)| name == variable) {
+                context.add_variable(*name, *total)?;
+            }
         }
+        Ok(())
+    }
+
+    /// The sums are only known once the programs are set, so any variable could be one.
+    fn check_cel_variables(_variables: &[&str]) -> bool {
+        true
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(results: &str) -> Vec<StreakEntry> {
+        results.chars().enumerate()
+            .map(|(idx, result)| StreakEntry {
+                game_id: format!("G{idx}"),
+                order: idx as u16 + 1,
+                result: result == 'x',
+                count: 1,
+            })
+            .collect()
+    }
+
+    fn spans(exec: &CelExec, results: &str) -> Vec<(String, String, u32, u32)> {
+        let key = Key { id: "p1".to_string(), year: 0, group: String::new() };
+        let map = HashMap::from([(&key, entries(results))]);
+        let mut spans: Vec<_> = exec.find_streaks(&map).into_iter()
+            .map(|span| (span.start, span.end, span.count, span.misses))
+            .collect();
+        spans.sort();
+        spans
+    }
+
+    #[test]
+    fn tolerant_streaks() {
+        let mut exec = CelExec::default();
+        exec.set_max_misses(1);
+        assert_eq!(spans(&exec, "xxx.xxx.xxx"), vec![
+            ("G0".to_string(), "G6".to_string(), 6, 1),
+            ("G4".to_string(), "G10".to_string(), 6, 1),
+        ]);
+        // Misses at the ends aren't part of the streak.
+        assert_eq!(spans(&exec, ".xx.."), vec![("G1".to_string(), "G2".to_string(), 2, 0)]);
+
+        let mut exec = CelExec::default();
+        exec.set_max_consecutive_misses(1);
+        assert_eq!(spans(&exec, "xx.x.xx..xx"), vec![
+            ("G0".to_string(), "G6".to_string(), 5, 2),
+            ("G9".to_string(), "G10".to_string(), 2, 0),
+        ]);
+    }
+
+    struct Game {
+        player_id: String,
+        game_id: String,
+        game: u16,
+        hr: i64,
+    }
+
+    impl CelEval for Game {
+        fn add_cel_variables(&self, context: &mut Context, _variables: &[&str]) -> Result<(), Box<dyn Error>> {
+            context.add_variable("hr", self.hr)?;
+            Ok(())
+        }
+
+        fn check_cel_variables(variables: &[&str]) -> bool {
+            variables.iter().all(|variable| *variable == "hr")
+        }
+    }
+
+    impl SearchKey for Game {
+        fn id(&self) -> &str {
+            &self.game_id
+        }
+
+        fn subject_id(&self) -> &str {
+            &self.player_id
+        }
+
+        fn order(&self, _career: bool) -> u16 {
+            self.game
+        }
+    }
+
+    fn games(player_id: &str, home_runs: &[i64]) -> Vec<Game> {
+        home_runs.iter().enumerate()
+            .map(|(idx, hr)| Game {
+                player_id: player_id.to_string(),
+                game_id: format!("{player_id}-G{}", idx + 1),
+                game: idx as u16 + 1,
+                hr: *hr,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn milestones() {
+        let map = HashMap::from([
+            ("p1", games("p1", &[0, 0, 1, 1])),
+            ("p2", games("p2", &[0, 1, 0])),
+            ("p3", games("p3", &[0, 0])),
+        ]);
+        let mut exec = CelExec::default();
+        assert!(exec.milestone_eval(&map).is_empty());
+
+        exec.set_condition("hr >= 1").unwrap();
+        let milestones: Vec<(&str, &str)> = exec.milestone_eval(&map).into_iter()
+            .map(|(player, game)| (*player, game.id()))
+            .collect();
+        assert_eq!(milestones, [("p2", "p2-G2"), ("p1", "p1-G3")]);
+
+        exec.set_limit(1);
+        assert_eq!(exec.milestone_eval(&map).len(), 1);
+    }
+
+    #[test]
+    fn regroups() {
+        let key = |id: &str| Key { id: id.to_string(), year: 2020, group: String::new() };
+        let map = || HashMap::from([(key("p1"), games("p1", &[0, 1, 0])), (key("p2"), games("p2", &[1]))]);
+        let grouped = |groups: HashMap<Key, Vec<Game>>| {
+            let mut grouped: Vec<(String, String, Vec<String>)> = groups.into_iter()
+                .map(|(key, games)| (key.id, key.group, games.into_iter().map(|game| game.game_id).collect()))
+                .collect();
+            grouped.sort();
+            grouped
+        };
+
+        let mut exec = CelExec::default();
+        assert_eq!(grouped(exec.regroup(map()).unwrap()).len(), 2);
+        exec.add_group("hr").unwrap();
+        assert_eq!(grouped(exec.regroup(map()).unwrap()), [
+            ("p1".to_string(), "0".to_string(), vec!["p1-G1".to_string(), "p1-G3".to_string()]),
+            ("p1".to_string(), "1".to_string(), vec!["p1-G2".to_string()]),
+            ("p2".to_string(), "1".to_string(), vec!["p2-G1".to_string()]),
+        ]);
+
+        // As the subject the group gathers the games of both players.
+        exec.set_group_subject(true);
+        let mut subjects = grouped(exec.regroup(map()).unwrap());
+        subjects[1].2.sort();
+        assert_eq!(subjects, [
+            ("0".to_string(), String::new(), vec!["p1-G1".to_string(), "p1-G3".to_string()]),
+            ("1".to_string(), String::new(), vec!["p1-G2".to_string(), "p2-G1".to_string()]),
+        ]);
+
+        let mut exec = CelExec::default();
+        exec.add_group("hr + unknown").unwrap();
+        assert!(exec.regroup(map()).is_err());
+    }
+
+    #[test]
+    fn dense_spans() {
+        let hits = |results: &str| results.chars().map(|result| result == 'x').collect::<Vec<_>>();
+        assert_eq!(longest_dense_span(&hits("x.xxxx.x.."), 0.8), Some((0, 5)));
+        assert_eq!(longest_dense_span(&hits("x.xxxx.x.."), 0.75), Some((0, 7)));
+        assert_eq!(longest_dense_span(&hits("..x.."), 1.0), Some((2, 2)));
+        assert_eq!(longest_dense_span(&hits("...."), 0.5), None);
     }
 }
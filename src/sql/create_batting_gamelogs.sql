@@ -28,5 +28,40 @@ CREATE TABLE batting_gamelogs (
     slg REAL,
     woba REAL,
     babip REAL,
-    pos TEXT
+    pos TEXT,
+    re24 REAL,
+    wpa REAL,
+    season_wpa REAL,
+    li REAL,
+    low_pa INTEGER,
+    low_ab INTEGER,
+    low_h INTEGER,
+    low_hr INTEGER,
+    low_bb INTEGER,
+    low_so INTEGER,
+    medium_pa INTEGER,
+    medium_ab INTEGER,
+    medium_h INTEGER,
+    medium_hr INTEGER,
+    medium_bb INTEGER,
+    medium_so INTEGER,
+    high_pa INTEGER,
+    high_ab INTEGER,
+    high_h INTEGER,
+    high_hr INTEGER,
+    high_bb INTEGER,
+    high_so INTEGER,
+    ops_plus REAL,
+    wrc_plus REAL,
+    career_pa INTEGER,
+    career_ab INTEGER,
+    career_r INTEGER,
+    career_h INTEGER,
+    career_d INTEGER,
+    career_t INTEGER,
+    career_hr INTEGER,
+    career_rbi INTEGER,
+    career_bb INTEGER,
+    career_so INTEGER,
+    career_sb INTEGER
 )
@@ -0,0 +1,5 @@
+CREATE TABLE career_game_updates (
+    table_name TEXT NOT NULL,
+    season INTEGER NOT NULL,
+    PRIMARY KEY (table_name, season)
+)
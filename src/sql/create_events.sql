@@ -0,0 +1,61 @@
+CREATE TABLE events (
+    game_id TEXT NOT NULL,
+    event_id INTEGER NOT NULL,
+    inn_ct INTEGER,
+    bat_home_id INTEGER,
+    outs_ct INTEGER,
+    balls_ct INTEGER,
+    strikes_ct INTEGER,
+    pitch_seq_tx TEXT,
+    away_score_ct INTEGER,
+    home_score_ct INTEGER,
+    bat_id TEXT NOT NULL,
+    bat_hand_cd TEXT,
+    resp_bat_id TEXT,
+    pit_id TEXT NOT NULL,
+    pit_hand_cd TEXT,
+    resp_pit_id TEXT,
+    bat_team_id TEXT,
+    fld_team_id TEXT,
+    base1_run_id TEXT,
+    base2_run_id TEXT,
+    base3_run_id TEXT,
+    start_bases_cd INTEGER,
+    end_bases_cd INTEGER,
+    event_tx TEXT,
+    leadoff_fl INTEGER,
+    ph_fl INTEGER,
+    bat_fld_cd INTEGER,
+    bat_lineup_id INTEGER,
+    event_cd INTEGER,
+    bat_event_fl INTEGER,
+    ab_fl INTEGER,
+    h_cd INTEGER,
+    sh_fl INTEGER,
+    sf_fl INTEGER,
+    event_outs_ct INTEGER,
+    dp_fl INTEGER,
+    tp_fl INTEGER,
+    rbi_ct INTEGER,
+    wp_fl INTEGER,
+    pb_fl INTEGER,
+    fld_cd INTEGER,
+    battedball_cd TEXT,
+    bunt_fl INTEGER,
+    foul_fl INTEGER,
+    battedball_loc_tx TEXT,
+    err_ct INTEGER,
+    bat_dest_id INTEGER,
+    run1_dest_id INTEGER,
+    run2_dest_id INTEGER,
+    run3_dest_id INTEGER,
+    run1_sb_fl INTEGER,
+    run2_sb_fl INTEGER,
+    run3_sb_fl INTEGER,
+    run1_cs_fl INTEGER,
+    run2_cs_fl INTEGER,
+    run3_cs_fl INTEGER,
+    event_runs_ct INTEGER,
+    pa_new_fl INTEGER,
+    PRIMARY KEY (game_id, event_id)
+)
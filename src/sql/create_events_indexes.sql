@@ -0,0 +1,3 @@
+CREATE INDEX events_game_idx ON events (game_id);
+CREATE INDEX events_bat_idx ON events (bat_id);
+CREATE INDEX events_pit_idx ON events (pit_id);
@@ -7,5 +7,11 @@ CREATE TABLE guts (
     woba_2b_weight REAL,
     woba_3b_weight REAL,
     woba_hr_weight REAL,
-    fip_constant REAL
+    fip_constant REAL,
+    league_woba REAL,
+    runs_per_pa REAL,
+    runs_per_win REAL,
+    league_obp REAL,
+    league_slg REAL,
+    league_era REAL
 )
@@ -0,0 +1,9 @@
+CREATE TABLE park_factors (
+    season INTEGER,
+    park_id TEXT,
+    games INTEGER,
+    runs REAL,
+    hits REAL,
+    hr REAL,
+    PRIMARY KEY (season, park_id)
+)
@@ -28,5 +28,39 @@ CREATE TABLE pitching_gamelogs (
     s INTEGER,
     decision TEXT,
     era REAL,
-    fip REAL
+    fip REAL,
+    re24 REAL,
+    wpa REAL,
+    season_wpa REAL,
+    li REAL,
+    low_pa INTEGER,
+    low_ab INTEGER,
+    low_h INTEGER,
+    low_hr INTEGER,
+    low_bb INTEGER,
+    low_so INTEGER,
+    medium_pa INTEGER,
+    medium_ab INTEGER,
+    medium_h INTEGER,
+    medium_hr INTEGER,
+    medium_bb INTEGER,
+    medium_so INTEGER,
+    high_pa INTEGER,
+    high_ab INTEGER,
+    high_h INTEGER,
+    high_hr INTEGER,
+    high_bb INTEGER,
+    high_so INTEGER,
+    era_plus REAL,
+    fip_minus REAL,
+    career_gs INTEGER,
+    career_w INTEGER,
+    career_l INTEGER,
+    career_sv INTEGER,
+    career_ipouts INTEGER,
+    career_h INTEGER,
+    career_er INTEGER,
+    career_hr INTEGER,
+    career_bb INTEGER,
+    career_so INTEGER
 )
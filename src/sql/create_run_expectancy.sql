@@ -0,0 +1,10 @@
+CREATE TABLE run_expectancy (
+    season INTEGER NOT NULL,
+    bases_cd INTEGER NOT NULL,
+    outs INTEGER NOT NULL,
+    runs INTEGER,
+    times INTEGER,
+    scoreless INTEGER,
+    expected_runs REAL,
+    PRIMARY KEY (season, bases_cd, outs)
+)
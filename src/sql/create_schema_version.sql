@@ -0,0 +1,3 @@
+CREATE TABLE schema_version (
+    version INTEGER NOT NULL
+)
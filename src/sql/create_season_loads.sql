@@ -0,0 +1,8 @@
+CREATE TABLE season_loads (
+    season INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    source_hash TEXT NOT NULL,
+    options TEXT NOT NULL,
+    loaded_at TEXT NOT NULL,
+    PRIMARY KEY (season, source)
+)
@@ -0,0 +1,8 @@
+CREATE TABLE teams (
+    season INTEGER NOT NULL,
+    team_id TEXT NOT NULL,
+    league TEXT,
+    city TEXT,
+    nickname TEXT,
+    PRIMARY KEY (season, team_id)
+)